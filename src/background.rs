@@ -0,0 +1,96 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use log::warn;
+
+/// A thread that runs a task at a fixed interval until it is dropped.
+/// Dropping it waits for the running task to finish.
+pub struct PeriodicTask {
+    name: &'static str,
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PeriodicTask {
+    pub fn start<F>(name: &'static str, interval: Duration, mut task: F) -> Self
+    where
+        F: FnMut() -> Result<(), anyhow::Error> + Send + 'static,
+    {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let handle = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name(name.to_string())
+                .spawn(move || {
+                    let (stopped, condvar) = &*stop;
+                    let mut stopped = stopped.lock().unwrap();
+                    loop {
+                        stopped = condvar.wait_timeout(stopped, interval).unwrap().0;
+                        if *stopped {
+                            break;
+                        }
+                        // Errors are retried at the next interval
+                        if let Err(e) = task() {
+                            warn!("{} failed: {}", name, e);
+                        }
+                    }
+                })
+                .expect("failed to spawn a background thread")
+        };
+        PeriodicTask {
+            name,
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for PeriodicTask {
+    fn drop(&mut self) {
+        let (stopped, condvar) = &*self.stop;
+        *stopped.lock().unwrap() = true;
+        condvar.notify_all();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("{} panicked", self.name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_periodic_task() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let task = {
+            let count = count.clone();
+            PeriodicTask::start("counter", Duration::from_millis(10), move || {
+                count.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+        };
+        let timestamp = Instant::now();
+        while count.load(Ordering::SeqCst) < 3 {
+            assert!(timestamp.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // Dropping the task does not wait for the next interval
+        let task_with_long_interval =
+            PeriodicTask::start("idle", Duration::from_secs(60), || Ok(()));
+        let timestamp = Instant::now();
+        drop(task);
+        drop(task_with_long_interval);
+        assert!(timestamp.elapsed() < Duration::from_secs(5));
+        let final_count = count.load(Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(count.load(Ordering::SeqCst), final_count);
+    }
+}
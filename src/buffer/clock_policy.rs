@@ -0,0 +1,43 @@
+use super::replacement_policy::ReplacementPolicyControl;
+
+pub struct ClockPolicy {
+    // Whether each buffer was pinned since the hand last passed it
+    referenced: Vec<bool>,
+    hand: usize,
+}
+
+impl ClockPolicy {
+    pub fn new(num_buffers: usize) -> Self {
+        ClockPolicy {
+            referenced: vec![false; num_buffers],
+            hand: 0,
+        }
+    }
+}
+
+impl ReplacementPolicyControl for ClockPolicy {
+    fn record_pin(&mut self, buffer_index: usize) {
+        self.referenced[buffer_index] = true;
+    }
+
+    fn record_assignment(&mut self, _buffer_index: usize) {}
+
+    fn choose_victim(&mut self, is_unpinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        let num_buffers = self.referenced.len();
+        // The first round may clear every bit, so the second round finds an
+        // unpinned buffer if there is one.
+        for _ in 0..2 * num_buffers {
+            let buffer_index = self.hand;
+            self.hand = (self.hand + 1) % num_buffers;
+            if !is_unpinned(buffer_index) {
+                continue;
+            }
+            if self.referenced[buffer_index] {
+                self.referenced[buffer_index] = false;
+            } else {
+                return Some(buffer_index);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+
+use super::replacement_policy::ReplacementPolicyControl;
+
+pub struct LruKPolicy {
+    k: usize,
+    // Times of the last k pins of the block in each buffer, oldest first
+    history: Vec<VecDeque<u64>>,
+    clock: u64,
+}
+
+impl LruKPolicy {
+    pub fn new(num_buffers: usize, k: usize) -> Self {
+        assert!(k > 0, "LRU-K needs k > 0");
+        LruKPolicy {
+            k,
+            history: vec![VecDeque::with_capacity(k); num_buffers],
+            clock: 0,
+        }
+    }
+
+    // Buffers with fewer than k pins come first, and ties are broken by the
+    // last pin as in LRU.
+    fn eviction_key(&self, buffer_index: usize) -> (bool, u64, u64) {
+        let history = &self.history[buffer_index];
+        let last_pinned = history.back().copied().unwrap_or(0);
+        if history.len() < self.k {
+            (false, 0, last_pinned)
+        } else {
+            (true, history[0], last_pinned)
+        }
+    }
+}
+
+impl ReplacementPolicyControl for LruKPolicy {
+    fn record_pin(&mut self, buffer_index: usize) {
+        self.clock += 1;
+        let history = &mut self.history[buffer_index];
+        if history.len() == self.k {
+            history.pop_front();
+        }
+        history.push_back(self.clock);
+    }
+
+    fn record_assignment(&mut self, buffer_index: usize) {
+        self.history[buffer_index].clear();
+    }
+
+    fn choose_victim(&mut self, is_unpinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        (0..self.history.len())
+            .filter(|&i| is_unpinned(i))
+            .min_by_key(|&i| self.eviction_key(i))
+    }
+}
@@ -0,0 +1,31 @@
+use super::replacement_policy::ReplacementPolicyControl;
+
+pub struct LruPolicy {
+    // Time of the last pin of each buffer, 0 if never pinned
+    last_pinned: Vec<u64>,
+    clock: u64,
+}
+
+impl LruPolicy {
+    pub fn new(num_buffers: usize) -> Self {
+        LruPolicy {
+            last_pinned: vec![0; num_buffers],
+            clock: 0,
+        }
+    }
+}
+
+impl ReplacementPolicyControl for LruPolicy {
+    fn record_pin(&mut self, buffer_index: usize) {
+        self.clock += 1;
+        self.last_pinned[buffer_index] = self.clock;
+    }
+
+    fn record_assignment(&mut self, _buffer_index: usize) {}
+
+    fn choose_victim(&mut self, is_unpinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        (0..self.last_pinned.len())
+            .filter(|&i| is_unpinned(i))
+            .min_by_key(|&i| self.last_pinned[i])
+    }
+}
@@ -0,0 +1,725 @@
+use log::warn;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::errors::TransactionError;
+use crate::file::{BlockId, FileId, FileManager};
+use crate::log::manager::LogManager;
+use crate::page::Page;
+
+pub mod clock_policy;
+pub mod lru_k_policy;
+pub mod lru_policy;
+pub mod naive_policy;
+pub mod replacement_policy;
+
+use replacement_policy::{ReplacementPolicy, ReplacementPolicyControl, ReplacementPolicyKind};
+
+const PIN_TIME_LIMIT_IN_MILLIS: u128 = 5_000;
+
+/// Bytes at the start of each block read through the buffer pool, holding
+/// the log sequence number of the last logged update of the block.
+pub const PAGE_HEADER_SIZE: usize = 8;
+
+pub struct Buffer {
+    file_manager: Arc<Mutex<FileManager>>,
+    log_manager: Arc<Mutex<LogManager>>,
+    pub page: Page,
+    pub block: Option<BlockId>,
+    modifying_transaction_id: Option<usize>,
+    log_sequence_number: usize,
+    // LSN of the first logged update since the page was last written
+    first_log_sequence_number: Option<usize>,
+}
+
+#[derive(Error, Debug)]
+pub struct BufferAbortError {}
+
+impl std::fmt::Display for BufferAbortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BufferAbortError")
+    }
+}
+
+/// Buffer is a struct that represents a buffer in the buffer pool.
+/// Each page in the buffer pool has information about which block it is assigned to.
+/// Whether it is pinned is tracked by the BufferManager.
+impl Buffer {
+    fn new(file_manager: Arc<Mutex<FileManager>>, log_manager: Arc<Mutex<LogManager>>) -> Self {
+        let block_size = file_manager.lock().unwrap().block_size;
+        Buffer {
+            file_manager,
+            log_manager,
+            page: Page::new(block_size),
+            block: None,
+            modifying_transaction_id: None,
+            log_sequence_number: 0,
+            first_log_sequence_number: None,
+        }
+    }
+
+    /// Marks the buffer as modified by the transaction. A positive
+    /// `log_sequence_number` is the log record of the update, which becomes
+    /// the page LSN; unlogged updates pass 0.
+    pub fn set_modified(&mut self, transaction_id: usize, log_sequence_number: usize) {
+        self.modifying_transaction_id = Some(transaction_id);
+        if log_sequence_number > 0 {
+            self.log_sequence_number = self.log_sequence_number.max(log_sequence_number);
+            self.first_log_sequence_number
+                .get_or_insert(log_sequence_number);
+            self.set_page_lsn(log_sequence_number);
+        }
+    }
+
+    /// Returns whether an update was logged since the page was last written.
+    pub fn has_logged_updates(&self) -> bool {
+        self.first_log_sequence_number.is_some()
+    }
+
+    pub fn get_page_lsn(&self) -> usize {
+        self.page.get_u64(0) as usize
+    }
+
+    pub fn set_page_lsn(&mut self, log_sequence_number: usize) {
+        self.page.set_u64(0, log_sequence_number as u64);
+    }
+
+    // Writes the page if modified and reads the block into it. The buffer
+    // keeps its block if the page is not written, and holds no block if the
+    // new one is not read.
+    fn assign_to_block(&mut self, block: &BlockId) -> Result<(), std::io::Error> {
+        self.flush()?;
+        self.block = None;
+        self.file_manager
+            .lock()
+            .unwrap()
+            .read(block, &mut self.page)?;
+        self.block = Some(block.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        // flush log and page if transaction_id is set
+        if self.modifying_transaction_id.is_some() {
+            let mut log_manager = self.log_manager.lock().unwrap();
+            log_manager.flush(self.log_sequence_number)?;
+            let mut file_manager = self.file_manager.lock().unwrap();
+            if let Some(block) = &self.block {
+                file_manager.write(block, &self.page)?;
+            }
+            self.modifying_transaction_id = None;
+            self.first_log_sequence_number = None;
+        }
+        Ok(())
+    }
+}
+
+/// Counts of pins since the buffer manager was created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    /// Pins of blocks already in the buffer pool
+    pub hits: u64,
+    /// Pins that read the block into a buffer
+    pub misses: u64,
+    /// Blocks replaced by another block, whether pinned or read ahead
+    pub evictions: u64,
+    /// Blocks read ahead of a sequential scan
+    pub prefetches: u64,
+    /// Blocks read ahead and replaced before they were pinned
+    pub unused_prefetches: u64,
+}
+
+// State shared by all buffers, guarded by one lock that is held only to
+// pin and unpin, not while blocks are read or written.
+struct BufferPoolState {
+    // Buffer index of each block in the buffer pool, including the blocks
+    // being replaced and read
+    page_table: HashMap<BlockId, usize>,
+    // Block of each buffer, as in the buffer itself
+    blocks: Vec<Option<BlockId>>,
+    // Whether each buffer is being assigned a new block, so that neither
+    // block can be pinned
+    loading: Vec<bool>,
+    num_pins: Vec<usize>,
+    // Whether each buffer holds a block read ahead and not pinned since
+    prefetched: Vec<bool>,
+    num_availables: usize,
+    replacement_policy: ReplacementPolicy,
+    stats: BufferStats,
+    // Slot of the block last pinned in each file, to detect sequential scans
+    last_pinned_slots: HashMap<FileId, usize>,
+}
+
+struct ReadAhead {
+    window: usize,
+    sender: Sender<BlockId>,
+}
+
+/// BufferManager pins blocks to buffers. Each buffer has its own lock, so
+/// transactions reading and writing different pinned buffers do not block
+/// each other.
+pub struct BufferManager {
+    file_manager: Arc<Mutex<FileManager>>,
+    buffers: Vec<Mutex<Buffer>>,
+    state: Mutex<BufferPoolState>,
+    condvar: Condvar,
+    read_ahead: Mutex<Option<ReadAhead>>,
+}
+
+impl BufferManager {
+    pub fn new(
+        file_manager: Arc<Mutex<FileManager>>,
+        log_manager: Arc<Mutex<LogManager>>,
+        num_buffers: usize,
+    ) -> Self {
+        Self::with_replacement_policy(
+            file_manager,
+            log_manager,
+            num_buffers,
+            ReplacementPolicyKind::default(),
+        )
+    }
+
+    pub fn with_replacement_policy(
+        file_manager: Arc<Mutex<FileManager>>,
+        log_manager: Arc<Mutex<LogManager>>,
+        num_buffers: usize,
+        replacement_policy: ReplacementPolicyKind,
+    ) -> Self {
+        let mut buffers = Vec::new();
+        for _ in 0..num_buffers {
+            buffers.push(Mutex::new(Buffer::new(
+                file_manager.clone(),
+                log_manager.clone(),
+            )));
+        }
+
+        BufferManager {
+            file_manager,
+            buffers,
+            state: Mutex::new(BufferPoolState {
+                page_table: HashMap::new(),
+                blocks: vec![None; num_buffers],
+                loading: vec![false; num_buffers],
+                num_pins: vec![0; num_buffers],
+                prefetched: vec![false; num_buffers],
+                num_availables: num_buffers,
+                replacement_policy: ReplacementPolicy::new(replacement_policy, num_buffers),
+                stats: BufferStats::default(),
+                last_pinned_slots: HashMap::new(),
+            }),
+            condvar: Condvar::new(),
+            read_ahead: Mutex::new(None),
+        }
+    }
+
+    /// Starts a thread that reads up to `window` blocks ahead of sequential
+    /// scans. The thread stops when the buffer manager is dropped.
+    pub fn start_read_ahead(self: &Arc<Self>, window: usize) {
+        let (sender, receiver) = channel::<BlockId>();
+        let buffer_manager = Arc::downgrade(self);
+        std::thread::spawn(move || {
+            for block in receiver {
+                let Some(buffer_manager) = buffer_manager.upgrade() else {
+                    break;
+                };
+                if let Err(e) = buffer_manager.prefetch(&block) {
+                    warn!("Failed to read ahead {:?}: {}", block, e);
+                }
+            }
+        });
+        *self.read_ahead.lock().unwrap() = Some(ReadAhead { window, sender });
+    }
+
+    /// Asks the read-ahead thread, if any, to read the blocks following
+    /// `block` in its file.
+    pub fn read_ahead(&self, block: &BlockId) {
+        if let Some(read_ahead) = self.read_ahead.lock().unwrap().as_ref() {
+            let mut next_block = block.clone();
+            for _ in 0..read_ahead.window {
+                next_block = next_block.get_next_block();
+                // The thread has stopped only if the buffer manager is dropped
+                read_ahead.sender.send(next_block.clone()).ok();
+            }
+        }
+    }
+
+    pub fn get_num_availables(&self) -> usize {
+        self.state.lock().unwrap().num_availables
+    }
+
+    pub fn get_stats(&self) -> BufferStats {
+        self.state.lock().unwrap().stats
+    }
+
+    // The buffer must be pinned while it is locked, so that it is not
+    // assigned to another block.
+    pub fn lock_buffer(&self, buffer_index: usize) -> MutexGuard<'_, Buffer> {
+        self.buffers[buffer_index].lock().unwrap()
+    }
+
+    pub fn unpin(&self, buffer_index: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.num_pins[buffer_index] -= 1;
+        if state.num_pins[buffer_index] == 0 {
+            state.num_availables += 1;
+            self.condvar.notify_all();
+        }
+    }
+
+    pub fn pin(&self, block: &BlockId) -> Result<usize, TransactionError> {
+        let timestamp = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        while timestamp.elapsed().as_millis() < PIN_TIME_LIMIT_IN_MILLIS {
+            let buffer_index = match state.page_table.get(block) {
+                Some(&buffer_index) if !state.loading[buffer_index] => {
+                    state.stats.hits += 1;
+                    state.prefetched[buffer_index] = false;
+                    Some(buffer_index)
+                }
+                // The block is being read, or written to replace it
+                Some(_) => None,
+                None => match self.start_loading(&mut state, block, true) {
+                    Some((buffer_index, evicted_block)) => {
+                        drop(state);
+                        let (result, held_block) = self.load(buffer_index, block);
+                        state = self.state.lock().unwrap();
+                        self.finish_loading(
+                            &mut state,
+                            buffer_index,
+                            block,
+                            evicted_block,
+                            held_block,
+                        );
+                        result?;
+                        state.stats.misses += 1;
+                        Some(buffer_index)
+                    }
+                    None => None,
+                },
+            };
+
+            if let Some(buffer_index) = buffer_index {
+                if state.num_pins[buffer_index] == 0 {
+                    state.num_availables -= 1;
+                }
+                state.num_pins[buffer_index] += 1;
+                state.replacement_policy.record_pin(buffer_index);
+                let previous_slot = state
+                    .last_pinned_slots
+                    .insert(block.file_id, block.block_slot);
+                drop(state);
+                if previous_slot.is_some_and(|slot| slot + 1 == block.block_slot) {
+                    self.read_ahead(block);
+                }
+                return Ok(buffer_index);
+            }
+            state = self
+                .condvar
+                .wait_timeout(
+                    state,
+                    Duration::new((PIN_TIME_LIMIT_IN_MILLIS / 1000) as u64, 0),
+                )
+                .unwrap()
+                .0;
+        }
+        Err(TransactionError::BufferAbortError)
+    }
+
+    // Chooses an unpinned buffer for the block with the replacement policy
+    // and marks it as loading, so that neither its block nor the new one is
+    // pinned until `finish_loading`. Returns the buffer and its block.
+    // Blocks read ahead are replaced only if `replace_prefetched`.
+    fn start_loading(
+        &self,
+        state: &mut BufferPoolState,
+        block: &BlockId,
+        replace_prefetched: bool,
+    ) -> Option<(usize, Option<BlockId>)> {
+        let num_pins = &state.num_pins;
+        let loading = &state.loading;
+        let prefetched = &state.prefetched;
+        let buffer_index = state.replacement_policy.choose_victim(&|i| {
+            num_pins[i] == 0 && !loading[i] && (replace_prefetched || !prefetched[i])
+        })?;
+
+        state.loading[buffer_index] = true;
+        state.page_table.insert(block.clone(), buffer_index);
+        if state.prefetched[buffer_index] {
+            state.prefetched[buffer_index] = false;
+            state.stats.unused_prefetches += 1;
+        }
+        Some((buffer_index, state.blocks[buffer_index].clone()))
+    }
+
+    // Replaces the block of the buffer, without the pool state locked.
+    // Returns the block the buffer holds afterwards too.
+    fn load(
+        &self,
+        buffer_index: usize,
+        block: &BlockId,
+    ) -> (Result<(), std::io::Error>, Option<BlockId>) {
+        let mut buffer = self.lock_buffer(buffer_index);
+        let result = buffer.assign_to_block(block);
+        (result, buffer.block.clone())
+    }
+
+    // Maps the blocks to the buffer as it is after loading, whether or not
+    // it succeeded, and wakes up the pins waiting for it.
+    fn finish_loading(
+        &self,
+        state: &mut BufferPoolState,
+        buffer_index: usize,
+        block: &BlockId,
+        evicted_block: Option<BlockId>,
+        held_block: Option<BlockId>,
+    ) {
+        if let Some(evicted_block) = evicted_block {
+            if held_block.as_ref() != Some(&evicted_block) {
+                state.page_table.remove(&evicted_block);
+                state.stats.evictions += 1;
+            }
+        }
+        if held_block.as_ref() == Some(block) {
+            state.replacement_policy.record_assignment(buffer_index);
+        } else {
+            state.page_table.remove(block);
+        }
+        state.blocks[buffer_index] = held_block;
+        state.loading[buffer_index] = false;
+        self.condvar.notify_all();
+    }
+
+    // Reads the block into the buffer pool without pinning it, unless it is
+    // already there, it is past the end of the file, or every other unpinned
+    // buffer holds a block read ahead.
+    fn prefetch(&self, block: &BlockId) -> Result<(), std::io::Error> {
+        let num_blocks = self
+            .file_manager
+            .lock()
+            .unwrap()
+            .get_num_blocks(&block.file_name())?;
+        if block.block_slot >= num_blocks {
+            return Ok(());
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if state.page_table.contains_key(block) {
+            return Ok(());
+        }
+        let Some((buffer_index, evicted_block)) = self.start_loading(&mut state, block, false)
+        else {
+            return Ok(());
+        };
+        drop(state);
+        let (result, held_block) = self.load(buffer_index, block);
+        let mut state = self.state.lock().unwrap();
+        self.finish_loading(&mut state, buffer_index, block, evicted_block, held_block);
+        result?;
+
+        state.prefetched[buffer_index] = true;
+        state.stats.prefetches += 1;
+        // Count reading ahead as a use, so that the block is not the next one
+        // to be replaced
+        state.replacement_policy.record_pin(buffer_index);
+        Ok(())
+    }
+
+    /// Writes up to `max_buffers` modified buffers that are not pinned, and
+    /// returns how many were written. The log is flushed up to the last
+    /// update of each buffer before the buffer is written.
+    pub fn write_dirty_buffers(&self, max_buffers: usize) -> Result<usize, std::io::Error> {
+        let mut num_written = 0;
+        for (buffer_index, buffer) in self.buffers.iter().enumerate() {
+            if num_written == max_buffers {
+                break;
+            }
+            // A buffer pinned after this check is written consistently, as
+            // updates lock the buffer too.
+            if self.state.lock().unwrap().num_pins[buffer_index] > 0 {
+                continue;
+            }
+            let mut buffer = buffer.lock().unwrap();
+            if buffer.modifying_transaction_id.is_some() {
+                buffer.flush()?;
+                num_written += 1;
+            }
+        }
+        Ok(num_written)
+    }
+
+    pub fn flush_all(&self, transaction_id: usize) -> Result<(), std::io::Error> {
+        for buffer in self.buffers.iter() {
+            let mut buffer = buffer.lock().unwrap();
+            if buffer.modifying_transaction_id == Some(transaction_id) {
+                buffer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the blocks of the buffers with logged updates not written yet,
+    /// with the LSN of the first such update.
+    pub fn get_dirty_page_table(&self) -> Vec<(BlockId, usize)> {
+        let mut dirty_page_table = vec![];
+        for buffer in self.buffers.iter() {
+            let buffer = buffer.lock().unwrap();
+            if let (Some(block), Some(log_sequence_number)) =
+                (&buffer.block, buffer.first_log_sequence_number)
+            {
+                dirty_page_table.push((block.clone(), log_sequence_number));
+            }
+        }
+        dirty_page_table
+    }
+
+    /// Writes every modified buffer, pinned or not, whichever transaction
+    /// modified it.
+    pub fn flush_all_modified(&self) -> Result<(), std::io::Error> {
+        for buffer in self.buffers.iter() {
+            buffer.lock().unwrap().flush()?;
+        }
+        Ok(())
+    }
+
+    /// Forces the blocks written so far to the disk, as far as the durability
+    /// mode syncs them.
+    pub fn sync_written_blocks(&self) -> Result<(), std::io::Error> {
+        self.file_manager.lock().unwrap().sync_written_files()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_manager() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, block_size)));
+        let log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let log_manager = Arc::new(Mutex::new(log_manager));
+        let buffer_manager = BufferManager::new(file_manager.clone(), log_manager.clone(), 3);
+
+        let block0 = file_manager.lock().unwrap().append_block("log")?;
+        let block1 = file_manager.lock().unwrap().append_block("log")?;
+        let block2 = file_manager.lock().unwrap().append_block("log")?;
+
+        buffer_manager.pin(&block0)?;
+        buffer_manager.pin(&block1)?;
+        buffer_manager.pin(&block2)?;
+        assert_eq!(buffer_manager.get_num_availables(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_buffer_stats() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, block_size)));
+        let log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let log_manager = Arc::new(Mutex::new(log_manager));
+        let buffer_manager = BufferManager::with_replacement_policy(
+            file_manager.clone(),
+            log_manager.clone(),
+            2,
+            ReplacementPolicyKind::Lru,
+        );
+
+        let mut blocks = vec![];
+        for _ in 0..3 {
+            blocks.push(file_manager.lock().unwrap().append_block("test")?);
+        }
+
+        let index0 = buffer_manager.pin(&blocks[0])?;
+        let index1 = buffer_manager.pin(&blocks[1])?;
+        buffer_manager.unpin(index1);
+        buffer_manager.unpin(index0);
+        assert_eq!(buffer_manager.pin(&blocks[0])?, index0);
+        buffer_manager.unpin(index0);
+
+        // Block 1 is the least recently used one
+        assert_eq!(buffer_manager.pin(&blocks[2])?, index1);
+        assert_eq!(
+            buffer_manager.get_stats(),
+            BufferStats {
+                hits: 1,
+                misses: 3,
+                evictions: 1,
+                ..BufferStats::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pin_waits_for_unpin() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, block_size)));
+        let log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let log_manager = Arc::new(Mutex::new(log_manager));
+        let buffer_manager = Arc::new(BufferManager::new(
+            file_manager.clone(),
+            log_manager.clone(),
+            1,
+        ));
+
+        let block0 = file_manager.lock().unwrap().append_block("test")?;
+        let block1 = file_manager.lock().unwrap().append_block("test")?;
+        let buffer_index = buffer_manager.pin(&block0)?;
+        buffer_manager.lock_buffer(buffer_index).page.set_i32(0, 1);
+
+        // The other thread waits for the only buffer without blocking this one
+        let handle = {
+            let buffer_manager = buffer_manager.clone();
+            std::thread::spawn(move || buffer_manager.pin(&block1).map(|_| ()))
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(buffer_manager.lock_buffer(buffer_index).page.get_i32(0), 1);
+        buffer_manager.unpin(buffer_index);
+        handle.join().unwrap()?;
+
+        assert_eq!(buffer_manager.get_num_availables(), 0);
+        assert_eq!(
+            buffer_manager.lock_buffer(buffer_index).block,
+            Some(BlockId::new("test", 1))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_ahead() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, block_size)));
+        let log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let log_manager = Arc::new(Mutex::new(log_manager));
+        let buffer_manager = Arc::new(BufferManager::new(
+            file_manager.clone(),
+            log_manager.clone(),
+            2,
+        ));
+        buffer_manager.start_read_ahead(1);
+
+        let mut blocks = vec![];
+        for _ in 0..4 {
+            blocks.push(file_manager.lock().unwrap().append_block("test")?);
+        }
+        let other_block = file_manager.lock().unwrap().append_block("other")?;
+        let wait_for_prefetches = |num_prefetches: u64| {
+            let timestamp = Instant::now();
+            while buffer_manager.get_stats().prefetches < num_prefetches {
+                assert!(timestamp.elapsed() < Duration::from_secs(5));
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        // Pinning block 1 after block 0 reads block 2 ahead
+        let index = buffer_manager.pin(&blocks[0])?;
+        buffer_manager.unpin(index);
+        let index1 = buffer_manager.pin(&blocks[1])?;
+        wait_for_prefetches(1);
+        buffer_manager.unpin(index1);
+        let index2 = buffer_manager.pin(&blocks[2])?;
+        assert_eq!(buffer_manager.get_stats().hits, 1);
+
+        // Block 3 is read ahead and replaced before it is pinned
+        wait_for_prefetches(2);
+        buffer_manager.unpin(index2);
+        buffer_manager.pin(&other_block)?;
+        assert_eq!(
+            buffer_manager.get_stats(),
+            BufferStats {
+                hits: 1,
+                misses: 3,
+                evictions: 3,
+                prefetches: 2,
+                unused_prefetches: 1,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_replacement() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, block_size)));
+        let faults = file_manager.lock().unwrap().get_faults();
+        let log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let log_manager = Arc::new(Mutex::new(log_manager));
+        let buffer_manager = BufferManager::new(file_manager.clone(), log_manager.clone(), 1);
+
+        let block0 = file_manager.lock().unwrap().append_block("test")?;
+        let block1 = file_manager.lock().unwrap().append_block("test")?;
+        let buffer_index = buffer_manager.pin(&block0)?;
+        {
+            let mut buffer = buffer_manager.lock_buffer(buffer_index);
+            buffer.page.set_i32(0, 10);
+            buffer.set_modified(1, 0);
+        }
+        buffer_manager.unpin(buffer_index);
+
+        // The modified block stays in the buffer if it cannot be written
+        faults.lock().unwrap().fail_io(1);
+        assert!(buffer_manager.pin(&block1).is_err());
+        let buffer_index = buffer_manager.pin(&block0)?;
+        assert_eq!(buffer_manager.lock_buffer(buffer_index).page.get_i32(0), 10);
+        buffer_manager.unpin(buffer_index);
+        buffer_manager.flush_all(1)?;
+
+        // The buffer holds no block if the new one cannot be read
+        faults.lock().unwrap().fail_io(1);
+        assert!(buffer_manager.pin(&block1).is_err());
+        assert_eq!(buffer_manager.lock_buffer(buffer_index).block, None);
+        assert_eq!(buffer_manager.get_num_availables(), 1);
+        let buffer_index = buffer_manager.pin(&block0)?;
+        assert_eq!(buffer_manager.lock_buffer(buffer_index).page.get_i32(0), 10);
+        buffer_manager.unpin(buffer_index);
+        buffer_manager.pin(&block1)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_dirty_buffers() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, block_size)));
+        let log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let log_manager = Arc::new(Mutex::new(log_manager));
+        let buffer_manager = BufferManager::new(file_manager.clone(), log_manager.clone(), 2);
+
+        let block0 = file_manager.lock().unwrap().append_block("test")?;
+        let block1 = file_manager.lock().unwrap().append_block("test")?;
+        let index0 = buffer_manager.pin(&block0)?;
+        let index1 = buffer_manager.pin(&block1)?;
+        for (buffer_index, value) in [(index0, 10), (index1, 11)] {
+            let mut buffer = buffer_manager.lock_buffer(buffer_index);
+            buffer.page.set_i32(0, value);
+            buffer.set_modified(1, 0);
+        }
+
+        // Pinned buffers are not written
+        buffer_manager.unpin(index0);
+        assert_eq!(buffer_manager.write_dirty_buffers(8)?, 1);
+        assert_eq!(buffer_manager.write_dirty_buffers(8)?, 0);
+        let mut page = Page::new(block_size);
+        file_manager.lock().unwrap().read(&block0, &mut page)?;
+        assert_eq!(page.get_i32(0), 10);
+        file_manager.lock().unwrap().read(&block1, &mut page)?;
+        assert_eq!(page.get_i32(0), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use super::replacement_policy::ReplacementPolicyControl;
+
+pub struct NaivePolicy {
+    num_buffers: usize,
+}
+
+impl NaivePolicy {
+    pub fn new(num_buffers: usize) -> Self {
+        NaivePolicy { num_buffers }
+    }
+}
+
+impl ReplacementPolicyControl for NaivePolicy {
+    fn record_pin(&mut self, _buffer_index: usize) {}
+
+    fn record_assignment(&mut self, _buffer_index: usize) {}
+
+    fn choose_victim(&mut self, is_unpinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        (0..self.num_buffers).find(|&i| is_unpinned(i))
+    }
+}
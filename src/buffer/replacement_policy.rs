@@ -0,0 +1,105 @@
+use enum_dispatch::enum_dispatch;
+
+use super::{
+    clock_policy::ClockPolicy, lru_k_policy::LruKPolicy, lru_policy::LruPolicy,
+    naive_policy::NaivePolicy,
+};
+
+/// Policy to choose which buffer is replaced when a block that is not in the
+/// buffer pool is pinned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplacementPolicyKind {
+    /// The first unpinned buffer
+    #[default]
+    Naive,
+    /// The least recently pinned buffer
+    Lru,
+    /// The first unpinned buffer not pinned since the clock hand last passed it
+    Clock,
+    /// The buffer whose K-th most recent pin is the oldest. Buffers pinned
+    /// fewer than K times are replaced first, in LRU order.
+    LruK(usize),
+}
+
+#[enum_dispatch]
+pub enum ReplacementPolicy {
+    Naive(NaivePolicy),
+    Lru(LruPolicy),
+    Clock(ClockPolicy),
+    LruK(LruKPolicy),
+}
+
+// Buffers are identified by their index in the buffer pool.
+#[enum_dispatch(ReplacementPolicy)]
+pub trait ReplacementPolicyControl {
+    /// Called each time the buffer is pinned, after it is assigned a block if
+    /// needed.
+    fn record_pin(&mut self, buffer_index: usize);
+
+    /// Called when the buffer is assigned a new block, before it is pinned.
+    fn record_assignment(&mut self, buffer_index: usize);
+
+    /// Returns a buffer for which `is_unpinned` holds, or None if every
+    /// buffer is pinned.
+    fn choose_victim(&mut self, is_unpinned: &dyn Fn(usize) -> bool) -> Option<usize>;
+}
+
+impl ReplacementPolicy {
+    pub fn new(kind: ReplacementPolicyKind, num_buffers: usize) -> Self {
+        match kind {
+            ReplacementPolicyKind::Naive => NaivePolicy::new(num_buffers).into(),
+            ReplacementPolicyKind::Lru => LruPolicy::new(num_buffers).into(),
+            ReplacementPolicyKind::Clock => ClockPolicy::new(num_buffers).into(),
+            ReplacementPolicyKind::LruK(k) => LruKPolicy::new(num_buffers, k).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin_all(policy: &mut ReplacementPolicy, buffer_indexes: &[usize]) {
+        for &buffer_index in buffer_indexes {
+            policy.record_pin(buffer_index);
+        }
+    }
+
+    #[test]
+    fn test_replacement_policies() {
+        let unpinned = |buffer_index: usize| buffer_index != 0;
+
+        let mut naive = ReplacementPolicy::new(ReplacementPolicyKind::Naive, 3);
+        pin_all(&mut naive, &[0, 1, 2]);
+        assert_eq!(naive.choose_victim(&unpinned), Some(1));
+
+        let mut lru = ReplacementPolicy::new(ReplacementPolicyKind::Lru, 3);
+        pin_all(&mut lru, &[0, 2, 1, 2]);
+        assert_eq!(lru.choose_victim(&unpinned), Some(1));
+        lru.record_pin(1);
+        assert_eq!(lru.choose_victim(&unpinned), Some(2));
+
+        // The hand clears the bits of buffers 1 and 2 and comes back to 1
+        let mut clock = ReplacementPolicy::new(ReplacementPolicyKind::Clock, 3);
+        pin_all(&mut clock, &[0, 1, 2]);
+        assert_eq!(clock.choose_victim(&unpinned), Some(1));
+        clock.record_assignment(1);
+        clock.record_pin(1);
+        assert_eq!(clock.choose_victim(&unpinned), Some(2));
+
+        // Buffer 2 is pinned more recently but only once
+        let mut lru_k = ReplacementPolicy::new(ReplacementPolicyKind::LruK(2), 3);
+        pin_all(&mut lru_k, &[0, 1, 1, 2]);
+        assert_eq!(lru_k.choose_victim(&unpinned), Some(2));
+        lru_k.record_pin(2);
+        assert_eq!(lru_k.choose_victim(&unpinned), Some(1));
+        // A new block does not inherit the history of the buffer
+        lru_k.record_assignment(2);
+        lru_k.record_pin(2);
+        assert_eq!(lru_k.choose_victim(&unpinned), Some(2));
+
+        assert_eq!(lru.choose_victim(&|_| false), None);
+        assert_eq!(clock.choose_victim(&|_| false), None);
+        assert_eq!(lru_k.choose_victim(&|_| false), None);
+    }
+}
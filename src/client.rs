@@ -9,7 +9,7 @@ use rustyline::{error::ReadlineError, DefaultEditor};
 use simpledb_rs::{
     driver::{
         embedded::EmbeddedDriver, network::driver::NetworkDriver, ConnectionControl, Driver,
-        DriverControl, MetadataControl, ResultSetControl, Statement, StatementControl,
+        DriverControl, MetadataControl, ResultSet, ResultSetControl, Statement, StatementControl,
     },
     record::field::Type,
 };
@@ -69,7 +69,24 @@ fn do_query<W: Write>(
     command: &str,
     writer: &mut W,
 ) -> Result<(), anyhow::Error> {
-    let mut result_set = statement.execute_query(command)?;
+    let result_set = statement.execute_query(command)?;
+    print_result_set(result_set, writer)
+}
+
+// Print an update command's RETURNING records like the result of a query
+fn do_update_returning<W: Write>(
+    statement: &mut Statement,
+    command: &str,
+    writer: &mut W,
+) -> Result<(), anyhow::Error> {
+    let result_set = statement.execute_update_returning(command)?;
+    print_result_set(result_set, writer)
+}
+
+fn print_result_set<W: Write>(
+    mut result_set: ResultSet,
+    writer: &mut W,
+) -> Result<(), anyhow::Error> {
     let metadata = result_set.get_metadata()?;
     let num_columns = metadata.get_column_count()?;
     let mut total_width = 0;
@@ -320,6 +337,8 @@ fn run_client<W: Write, E: ClientEditor>(
             do_show_tables(&mut statement, writer)
         } else if upper.starts_with("SELECT") {
             do_query(&mut statement, trimmed, writer)
+        } else if upper.split_whitespace().any(|word| word == "RETURNING") {
+            do_update_returning(&mut statement, trimmed, writer)
         } else {
             do_update(&mut statement, trimmed, writer)
         };
@@ -24,21 +24,20 @@ fn main() -> Result<(), anyhow::Error> {
         "(8, 'pat', 20, 2019)",
         "(9, 'lee', 10, 2021)",
     ];
-    for val in studvals.iter() {
-        stmt.execute_update(&format!(
-            "insert into STUDENT(SId, SName, MajorId, GradYear) values {}",
-            val
-        ))?;
-    }
+    stmt.execute_update(&format!(
+        "insert into STUDENT(SId, SName, MajorId, GradYear) values {}",
+        studvals.join(", ")
+    ))?;
     println!("STUDENT records inserted.");
 
     // DEPT table
     stmt.execute_update("create table DEPT(DId I32, DName VARCHAR(8))")?;
     println!("Table DEPT created.");
     let deptvals = ["(10, 'compsci')", "(20, 'math')", "(30, 'drama')"];
-    for val in deptvals.iter() {
-        stmt.execute_update(&format!("insert into DEPT(DId, DName) values {}", val))?;
-    }
+    stmt.execute_update(&format!(
+        "insert into DEPT(DId, DName) values {}",
+        deptvals.join(", ")
+    ))?;
     println!("DEPT records inserted.");
 
     // COURSE table
@@ -52,12 +51,10 @@ fn main() -> Result<(), anyhow::Error> {
         "(52, 'acting', 30)",
         "(62, 'elocution', 30)",
     ];
-    for val in coursevals.iter() {
-        stmt.execute_update(&format!(
-            "insert into COURSE(CId, Title, DeptId) values {}",
-            val
-        ))?;
-    }
+    stmt.execute_update(&format!(
+        "insert into COURSE(CId, Title, DeptId) values {}",
+        coursevals.join(", ")
+    ))?;
     println!("COURSE records inserted.");
 
     // SECTION table
@@ -72,12 +69,10 @@ fn main() -> Result<(), anyhow::Error> {
         "(43, 32, 'einstein', 2017)",
         "(53, 62, 'brando', 2018)",
     ];
-    for val in sectvals.iter() {
-        stmt.execute_update(&format!(
-            "insert into SECTION(SectId, CourseId, Prof, YearOffered) values {}",
-            val
-        ))?;
-    }
+    stmt.execute_update(&format!(
+        "insert into SECTION(SectId, CourseId, Prof, YearOffered) values {}",
+        sectvals.join(", ")
+    ))?;
     println!("SECTION records inserted.");
 
     // ENROLL table
@@ -93,12 +88,10 @@ fn main() -> Result<(), anyhow::Error> {
         "(54, 4, 53, 'A')",
         "(64, 6, 53, 'A')",
     ];
-    for val in enrollvals.iter() {
-        stmt.execute_update(&format!(
-            "insert into ENROLL(EId, StudentId, SectionId, Grade) values {}",
-            val
-        ))?;
-    }
+    stmt.execute_update(&format!(
+        "insert into ENROLL(EId, StudentId, SectionId, Grade) values {}",
+        enrollvals.join(", ")
+    ))?;
     println!("ENROLL records inserted.");
 
     conn.close()?;
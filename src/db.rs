@@ -1,29 +1,66 @@
 use std::{
     path::PathBuf,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use log::info;
+use log::{debug, info};
 
 use crate::{
-    buffer::BufferManager,
+    background::PeriodicTask,
+    buffer::{replacement_policy::ReplacementPolicyKind, BufferManager, BufferStats},
     errors::TransactionError,
-    file::FileManager,
-    log::manager::LogManager,
+    file::{Durability, FileManager, FORMAT_VERSION},
+    log::{
+        group_commit::GroupCommit,
+        manager::{LogManager, LogRetention, DEFAULT_LOG_SEGMENT_SIZE},
+    },
     metadata::MetadataManager,
     planner::{
         basic_query_planner::BasicQueryPlanner, index_update_planner::IndexUpdatePlanner, Planner,
     },
-    tx::{concurrency::LockTable, transaction::Transaction},
+    tx::{
+        checkpoint::write_nonquiescent_checkpoint,
+        concurrency::LockTable,
+        transaction::{next_transaction_id, Transaction},
+    },
 };
 
+// Maximum number of buffers written by the background writer at a time
+const BACKGROUND_WRITE_BATCH_SIZE: usize = 8;
+
+/// Settings of a [`SimpleDB`] other than its directory and sizes.
+#[derive(Clone, Debug, Default)]
+pub struct SimpleDBOptions {
+    pub replacement_policy: ReplacementPolicyKind,
+    /// Number of blocks read ahead of sequential scans by a background
+    /// thread, or 0 not to read ahead.
+    pub read_ahead_window: usize,
+    /// Interval at which a background thread writes modified buffers that
+    /// are not pinned, or None not to write them in the background.
+    pub background_write_interval: Option<Duration>,
+    /// Interval at which a background thread writes a non-quiescent
+    /// checkpoint, or None to checkpoint only at startup.
+    pub checkpoint_interval: Option<Duration>,
+    /// What happens to the log segments before the last checkpoint
+    pub log_retention: LogRetention,
+    /// Longest time a committing transaction waits for the other active
+    /// transactions to commit and force the log together
+    pub group_commit_delay: Duration,
+    /// When the writes to the log and data files are synced
+    pub durability: Durability,
+}
+
 pub struct SimpleDB {
     pub file_manager: Arc<Mutex<FileManager>>,
     lock_table: Arc<Mutex<LockTable>>,
-    log_manager: Arc<Mutex<LogManager>>,
-    buffer_manager: Arc<Mutex<BufferManager>>,
+    pub(crate) log_manager: Arc<Mutex<LogManager>>,
+    group_commit: Arc<GroupCommit>,
+    pub(crate) buffer_manager: Arc<BufferManager>,
     pub metadata_manager: Arc<Mutex<MetadataManager>>,
     pub planner: Arc<Mutex<Planner>>,
+    // Held only to stop the background threads when the database is dropped
+    _background_tasks: Vec<PeriodicTask>,
 }
 
 impl SimpleDB {
@@ -32,19 +69,73 @@ impl SimpleDB {
         block_size: usize,
         num_buffers: usize,
     ) -> Result<SimpleDB, TransactionError> {
-        let file_manager = Arc::new(Mutex::new(FileManager::new(directory, block_size)));
+        Self::with_options(
+            directory,
+            block_size,
+            num_buffers,
+            SimpleDBOptions::default(),
+        )
+    }
+
+    pub fn with_options(
+        directory: PathBuf,
+        block_size: usize,
+        num_buffers: usize,
+        options: SimpleDBOptions,
+    ) -> Result<SimpleDB, TransactionError> {
+        if options.replacement_policy == ReplacementPolicyKind::LruK(0) {
+            return Err(TransactionError::InvalidOption(
+                "LRU-K needs k > 0".to_string(),
+            ));
+        }
+        let mut file_manager =
+            FileManager::with_durability(directory, block_size, options.durability);
+        // A directory in another format is refused rather than misread
+        if file_manager.is_new {
+            file_manager.write_format_version()?;
+        } else {
+            match file_manager.read_format_version()? {
+                Some(FORMAT_VERSION) => {}
+                Some(version) => {
+                    return Err(TransactionError::UnsupportedFormat(format!(
+                        "version {} instead of {}",
+                        version, FORMAT_VERSION
+                    )));
+                }
+                None => {
+                    return Err(TransactionError::UnsupportedFormat(
+                        "no version, as written by an older release".to_string(),
+                    ));
+                }
+            }
+        }
+        let file_manager = Arc::new(Mutex::new(file_manager));
         let lock_table = Arc::new(Mutex::new(LockTable::new(10)));
-        let log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let log_manager = LogManager::with_segments(
+            file_manager.clone(),
+            "log".into(),
+            DEFAULT_LOG_SEGMENT_SIZE,
+            options.log_retention.clone(),
+        )?;
         let log_manager = Arc::new(Mutex::new(log_manager));
-        let buffer_manager = Arc::new(Mutex::new(BufferManager::new(
+        let group_commit = Arc::new(GroupCommit::new(
+            log_manager.clone(),
+            options.group_commit_delay,
+        ));
+        let buffer_manager = Arc::new(BufferManager::with_replacement_policy(
             file_manager.clone(),
             log_manager.clone(),
             num_buffers,
-        )));
+            options.replacement_policy,
+        ));
+        if options.read_ahead_window > 0 {
+            buffer_manager.start_read_ahead(options.read_ahead_window);
+        }
 
         let tx = Arc::new(Mutex::new(Transaction::new(
             file_manager.clone(),
             log_manager.clone(),
+            group_commit.clone(),
             buffer_manager.clone(),
             lock_table.clone(),
         )?));
@@ -64,13 +155,41 @@ impl SimpleDB {
         let update_planner = Box::new(IndexUpdatePlanner::new(metadata_manager.clone()));
         let planner = Arc::new(Mutex::new(Planner::new(query_planner, update_planner)));
 
+        let mut background_tasks = vec![];
+        if let Some(interval) = options.background_write_interval {
+            let buffer_manager = buffer_manager.clone();
+            background_tasks.push(PeriodicTask::start(
+                "background writer",
+                interval,
+                move || {
+                    buffer_manager.write_dirty_buffers(BACKGROUND_WRITE_BATCH_SIZE)?;
+                    Ok(())
+                },
+            ));
+        }
+        if let Some(interval) = options.checkpoint_interval {
+            let log_manager = log_manager.clone();
+            let buffer_manager = buffer_manager.clone();
+            background_tasks.push(PeriodicTask::start("checkpointer", interval, move || {
+                write_nonquiescent_checkpoint(
+                    &log_manager,
+                    &buffer_manager,
+                    next_transaction_id(),
+                )?;
+                debug!("Wrote a checkpoint");
+                Ok(())
+            }));
+        }
+
         Ok(SimpleDB {
             file_manager,
             lock_table,
             log_manager,
+            group_commit,
             buffer_manager,
             metadata_manager,
             planner,
+            _background_tasks: background_tasks,
         })
     }
 
@@ -78,8 +197,67 @@ impl SimpleDB {
         Transaction::new(
             self.file_manager.clone(),
             self.log_manager.clone(),
+            self.group_commit.clone(),
             self.buffer_manager.clone(),
             self.lock_table.clone(),
         )
     }
+
+    pub fn get_buffer_stats(&self) -> BufferStats {
+        self.buffer_manager.get_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_lru_k() {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let options = SimpleDBOptions {
+            replacement_policy: ReplacementPolicyKind::LruK(0),
+            ..Default::default()
+        };
+        let result = SimpleDB::with_options(temp_dir, 400, 8, options);
+        assert!(matches!(result, Err(TransactionError::InvalidOption(_))));
+    }
+
+    #[test]
+    fn test_format_version() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        drop(SimpleDB::new(temp_dir.clone(), 400, 8)?);
+        drop(SimpleDB::new(temp_dir.clone(), 400, 8)?);
+
+        std::fs::write(temp_dir.join("format"), "0\n")?;
+        let result = SimpleDB::new(temp_dir.clone(), 400, 8);
+        assert!(matches!(
+            result,
+            Err(TransactionError::UnsupportedFormat(_))
+        ));
+
+        // Directories written before the version was stored have none
+        std::fs::remove_file(temp_dir.join("format"))?;
+        let result = SimpleDB::new(temp_dir, 400, 8);
+        assert!(matches!(
+            result,
+            Err(TransactionError::UnsupportedFormat(_))
+        ));
+
+        // A directory without data files is a new database, whether it was
+        // created empty or a crash left only the format in it
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("empty");
+        std::fs::create_dir(&temp_dir)?;
+        drop(SimpleDB::new(temp_dir.clone(), 400, 8)?);
+        assert!(temp_dir.join("format").exists());
+        assert!(!temp_dir.join("temp_format").exists());
+
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("format_only");
+        std::fs::create_dir(&temp_dir)?;
+        std::fs::write(temp_dir.join("temp_format"), "")?;
+        std::fs::write(temp_dir.join("format"), "")?;
+        drop(SimpleDB::new(temp_dir.clone(), 400, 8)?);
+        drop(SimpleDB::new(temp_dir, 400, 8)?);
+        Ok(())
+    }
 }
@@ -7,11 +7,15 @@ use std::{
 use crate::{
     config,
     db::SimpleDB,
-    errors::ExecutionError,
+    errors::{ExecutionError, QueryError, TransactionError},
+    parser::{
+        parameter::ParsedStatement,
+        statement::{Statement as SqlStatement, TransactionCommand},
+    },
     plan::{Plan, PlanControl},
-    planner::Planner,
+    planner::{Planner, ReturnedRecords},
     record::{
-        field::{Spec, Type},
+        field::{Spec, Type, Value},
         schema::Schema,
     },
     scan::{Scan, ScanControl},
@@ -19,8 +23,8 @@ use crate::{
 };
 
 use super::{
-    Connection, ConnectionControl, DriverControl, Metadata, MetadataControl, ResultSet,
-    ResultSetControl, Statement, StatementControl,
+    Connection, ConnectionControl, DriverControl, Metadata, MetadataControl, PreparedStatement,
+    PreparedStatementControl, ResultSet, ResultSetControl, Statement, StatementControl,
 };
 
 const MAX_VARCHAR_DISPLAY_SIZE: usize = 30;
@@ -55,7 +59,8 @@ impl MetadataControl for EmbeddedMetadata {
 
 pub struct EmbeddedResultSet {
     connection: Arc<Mutex<EmbeddedConnectionImpl>>,
-    scan: Option<Scan>,
+    // Boxed to keep `ResultSet` as small as its network variant
+    scan: Option<Box<Scan>>,
     schema: Schema,
 }
 
@@ -64,13 +69,27 @@ impl EmbeddedResultSet {
         mut plan: Plan,
         connection: Arc<Mutex<EmbeddedConnectionImpl>>,
     ) -> Result<Self, ExecutionError> {
-        let scan = Some(plan.open(connection.lock().unwrap().get_transaction())?);
+        let scan = Some(Box::new(
+            plan.open(connection.lock().unwrap().get_transaction())?,
+        ));
         Ok(EmbeddedResultSet {
             connection,
             scan,
             schema: plan.schema().clone(),
         })
     }
+
+    fn from_returned_records(
+        returned: ReturnedRecords,
+        connection: Arc<Mutex<EmbeddedConnectionImpl>>,
+    ) -> Self {
+        let schema = returned.schema().clone();
+        EmbeddedResultSet {
+            connection,
+            scan: Some(Box::new(returned.into_scan())),
+            schema,
+        }
+    }
 }
 
 impl ResultSetControl for EmbeddedResultSet {
@@ -128,7 +147,7 @@ impl ResultSetControl for EmbeddedResultSet {
 
     fn close(&mut self) -> Result<(), anyhow::Error> {
         self.scan = None;
-        Ok(self.connection.lock().unwrap().commit()?)
+        Ok(self.connection.lock().unwrap().autocommit()?)
     }
 }
 
@@ -160,16 +179,144 @@ impl StatementControl for EmbeddedStatement {
     }
 
     fn execute_update(&mut self, command: &str) -> Result<usize, anyhow::Error> {
+        let statement = self.planner.lock().unwrap().prepare(command)?;
+        Ok(execute_update_statement(
+            &self.connection,
+            &self.planner,
+            statement.statement()?,
+        )?)
+    }
+
+    fn execute_update_returning(&mut self, command: &str) -> Result<ResultSet, anyhow::Error> {
         let tx = self.connection.lock().unwrap().get_transaction();
-        let num_updated = self.planner.lock().unwrap().execute_update(command, tx)?;
-        self.connection.lock().unwrap().commit()?;
-        Ok(num_updated)
+        let returned = self
+            .planner
+            .lock()
+            .unwrap()
+            .execute_update_returning(command, tx)?;
+        // The returned records are held in memory, so the update commits now
+        self.connection.lock().unwrap().autocommit()?;
+        let result_set =
+            EmbeddedResultSet::from_returned_records(returned, self.connection.clone());
+        Ok(ResultSet::Embedded(result_set))
+    }
+}
+
+// Executes an update or transaction command for a statement or a prepared
+// statement of the connection
+fn execute_update_statement(
+    connection: &Arc<Mutex<EmbeddedConnectionImpl>>,
+    planner: &Arc<Mutex<Planner>>,
+    statement: &SqlStatement,
+) -> Result<usize, ExecutionError> {
+    match statement {
+        SqlStatement::TransactionCommand(command) => {
+            connection
+                .lock()
+                .unwrap()
+                .execute_transaction_command(command)?;
+            Ok(0)
+        }
+        SqlStatement::UpdateCommand(command) => {
+            let tx = connection.lock().unwrap().get_transaction();
+            let num_updated = planner
+                .lock()
+                .unwrap()
+                .execute_update_command(command, tx)?;
+            connection.lock().unwrap().autocommit()?;
+            Ok(num_updated)
+        }
+        SqlStatement::Query(_) => {
+            Err(QueryError::InvalidStatement("expected an update command".to_string()).into())
+        }
+    }
+}
+
+pub struct EmbeddedPreparedStatement {
+    connection: Arc<Mutex<EmbeddedConnectionImpl>>,
+    planner: Arc<Mutex<Planner>>,
+    statement: Arc<ParsedStatement>,
+    parameters: Vec<Option<Value>>, // None until a value is set
+}
+
+impl EmbeddedPreparedStatement {
+    fn new(
+        connection: Arc<Mutex<EmbeddedConnectionImpl>>,
+        sql: &str,
+    ) -> Result<Self, ExecutionError> {
+        let planner = connection.lock().unwrap().db.planner.clone();
+        let statement = planner.lock().unwrap().prepare(sql)?;
+        let parameters = vec![None; statement.num_parameters()];
+        Ok(Self {
+            connection,
+            planner,
+            statement,
+            parameters,
+        })
+    }
+
+    fn set_value(&mut self, index: usize, value: Value) -> Result<(), QueryError> {
+        let num_parameters = self.parameters.len();
+        match index
+            .checked_sub(1)
+            .and_then(|i| self.parameters.get_mut(i))
+        {
+            Some(parameter) => {
+                *parameter = Some(value);
+                Ok(())
+            }
+            None => Err(QueryError::InvalidParameter(format!(
+                "index {} is out of range 1..={}",
+                index, num_parameters
+            ))),
+        }
+    }
+}
+
+impl PreparedStatementControl for EmbeddedPreparedStatement {
+    fn set_i32(&mut self, index: usize, value: i32) -> Result<(), anyhow::Error> {
+        Ok(self.set_value(index, Value::I32(value))?)
+    }
+
+    fn set_string(&mut self, index: usize, value: &str) -> Result<(), anyhow::Error> {
+        Ok(self.set_value(index, Value::String(value.to_string()))?)
+    }
+
+    fn set_null(&mut self, index: usize) -> Result<(), anyhow::Error> {
+        Ok(self.set_value(index, Value::Null)?)
+    }
+
+    fn execute_query(&mut self) -> Result<ResultSet, anyhow::Error> {
+        let SqlStatement::Query(query) = self.statement.bind(&self.parameters)? else {
+            return Err(QueryError::InvalidStatement("expected a query".to_string()).into());
+        };
+        let tx = self.connection.lock().unwrap().get_transaction();
+        let plan = self.planner.lock().unwrap().create_plan(&query, tx)?;
+        let result_set = EmbeddedResultSet::new(plan, self.connection.clone())?;
+        Ok(ResultSet::Embedded(result_set))
+    }
+
+    fn execute_update(&mut self) -> Result<usize, anyhow::Error> {
+        let statement = self.statement.bind(&self.parameters)?;
+        Ok(execute_update_statement(
+            &self.connection,
+            &self.planner,
+            &statement,
+        )?)
+    }
+
+    // The statement holds no resources beyond itself
+    fn close(&mut self) -> Result<(), anyhow::Error> {
+        Ok(())
     }
 }
 
 pub struct EmbeddedConnectionImpl {
     db: SimpleDB,
     current_tx: Arc<Mutex<Transaction>>,
+    autocommit: bool,
+    // Whether BEGIN started a transaction that has not ended yet
+    in_transaction_block: bool,
 }
 
 impl EmbeddedConnectionImpl {
@@ -196,6 +343,7 @@ impl EmbeddedConnectionImpl {
         self.current_tx.lock().unwrap().commit()?;
         let new_tx = Arc::new(Mutex::new(self.db.new_transaction()?));
         self.current_tx = new_tx;
+        self.in_transaction_block = false;
         self.print_file_access_stats();
         Ok(())
     }
@@ -204,10 +352,63 @@ impl EmbeddedConnectionImpl {
         self.current_tx.lock().unwrap().rollback()?;
         let new_tx = Arc::new(Mutex::new(self.db.new_transaction()?));
         self.current_tx = new_tx;
+        self.in_transaction_block = false;
         self.print_file_access_stats();
         Ok(())
     }
 
+    // Commits the work of a finished statement unless a transaction is open
+    fn autocommit(&mut self) -> Result<(), ExecutionError> {
+        if self.autocommit && !self.in_transaction_block {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn set_autocommit(&mut self, autocommit: bool) -> Result<(), ExecutionError> {
+        if autocommit && !self.autocommit {
+            self.commit()?;
+        }
+        self.autocommit = autocommit;
+        Ok(())
+    }
+
+    // Savepoints would be lost by the commit after each statement
+    fn check_transaction_block(&self, command: &str) -> Result<(), TransactionError> {
+        if self.autocommit && !self.in_transaction_block {
+            return Err(TransactionError::NoTransactionBlock(command.to_string()));
+        }
+        Ok(())
+    }
+
+    fn execute_transaction_command(
+        &mut self,
+        command: &TransactionCommand,
+    ) -> Result<(), ExecutionError> {
+        match command {
+            // Without autocommit a transaction is always open
+            TransactionCommand::Begin => self.in_transaction_block = true,
+            TransactionCommand::Commit => self.commit()?,
+            TransactionCommand::Rollback => self.rollback()?,
+            TransactionCommand::Savepoint(name) => {
+                self.check_transaction_block("SAVEPOINT")?;
+                self.current_tx.lock().unwrap().savepoint(name)?;
+            }
+            TransactionCommand::RollbackTo(name) => {
+                self.check_transaction_block("ROLLBACK TO")?;
+                self.current_tx
+                    .lock()
+                    .unwrap()
+                    .rollback_to_savepoint(name)?;
+            }
+            TransactionCommand::Release(name) => {
+                self.check_transaction_block("RELEASE")?;
+                self.current_tx.lock().unwrap().release_savepoint(name)?;
+            }
+        }
+        Ok(())
+    }
+
     fn get_transaction(&self) -> Arc<Mutex<Transaction>> {
         self.current_tx.clone()
     }
@@ -224,7 +425,12 @@ impl EmbeddedConnection {
             file_manager.file_access_stats.reset();
         }
         let current_tx = Arc::new(Mutex::new(db.new_transaction()?));
-        let connection = EmbeddedConnectionImpl { db, current_tx };
+        let connection = EmbeddedConnectionImpl {
+            db,
+            current_tx,
+            autocommit: true,
+            in_transaction_block: false,
+        };
         Ok(Self {
             connection: Arc::new(Mutex::new(connection)),
         })
@@ -238,6 +444,13 @@ impl ConnectionControl for EmbeddedConnection {
         )?))
     }
 
+    fn prepare_statement(&self, sql: &str) -> Result<PreparedStatement, anyhow::Error> {
+        Ok(PreparedStatement::Embedded(EmbeddedPreparedStatement::new(
+            self.connection.clone(),
+            sql,
+        )?))
+    }
+
     fn close(&mut self) -> Result<(), anyhow::Error> {
         Ok(self.connection.lock().unwrap().close()?)
     }
@@ -249,6 +462,14 @@ impl ConnectionControl for EmbeddedConnection {
     fn rollback(&mut self) -> Result<(), anyhow::Error> {
         Ok(self.connection.lock().unwrap().rollback()?)
     }
+
+    fn set_autocommit(&mut self, autocommit: bool) -> Result<(), anyhow::Error> {
+        Ok(self.connection.lock().unwrap().set_autocommit(autocommit)?)
+    }
+
+    fn get_autocommit(&self) -> Result<bool, anyhow::Error> {
+        Ok(self.connection.lock().unwrap().autocommit)
+    }
 }
 
 // TODO: Locate these constants in the appropriate module
@@ -339,4 +560,181 @@ mod tests {
         assert_eq!(result_set.get_string("B")?, None);
         Ok(())
     }
+
+    #[test]
+    fn test_embedded_driver_expression_metadata() -> Result<(), anyhow::Error> {
+        let temp_dir = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("dir_metadata");
+        let db_name = temp_dir.to_string_lossy();
+
+        let driver = EmbeddedDriver::new();
+        let (_db_name, connection) = driver.connect(&db_name)?;
+
+        let mut statement = connection.create_statement()?;
+        statement.execute_update("create table test (A I32, B VARCHAR(20))")?;
+        statement.execute_update("insert into test (A, B) values (1, 'a')")?;
+
+        let mut result_set =
+            statement.execute_query("select upper(B) as U, length(B) as L from test")?;
+        let metadata = result_set.get_metadata()?;
+        let column_type = |name: &str| -> Result<Type, anyhow::Error> {
+            let index = (0..metadata.get_column_count()?)
+                .find(|i| metadata.get_column_name(*i).unwrap() == name)
+                .unwrap();
+            metadata.get_column_type(index)
+        };
+        assert!(matches!(column_type("U")?, Type::String));
+        assert!(matches!(column_type("L")?, Type::I32));
+
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_string("U")?, Some("A".to_string()));
+        assert_eq!(result_set.get_i32("L")?, Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_embedded_driver_update_returning() -> Result<(), anyhow::Error> {
+        let temp_dir = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("dir_returning");
+        let db_name = temp_dir.to_string_lossy();
+
+        let driver = EmbeddedDriver::new();
+        let (_db_name, connection) = driver.connect(&db_name)?;
+
+        let mut statement = connection.create_statement()?;
+        statement.execute_update("create table test (A I32, B VARCHAR(20))")?;
+        let mut result_set = statement.execute_update_returning(
+            "insert into test (A, B) values (1, 'a'), (2, 'b') returning A, A * 10 as C",
+        )?;
+        let metadata = result_set.get_metadata()?;
+        assert_eq!(metadata.get_column_count()?, 2);
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_i32("C")?, Some(10));
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_i32("A")?, Some(2));
+        assert!(!result_set.next()?);
+        result_set.close()?;
+
+        let mut result_set =
+            statement.execute_update_returning("delete from test where A = 1 returning *")?;
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_i32("A")?, Some(1));
+        assert_eq!(result_set.get_string("B")?, Some("a".to_string()));
+        assert!(!result_set.next()?);
+        result_set.close()?;
+
+        // A command without RETURNING has no records to return
+        assert!(statement
+            .execute_update_returning("delete from test")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_embedded_driver_transaction_commands() -> Result<(), anyhow::Error> {
+        let temp_dir = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("dir_transaction");
+        let db_name = temp_dir.to_string_lossy();
+
+        let driver = EmbeddedDriver::new();
+        let (_db_name, mut connection) = driver.connect(&db_name)?;
+
+        let mut statement = connection.create_statement()?;
+        let select_all = |statement: &mut Statement| -> Result<Vec<i32>, anyhow::Error> {
+            let mut result_set = statement.execute_query("select A from test")?;
+            let mut values = vec![];
+            while result_set.next()? {
+                values.push(result_set.get_i32("A")?.unwrap());
+            }
+            result_set.close()?;
+            Ok(values)
+        };
+        statement.execute_update("create table test (A I32)")?;
+
+        // Statements between BEGIN and ROLLBACK are undone together
+        statement.execute_update("begin")?;
+        statement.execute_update("insert into test (A) values (1)")?;
+        statement.execute_update("insert into test (A) values (2)")?;
+        assert_eq!(select_all(&mut statement)?, vec![1, 2]);
+        statement.execute_update("rollback")?;
+        assert_eq!(select_all(&mut statement)?, Vec::<i32>::new());
+
+        statement.execute_update("begin")?;
+        statement.execute_update("insert into test (A) values (1)")?;
+        statement.execute_update("savepoint s")?;
+        statement.execute_update("insert into test (A) values (2)")?;
+        statement.execute_update("rollback to savepoint s")?;
+        statement.execute_update("insert into test (A) values (3)")?;
+        statement.execute_update("release s")?;
+        assert!(statement.execute_update("rollback to s").is_err());
+        statement.execute_update("commit")?;
+        assert_eq!(select_all(&mut statement)?, vec![1, 3]);
+
+        // Savepoints need a transaction that outlives the statement
+        assert!(statement.execute_update("savepoint s").is_err());
+
+        connection.set_autocommit(false)?;
+        assert!(!connection.get_autocommit()?);
+        statement.execute_update("delete from test where A = 1")?;
+        statement.execute_update("savepoint s")?;
+        connection.rollback()?;
+        statement.execute_update("delete from test where A = 3")?;
+        connection.set_autocommit(true)?;
+        assert_eq!(select_all(&mut statement)?, vec![1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_embedded_driver_prepared_statement() -> Result<(), anyhow::Error> {
+        let temp_dir = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("dir_prepared");
+        let db_name = temp_dir.to_string_lossy();
+
+        let driver = EmbeddedDriver::new();
+        let (_db_name, connection) = driver.connect(&db_name)?;
+
+        let mut statement = connection.create_statement()?;
+        statement.execute_update("create table test (A I32, B VARCHAR(20))")?;
+
+        // The same statement is executed with new values each time
+        let mut insert = connection.prepare_statement("insert into test (A, B) values (?, ?)")?;
+        for (a, b) in [(1, "it's"), (2, "b")] {
+            insert.set_i32(1, a)?;
+            insert.set_string(2, b)?;
+            assert_eq!(insert.execute_update()?, 1);
+        }
+        insert.set_i32(1, 3)?;
+        insert.set_null(2)?;
+        insert.execute_update()?;
+        assert!(insert.set_i32(0, 1).is_err());
+        assert!(insert.set_i32(3, 1).is_err());
+
+        let mut select = connection.prepare_statement("select A, B from test where A = $1")?;
+        assert!(select.execute_query().is_err());
+        select.set_i32(1, 1)?;
+        let mut result_set = select.execute_query()?;
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_string("B")?, Some("it's".to_string()));
+        assert!(!result_set.next()?);
+        result_set.close()?;
+        select.set_i32(1, 3)?;
+        let mut result_set = select.execute_query()?;
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_string("B")?, None);
+        result_set.close()?;
+
+        // A query cannot be executed as an update and vice versa
+        assert!(select.execute_update().is_err());
+        assert!(insert.execute_query().is_err());
+        assert!(connection.prepare_statement("select from").is_err());
+        Ok(())
+    }
 }
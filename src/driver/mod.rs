@@ -1,11 +1,13 @@
 use embedded::{
-    EmbeddedConnection, EmbeddedDriver, EmbeddedMetadata, EmbeddedResultSet, EmbeddedStatement,
+    EmbeddedConnection, EmbeddedDriver, EmbeddedMetadata, EmbeddedPreparedStatement,
+    EmbeddedResultSet, EmbeddedStatement,
 };
 use enum_dispatch::enum_dispatch;
 
 use network::{
     connection::NetworkConnection, driver::NetworkDriver, metadata::NetworkMetadata,
-    result_set::NetworkResultSet, statement::NetworkStatement,
+    prepared_statement::NetworkPreparedStatement, result_set::NetworkResultSet,
+    statement::NetworkStatement,
 };
 // use network::NetworkMetadata;
 
@@ -57,6 +59,27 @@ pub enum Statement {
 pub trait StatementControl {
     fn execute_query(&mut self, command: &str) -> Result<ResultSet, anyhow::Error>;
     fn execute_update(&mut self, command: &str) -> Result<usize, anyhow::Error>;
+    // Execute an INSERT, UPDATE or DELETE with a RETURNING clause
+    fn execute_update_returning(&mut self, command: &str) -> Result<ResultSet, anyhow::Error>;
+}
+
+#[enum_dispatch]
+pub enum PreparedStatement {
+    Embedded(EmbeddedPreparedStatement),
+    Network(NetworkPreparedStatement),
+}
+
+// A statement parsed once with `?` or `$1`, `$2`, ... placeholders, which
+// are numbered from 1 and hold the values set before each execution
+#[enum_dispatch(PreparedStatement)]
+pub trait PreparedStatementControl {
+    fn set_i32(&mut self, index: usize, value: i32) -> Result<(), anyhow::Error>;
+    fn set_string(&mut self, index: usize, value: &str) -> Result<(), anyhow::Error>;
+    fn set_null(&mut self, index: usize) -> Result<(), anyhow::Error>;
+    fn execute_query(&mut self) -> Result<ResultSet, anyhow::Error>;
+    fn execute_update(&mut self) -> Result<usize, anyhow::Error>;
+    // Release the statement; it cannot be executed afterwards
+    fn close(&mut self) -> Result<(), anyhow::Error>;
 }
 
 #[enum_dispatch]
@@ -68,9 +91,14 @@ pub enum Connection {
 #[enum_dispatch(Connection)]
 pub trait ConnectionControl {
     fn create_statement(&self) -> Result<Statement, anyhow::Error>;
+    fn prepare_statement(&self, sql: &str) -> Result<PreparedStatement, anyhow::Error>;
     fn close(&mut self) -> Result<(), anyhow::Error>;
     fn commit(&mut self) -> Result<(), anyhow::Error>;
     fn rollback(&mut self) -> Result<(), anyhow::Error>;
+    // Without autocommit, updates stay in one transaction until COMMIT or
+    // ROLLBACK. Turning it back on commits the pending transaction.
+    fn set_autocommit(&mut self, autocommit: bool) -> Result<(), anyhow::Error>;
+    fn get_autocommit(&self) -> Result<bool, anyhow::Error>;
 }
 
 #[enum_dispatch]
@@ -5,23 +5,29 @@ use tokio::runtime::Runtime;
 use tonic::transport::Channel;
 use tonic::{Request, Response, Status};
 
-use crate::driver::embedded::{EmbeddedConnection, EmbeddedStatement};
-use crate::driver::{ConnectionControl, Statement};
+use crate::driver::embedded::{EmbeddedConnection, EmbeddedPreparedStatement, EmbeddedStatement};
+use crate::driver::{ConnectionControl, PreparedStatement, Statement};
 use crate::proto::simpledb::connection_service_client::ConnectionServiceClient;
 use crate::proto::simpledb::connection_service_server::ConnectionService;
 use crate::proto::simpledb::{
     ConnectionCloseRequest, ConnectionCloseResponse, ConnectionCommitRequest,
     ConnectionCommitResponse, ConnectionCreateStatementRequest, ConnectionCreateStatementResponse,
-    ConnectionRollbackRequest, ConnectionRollbackResponse,
+    ConnectionGetAutocommitRequest, ConnectionGetAutocommitResponse,
+    ConnectionPrepareStatementRequest, ConnectionPrepareStatementResponse,
+    ConnectionRollbackRequest, ConnectionRollbackResponse, ConnectionSetAutocommitRequest,
+    ConnectionSetAutocommitResponse,
 };
 
 use super::driver::RemoteDriver;
+use super::prepared_statement::NetworkPreparedStatement;
 use super::statement::{NetworkStatement, RemoteStatement};
 
-// Each connection can have multiple statements.
+// Each connection can have multiple statements and prepared statements.
 pub struct RemoteConnection {
     embedded_connection_dict: Arc<Mutex<HashMap<u64, EmbeddedConnection>>>,
     pub(crate) embedded_statement_dict: Arc<Mutex<HashMap<u64, EmbeddedStatement>>>,
+    pub(crate) embedded_prepared_statement_dict:
+        Arc<Mutex<HashMap<u64, EmbeddedPreparedStatement>>>,
 }
 
 impl RemoteConnection {
@@ -29,6 +35,7 @@ impl RemoteConnection {
         Self {
             embedded_connection_dict: driver.embedded_connection_dict.clone(),
             embedded_statement_dict: Arc::new(Mutex::new(HashMap::new())),
+            embedded_prepared_statement_dict: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -71,6 +78,36 @@ impl ConnectionService for RemoteConnection {
         }))
     }
 
+    // The client accesses the prepared statement with a returned
+    // prepared_statement_id. SQL that does not parse is reported to the client.
+    async fn prepare_statement(
+        &self,
+        request: Request<ConnectionPrepareStatementRequest>,
+    ) -> Result<Response<ConnectionPrepareStatementResponse>, Status> {
+        let request = request.into_inner();
+        let embedded_prepared_statement = {
+            let lock = self.embedded_connection_dict.lock().unwrap();
+            let connection = lock
+                .get(&request.id)
+                .unwrap_or_else(|| panic!("Unknown connection_id: {}", request.id));
+            match connection.prepare_statement(&request.sql) {
+                Ok(PreparedStatement::Embedded(prepared_statement)) => prepared_statement,
+                Ok(_) => panic!("Unexpected prepared statement type"),
+                Err(e) => return Err(Status::invalid_argument(e.to_string())),
+            }
+        };
+
+        let mut prepared_statement_id = 0;
+        let mut lock = self.embedded_prepared_statement_dict.lock().unwrap();
+        while lock.contains_key(&prepared_statement_id) {
+            prepared_statement_id += 1;
+        }
+        lock.insert(prepared_statement_id, embedded_prepared_statement);
+        Ok(Response::new(ConnectionPrepareStatementResponse {
+            prepared_statement_id,
+        }))
+    }
+
     async fn close(
         &self,
         request: Request<ConnectionCloseRequest>,
@@ -111,6 +148,34 @@ impl ConnectionService for RemoteConnection {
         connection.rollback().unwrap();
         Ok(Response::new(ConnectionRollbackResponse {}))
     }
+
+    async fn set_autocommit(
+        &self,
+        request: Request<ConnectionSetAutocommitRequest>,
+    ) -> Result<Response<ConnectionSetAutocommitResponse>, Status> {
+        let request = request.into_inner();
+        let mut lock = self.embedded_connection_dict.lock().unwrap();
+        let connection = lock
+            .get_mut(&request.id)
+            .unwrap_or_else(|| panic!("Unknown connection_id: {}", request.id));
+        connection.set_autocommit(request.autocommit).unwrap();
+        Ok(Response::new(ConnectionSetAutocommitResponse {}))
+    }
+
+    async fn get_autocommit(
+        &self,
+        request: Request<ConnectionGetAutocommitRequest>,
+    ) -> Result<Response<ConnectionGetAutocommitResponse>, Status> {
+        let connection_id = request.into_inner().id;
+        let lock = self.embedded_connection_dict.lock().unwrap();
+        let connection = lock
+            .get(&connection_id)
+            .unwrap_or_else(|| panic!("Unknown connection_id: {}", connection_id));
+        let autocommit = connection.get_autocommit().unwrap();
+        Ok(Response::new(ConnectionGetAutocommitResponse {
+            autocommit,
+        }))
+    }
 }
 
 #[derive(Clone)]
@@ -158,6 +223,24 @@ impl ConnectionControl for NetworkConnection {
         )))
     }
 
+    fn prepare_statement(&self, sql: &str) -> Result<PreparedStatement, anyhow::Error> {
+        let mut client = ConnectionServiceClient::new(self.get_channel());
+        let request = Request::new(ConnectionPrepareStatementRequest {
+            id: self.connection_id,
+            sql: sql.to_string(),
+        });
+        let response = self
+            .runtime
+            .block_on(client.prepare_statement(request))?
+            .into_inner();
+
+        Ok(PreparedStatement::Network(NetworkPreparedStatement::new(
+            self.clone(),
+            response.prepared_statement_id,
+            self.runtime.clone(),
+        )))
+    }
+
     fn close(&mut self) -> Result<(), anyhow::Error> {
         let mut client = ConnectionServiceClient::new(self.get_channel());
         let request = Request::new(ConnectionCloseRequest {
@@ -184,4 +267,26 @@ impl ConnectionControl for NetworkConnection {
         self.runtime.block_on(client.rollback(request))?;
         Ok(())
     }
+
+    fn set_autocommit(&mut self, autocommit: bool) -> Result<(), anyhow::Error> {
+        let mut client = ConnectionServiceClient::new(self.get_channel());
+        let request = Request::new(ConnectionSetAutocommitRequest {
+            id: self.connection_id,
+            autocommit,
+        });
+        self.runtime.block_on(client.set_autocommit(request))?;
+        Ok(())
+    }
+
+    fn get_autocommit(&self) -> Result<bool, anyhow::Error> {
+        let mut client = ConnectionServiceClient::new(self.get_channel());
+        let request = Request::new(ConnectionGetAutocommitRequest {
+            id: self.connection_id,
+        });
+        let response = self
+            .runtime
+            .block_on(client.get_autocommit(request))?
+            .into_inner();
+        Ok(response.autocommit)
+    }
 }
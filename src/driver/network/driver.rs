@@ -117,10 +117,14 @@ impl DriverControl for NetworkDriver {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::driver::{ConnectionControl, MetadataControl, ResultSetControl, StatementControl};
+    use crate::driver::{
+        ConnectionControl, MetadataControl, PreparedStatementControl, ResultSetControl,
+        StatementControl,
+    };
     use crate::proto::simpledb::{
         connection_service_server::ConnectionServiceServer,
         driver_service_server::DriverServiceServer, metadata_service_server::MetadataServiceServer,
+        prepared_statement_service_server::PreparedStatementServiceServer,
         result_set_service_server::ResultSetServiceServer,
         statement_service_server::StatementServiceServer,
     };
@@ -147,6 +151,8 @@ mod tests {
                     let remote_driver = RemoteDriver::new();
                     let remote_connection = remote_driver.create_remote_connection();
                     let remote_statement = remote_connection.create_remote_statement();
+                    let remote_prepared_statement =
+                        remote_statement.create_remote_prepared_statement();
                     let remote_result_set = remote_statement.create_remote_result_set();
                     let remote_metadata = remote_result_set.create_remote_metadata();
 
@@ -154,6 +160,9 @@ mod tests {
                         .add_service(DriverServiceServer::new(remote_driver))
                         .add_service(ConnectionServiceServer::new(remote_connection))
                         .add_service(StatementServiceServer::new(remote_statement))
+                        .add_service(PreparedStatementServiceServer::new(
+                            remote_prepared_statement,
+                        ))
                         .add_service(ResultSetServiceServer::new(remote_result_set))
                         .add_service(MetadataServiceServer::new(remote_metadata))
                         .serve_with_shutdown(addr, async {
@@ -169,7 +178,7 @@ mod tests {
         thread::sleep(Duration::from_millis(100));
 
         let driver = NetworkDriver::new("127.0.0.1", 50051);
-        let (_db_name, connection) = driver.connect("sample")?;
+        let (_db_name, mut connection) = driver.connect("sample")?;
 
         let mut statement = connection.create_statement()?;
         statement.execute_update("create table test (A I32, B VARCHAR(20))")?;
@@ -207,6 +216,46 @@ mod tests {
         assert!(!result_set.previous()?);
         result_set.close()?;
 
+        let mut result_set = statement
+            .execute_update_returning("update test set A = A + 10 where A = 3 returning A, B")?;
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_i32("A")?, Some(13));
+        assert_eq!(result_set.get_string("B")?, Some("b".to_string()));
+        assert!(!result_set.next()?);
+        result_set.close()?;
+
+        connection.set_autocommit(false)?;
+        assert!(!connection.get_autocommit()?);
+        statement.execute_update("delete from test")?;
+        connection.rollback()?;
+        connection.set_autocommit(true)?;
+        let mut result_set = statement.execute_query("select A from test")?;
+        let mut num_records = 0;
+        while result_set.next()? {
+            num_records += 1;
+        }
+        assert_eq!(num_records, 3);
+        result_set.close()?;
+
+        let mut prepared = connection.prepare_statement("update test set B = ? where A = ?")?;
+        prepared.set_string(1, "it's")?;
+        prepared.set_i32(2, 1)?;
+        assert_eq!(prepared.execute_update()?, 1);
+        assert!(prepared.set_null(3).is_err());
+        // A closed statement is removed from the server
+        prepared.close()?;
+        assert!(prepared.execute_update().is_err());
+        assert!(prepared.close().is_err());
+        let mut prepared = connection.prepare_statement("select B from test where A = $1")?;
+        prepared.set_i32(1, 1)?;
+        let mut result_set = prepared.execute_query()?;
+        assert!(result_set.next()?);
+        assert_eq!(result_set.get_string("B")?, Some("it's".to_string()));
+        assert!(!result_set.next()?);
+        result_set.close()?;
+        prepared.close()?;
+        assert!(connection.prepare_statement("select from").is_err());
+
         // Drop the connection instead of explicitly closing to avoid
         // blocking on the server side during the test.
         drop(connection);
@@ -1,5 +1,6 @@
 pub mod connection;
 pub mod driver;
 pub mod metadata;
+pub mod prepared_statement;
 pub mod result_set;
 pub mod statement;
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::runtime::Runtime;
+use tonic::{Request, Response, Status};
+
+use crate::driver::embedded::{EmbeddedPreparedStatement, EmbeddedResultSet};
+use crate::driver::{PreparedStatementControl, ResultSet};
+use crate::proto::simpledb::prepared_statement_service_client::PreparedStatementServiceClient;
+use crate::proto::simpledb::prepared_statement_service_server::PreparedStatementService;
+use crate::proto::simpledb::{
+    PreparedStatementCloseRequest, PreparedStatementCloseResponse,
+    PreparedStatementExecuteQueryRequest, PreparedStatementExecuteQueryResponse,
+    PreparedStatementExecuteUpdateRequest, PreparedStatementExecuteUpdateResponse,
+    PreparedStatementSetI32Request, PreparedStatementSetI32Response,
+    PreparedStatementSetNullRequest, PreparedStatementSetNullResponse,
+    PreparedStatementSetStringRequest, PreparedStatementSetStringResponse,
+};
+
+use super::connection::NetworkConnection;
+use super::result_set::NetworkResultSet;
+use super::statement::{register_result_set, RemoteStatement};
+
+// Result sets of prepared statements are served by the ResultSetService of
+// the statements.
+pub struct RemotePreparedStatement {
+    embedded_prepared_statement_dict: Arc<Mutex<HashMap<u64, EmbeddedPreparedStatement>>>,
+    embedded_result_set_dict: Arc<Mutex<HashMap<u64, EmbeddedResultSet>>>,
+}
+
+impl RemotePreparedStatement {
+    pub fn new(statement: &RemoteStatement) -> Self {
+        Self {
+            embedded_prepared_statement_dict: statement.embedded_prepared_statement_dict.clone(),
+            embedded_result_set_dict: statement.embedded_result_set_dict.clone(),
+        }
+    }
+
+    fn with_prepared_statement<T>(
+        &self,
+        prepared_statement_id: u64,
+        f: impl FnOnce(&mut EmbeddedPreparedStatement) -> Result<T, anyhow::Error>,
+    ) -> Result<T, anyhow::Error> {
+        let mut lock = self.embedded_prepared_statement_dict.lock().unwrap();
+        let prepared_statement = lock.get_mut(&prepared_statement_id).ok_or_else(|| {
+            anyhow::anyhow!("Unknown prepared_statement_id: {}", prepared_statement_id)
+        })?;
+        f(prepared_statement)
+    }
+}
+
+// Errors of binding and executing are reported to the client
+fn invalid_argument(e: anyhow::Error) -> Status {
+    Status::invalid_argument(e.to_string())
+}
+
+#[tonic::async_trait]
+impl PreparedStatementService for RemotePreparedStatement {
+    async fn set_i32(
+        &self,
+        request: Request<PreparedStatementSetI32Request>,
+    ) -> Result<Response<PreparedStatementSetI32Response>, Status> {
+        let request = request.into_inner();
+        self.with_prepared_statement(request.id, |prepared_statement| {
+            prepared_statement.set_i32(request.index as usize, request.value)
+        })
+        .map_err(invalid_argument)?;
+        Ok(Response::new(PreparedStatementSetI32Response {}))
+    }
+
+    async fn set_string(
+        &self,
+        request: Request<PreparedStatementSetStringRequest>,
+    ) -> Result<Response<PreparedStatementSetStringResponse>, Status> {
+        let request = request.into_inner();
+        self.with_prepared_statement(request.id, |prepared_statement| {
+            prepared_statement.set_string(request.index as usize, &request.value)
+        })
+        .map_err(invalid_argument)?;
+        Ok(Response::new(PreparedStatementSetStringResponse {}))
+    }
+
+    async fn set_null(
+        &self,
+        request: Request<PreparedStatementSetNullRequest>,
+    ) -> Result<Response<PreparedStatementSetNullResponse>, Status> {
+        let request = request.into_inner();
+        self.with_prepared_statement(request.id, |prepared_statement| {
+            prepared_statement.set_null(request.index as usize)
+        })
+        .map_err(invalid_argument)?;
+        Ok(Response::new(PreparedStatementSetNullResponse {}))
+    }
+
+    async fn execute_query(
+        &self,
+        request: Request<PreparedStatementExecuteQueryRequest>,
+    ) -> Result<Response<PreparedStatementExecuteQueryResponse>, Status> {
+        let request = request.into_inner();
+        let result_set = self
+            .with_prepared_statement(request.id, |prepared_statement| {
+                prepared_statement.execute_query()
+            })
+            .map_err(invalid_argument)?;
+
+        let result_set_id = register_result_set(&self.embedded_result_set_dict, result_set);
+        Ok(Response::new(PreparedStatementExecuteQueryResponse {
+            result_set_id,
+        }))
+    }
+
+    async fn execute_update(
+        &self,
+        request: Request<PreparedStatementExecuteUpdateRequest>,
+    ) -> Result<Response<PreparedStatementExecuteUpdateResponse>, Status> {
+        let request = request.into_inner();
+        let num_updated_rows = self
+            .with_prepared_statement(request.id, |prepared_statement| {
+                prepared_statement.execute_update()
+            })
+            .map_err(invalid_argument)? as u64;
+        Ok(Response::new(PreparedStatementExecuteUpdateResponse {
+            num_updated_rows,
+        }))
+    }
+    async fn close(
+        &self,
+        request: Request<PreparedStatementCloseRequest>,
+    ) -> Result<Response<PreparedStatementCloseResponse>, Status> {
+        let request = request.into_inner();
+        let mut prepared_statement = {
+            let mut lock = self.embedded_prepared_statement_dict.lock().unwrap();
+            lock.remove(&request.id).ok_or_else(|| {
+                Status::invalid_argument(format!("Unknown prepared_statement_id: {}", request.id))
+            })?
+        };
+        prepared_statement.close().map_err(invalid_argument)?;
+        Ok(Response::new(PreparedStatementCloseResponse {}))
+    }
+}
+
+pub struct NetworkPreparedStatement {
+    connection: NetworkConnection,
+    prepared_statement_id: u64,
+    run_time: Arc<Runtime>,
+}
+
+impl NetworkPreparedStatement {
+    pub fn new(
+        connection: NetworkConnection,
+        prepared_statement_id: u64,
+        run_time: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            connection,
+            prepared_statement_id,
+            run_time,
+        }
+    }
+
+    fn client(&self) -> PreparedStatementServiceClient<tonic::transport::Channel> {
+        PreparedStatementServiceClient::new(self.connection.get_channel())
+    }
+}
+
+impl PreparedStatementControl for NetworkPreparedStatement {
+    fn set_i32(&mut self, index: usize, value: i32) -> Result<(), anyhow::Error> {
+        let request = Request::new(PreparedStatementSetI32Request {
+            id: self.prepared_statement_id,
+            index: index as u64,
+            value,
+        });
+        self.run_time.block_on(self.client().set_i32(request))?;
+        Ok(())
+    }
+
+    fn set_string(&mut self, index: usize, value: &str) -> Result<(), anyhow::Error> {
+        let request = Request::new(PreparedStatementSetStringRequest {
+            id: self.prepared_statement_id,
+            index: index as u64,
+            value: value.to_string(),
+        });
+        self.run_time.block_on(self.client().set_string(request))?;
+        Ok(())
+    }
+
+    fn set_null(&mut self, index: usize) -> Result<(), anyhow::Error> {
+        let request = Request::new(PreparedStatementSetNullRequest {
+            id: self.prepared_statement_id,
+            index: index as u64,
+        });
+        self.run_time.block_on(self.client().set_null(request))?;
+        Ok(())
+    }
+
+    fn execute_query(&mut self) -> Result<ResultSet, anyhow::Error> {
+        let request = Request::new(PreparedStatementExecuteQueryRequest {
+            id: self.prepared_statement_id,
+        });
+        let response = self
+            .run_time
+            .block_on(self.client().execute_query(request))?
+            .into_inner();
+
+        Ok(ResultSet::NetworkResultSet(NetworkResultSet::new(
+            self.connection.clone(),
+            response.result_set_id,
+            self.run_time.clone(),
+        )))
+    }
+
+    fn execute_update(&mut self) -> Result<usize, anyhow::Error> {
+        let request = Request::new(PreparedStatementExecuteUpdateRequest {
+            id: self.prepared_statement_id,
+        });
+        let response = self
+            .run_time
+            .block_on(self.client().execute_update(request))?
+            .into_inner();
+
+        Ok(response.num_updated_rows as usize)
+    }
+    fn close(&mut self) -> Result<(), anyhow::Error> {
+        let request = Request::new(PreparedStatementCloseRequest {
+            id: self.prepared_statement_id,
+        });
+        self.run_time.block_on(self.client().close(request))?;
+        Ok(())
+    }
+}
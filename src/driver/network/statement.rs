@@ -4,22 +4,26 @@ use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
 use tonic::{Request, Response, Status};
 
-use crate::driver::embedded::{EmbeddedResultSet, EmbeddedStatement};
+use crate::driver::embedded::{EmbeddedPreparedStatement, EmbeddedResultSet, EmbeddedStatement};
 use crate::driver::{ResultSet, StatementControl};
 use crate::proto::simpledb::statement_service_client::StatementServiceClient;
 use crate::proto::simpledb::statement_service_server::StatementService;
 use crate::proto::simpledb::{
     StatementExecuteQueryRequest, StatementExecuteQueryResponse, StatementExecuteUpdateRequest,
-    StatementExecuteUpdateResponse,
+    StatementExecuteUpdateResponse, StatementExecuteUpdateReturningRequest,
+    StatementExecuteUpdateReturningResponse,
 };
 
 use super::connection::{NetworkConnection, RemoteConnection};
+use super::prepared_statement::RemotePreparedStatement;
 use super::result_set::{NetworkResultSet, RemoteResultSet};
 
-// Each embedded statement can have multiple result sets.
+// Each embedded statement and prepared statement can have multiple result sets.
 pub struct RemoteStatement {
     pub(crate) embedded_result_set_dict: Arc<Mutex<HashMap<u64, EmbeddedResultSet>>>,
     embedded_statement_dict: Arc<Mutex<HashMap<u64, EmbeddedStatement>>>,
+    pub(crate) embedded_prepared_statement_dict:
+        Arc<Mutex<HashMap<u64, EmbeddedPreparedStatement>>>,
 }
 
 impl RemoteStatement {
@@ -27,12 +31,38 @@ impl RemoteStatement {
         Self {
             embedded_result_set_dict: Arc::new(Mutex::new(HashMap::new())),
             embedded_statement_dict: connection.embedded_statement_dict.clone(),
+            embedded_prepared_statement_dict: connection.embedded_prepared_statement_dict.clone(),
         }
     }
 
     pub fn create_remote_result_set(&self) -> RemoteResultSet {
         RemoteResultSet::new(self)
     }
+
+    pub fn create_remote_prepared_statement(&self) -> RemotePreparedStatement {
+        RemotePreparedStatement::new(self)
+    }
+
+    fn register_result_set(&self, result_set: ResultSet) -> u64 {
+        register_result_set(&self.embedded_result_set_dict, result_set)
+    }
+}
+
+// Keep a result set for the ResultSetService under a new ID
+pub(crate) fn register_result_set(
+    embedded_result_set_dict: &Mutex<HashMap<u64, EmbeddedResultSet>>,
+    result_set: ResultSet,
+) -> u64 {
+    let ResultSet::Embedded(result_set) = result_set else {
+        panic!("Unexpected result set type");
+    };
+    let mut lock = embedded_result_set_dict.lock().unwrap();
+    let mut result_set_id = 0;
+    while lock.contains_key(&result_set_id) {
+        result_set_id += 1;
+    }
+    lock.insert(result_set_id, result_set);
+    result_set_id
 }
 
 #[tonic::async_trait]
@@ -43,26 +73,15 @@ impl StatementService for RemoteStatement {
     ) -> Result<Response<StatementExecuteQueryResponse>, Status> {
         let request = request.into_inner();
         let statement_id = request.id;
-        let embedded_result_set = {
+        let result_set = {
             let mut lock = self.embedded_statement_dict.lock().unwrap();
             let embedded_statement = lock
                 .get_mut(&statement_id)
                 .expect(&format!("Unknown statement_id: {}", statement_id));
-
-            let result_set = embedded_statement.execute_query(&request.command).unwrap();
-            if let ResultSet::Embedded(result_set) = result_set {
-                result_set
-            } else {
-                panic!("Unexpected result set type");
-            }
+            embedded_statement.execute_query(&request.command).unwrap()
         };
 
-        let mut lock = self.embedded_result_set_dict.lock().unwrap();
-        let mut result_set_id = 0;
-        while lock.contains_key(&result_set_id) {
-            result_set_id += 1;
-        }
-        lock.insert(result_set_id, embedded_result_set);
+        let result_set_id = self.register_result_set(result_set);
         Ok(Response::new(StatementExecuteQueryResponse {
             result_set_id,
         }))
@@ -83,6 +102,27 @@ impl StatementService for RemoteStatement {
             num_updated_rows,
         }))
     }
+    async fn execute_update_returning(
+        &self,
+        request: Request<StatementExecuteUpdateReturningRequest>,
+    ) -> Result<Response<StatementExecuteUpdateReturningResponse>, Status> {
+        let request = request.into_inner();
+        let statement_id = request.id;
+        let result_set = {
+            let mut lock = self.embedded_statement_dict.lock().unwrap();
+            let embedded_statement = lock
+                .get_mut(&statement_id)
+                .unwrap_or_else(|| panic!("Unknown statement_id: {}", statement_id));
+            embedded_statement
+                .execute_update_returning(&request.command)
+                .unwrap()
+        };
+
+        let result_set_id = self.register_result_set(result_set);
+        Ok(Response::new(StatementExecuteUpdateReturningResponse {
+            result_set_id,
+        }))
+    }
 }
 
 pub struct NetworkStatement {
@@ -135,4 +175,22 @@ impl StatementControl for NetworkStatement {
 
         Ok(response.num_updated_rows as usize)
     }
+    fn execute_update_returning(&mut self, sql: &str) -> Result<ResultSet, anyhow::Error> {
+        let mut client = StatementServiceClient::new(self.connection.get_channel());
+        let request = Request::new(StatementExecuteUpdateReturningRequest {
+            id: self.statement_id,
+            command: sql.to_string(),
+        });
+        let response: StatementExecuteUpdateReturningResponse = self
+            .run_time
+            .block_on(client.execute_update_returning(request))
+            .unwrap()
+            .into_inner();
+
+        Ok(ResultSet::NetworkResultSet(NetworkResultSet::new(
+            self.connection.clone(),
+            response.result_set_id,
+            self.run_time.clone(),
+        )))
+    }
 }
@@ -15,6 +15,27 @@ pub enum TransactionError {
     #[error("Table {0} already exists")]
     TableAlreadyExists(String),
 
+    #[error("Recursive query {0} did not finish within {1} iterations")]
+    RecursionLimitExceeded(String, usize),
+
+    #[error("Cannot cast {0}")]
+    InvalidCast(String),
+
+    #[error("Savepoint {0} does not exist")]
+    UnknownSavepoint(String),
+
+    #[error("{0} can only be used in a transaction block")]
+    NoTransactionBlock(String),
+
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+
+    #[error("Invalid update: {0}")]
+    InvalidUpdate(String),
+
+    #[error("Unsupported database format: {0}")]
+    UnsupportedFormat(String),
+
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
 }
@@ -25,6 +46,24 @@ pub enum QueryError {
     ParseError(ParseError<usize, String, String>),
     #[error("Invalid table: {0}")]
     InvalidTable(String),
+    #[error("Invalid ORDER BY: {0}")]
+    InvalidOrderBy(String),
+    #[error("Incompatible set operation: {0}")]
+    IncompatibleSetOperation(String),
+    #[error("Invalid common table {0}: {1}")]
+    InvalidCommonTable(String, String),
+    #[error("Invalid window function: {0}")]
+    InvalidWindow(String),
+    #[error("Invalid expression: {0}")]
+    InvalidExpression(String),
+    #[error("Invalid insert: {0}")]
+    InvalidInsert(String),
+    #[error("Invalid RETURNING clause: {0}")]
+    InvalidReturning(String),
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("Invalid statement: {0}")]
+    InvalidStatement(String),
 }
 
 impl From<ParseError<usize, Token<'_>, &str>> for QueryError {
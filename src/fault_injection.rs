@@ -0,0 +1,476 @@
+//! Storage faults injected below the [`FileManager`] in tests, to check that
+//! recovery copes with crashes.
+//!
+//! [`FileManager`]: crate::file::FileManager
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{remove_file, File, OpenOptions},
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Tracks what the file manager writes but does not sync, so that a crash
+/// can undo it as a power loss would. It can also tear the last write of a
+/// crash, keeping only some of its bytes, and fail an I/O with an error.
+#[derive(Default)]
+pub struct FaultInjector {
+    // Length of each file written since it was last synced and the previous
+    // contents of the ranges written, oldest first
+    unsynced_files: HashMap<String, (u64, Vec<(u64, Vec<u8>)>)>,
+    // Files created since the directory was last synced
+    created_files: HashSet<String>,
+    // Last write not synced yet and the bytes it wrote
+    last_write: Option<(String, u64, Vec<u8>)>,
+    // Range of the bytes of the last write that a crash keeps
+    torn_write: Option<(usize, usize)>,
+    num_ios: usize,
+    failing_io: Option<usize>,
+}
+
+impl FaultInjector {
+    /// Makes the `n`th I/O from now fail, counting from 1.
+    pub fn fail_io(&mut self, n: usize) {
+        self.failing_io = Some(self.num_ios + n);
+    }
+
+    /// Makes a crash keep the bytes from `start` to `end` of the last write
+    /// not synced, which is otherwise undone like the others.
+    pub fn tear_last_write(&mut self, start: usize, end: usize) {
+        self.torn_write = Some((start, end));
+    }
+
+    /// Returns how many reads, writes and syncs were issued.
+    pub fn get_num_ios(&self) -> usize {
+        self.num_ios
+    }
+
+    /// Counts an I/O and fails it if it is the one to fail.
+    pub fn before_io(&mut self) -> Result<()> {
+        self.num_ios += 1;
+        if self.failing_io == Some(self.num_ios) {
+            return Err(Error::new(ErrorKind::Other, "injected I/O failure"));
+        }
+        Ok(())
+    }
+
+    /// Remembers the contents of the file that the bytes written at the
+    /// offset overwrite.
+    pub fn before_write(
+        &mut self,
+        file_name: &str,
+        file: &mut File,
+        offset: u64,
+        bytes: &[u8],
+    ) -> Result<()> {
+        self.before_io()?;
+        let file_length = file.metadata()?.len();
+        let (synced_length, previous_contents) = self
+            .unsynced_files
+            .entry(file_name.to_string())
+            .or_insert((file_length, vec![]));
+        // The bytes after the synced length are cut off by a crash
+        if offset < *synced_length {
+            let length = bytes.len().min((*synced_length - offset) as usize);
+            let mut previous_bytes = vec![0; length];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut previous_bytes)?;
+            previous_contents.push((offset, previous_bytes));
+        }
+        self.last_write = Some((file_name.to_string(), offset, bytes.to_vec()));
+        Ok(())
+    }
+
+    pub fn file_created(&mut self, file_name: &str) {
+        self.created_files.insert(file_name.to_string());
+    }
+
+    pub fn file_synced(&mut self, file_name: &str) {
+        self.unsynced_files.remove(file_name);
+        if self
+            .last_write
+            .as_ref()
+            .is_some_and(|(name, ..)| name == file_name)
+        {
+            self.last_write = None;
+        }
+    }
+
+    pub fn directory_synced(&mut self) {
+        self.created_files.clear();
+    }
+
+    pub fn file_removed(&mut self, file_name: &str) {
+        self.file_synced(file_name);
+        self.created_files.remove(file_name);
+    }
+
+    /// Undoes what was not synced in the directory, as a power loss would.
+    /// The files must not be written through handles opened before.
+    pub fn crash(&mut self, directory: &Path) -> Result<()> {
+        let Self {
+            unsynced_files,
+            created_files,
+            last_write,
+            torn_write,
+            ..
+        } = std::mem::take(self);
+
+        for file_name in created_files.iter() {
+            let path = directory.join(file_name);
+            if path.exists() {
+                remove_file(path)?;
+            }
+        }
+        for (file_name, (synced_length, previous_contents)) in unsynced_files {
+            if created_files.contains(&file_name) {
+                continue;
+            }
+            let mut file = OpenOptions::new()
+                .write(true)
+                .open(directory.join(&file_name))?;
+            for (offset, bytes) in previous_contents.iter().rev() {
+                file.seek(SeekFrom::Start(*offset))?;
+                file.write_all(bytes)?;
+            }
+            file.set_len(synced_length)?;
+        }
+
+        if let (Some((file_name, offset, bytes)), Some((start, end))) = (last_write, torn_write) {
+            let path = directory.join(&file_name);
+            if created_files.contains(&file_name) || !path.exists() {
+                return Ok(());
+            }
+            let mut file = OpenOptions::new().write(true).open(path)?;
+            // Only a write over the synced contents is torn
+            let end = end
+                .min(bytes.len())
+                .min(file.metadata()?.len().saturating_sub(offset) as usize);
+            if start < end {
+                file.seek(SeekFrom::Start(offset + start as u64))?;
+                file.write_all(&bytes[start..end])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::{
+        db::SimpleDB,
+        errors::{ExecutionError, TransactionError},
+        file::{BlockId, FileManager},
+        page::Page,
+        plan::PlanControl,
+        scan::ScanControl,
+        tx::{
+            checkpoint::write_nonquiescent_checkpoint,
+            transaction::{next_transaction_id, Transaction},
+        },
+    };
+
+    #[test]
+    fn test_torn_write() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let mut file_manager = FileManager::new(temp_dir.clone(), 256);
+
+        let block = file_manager.append_block("testfile")?;
+        let mut page = Page::new(256);
+        page.set_i32(80, 1);
+        page.set_i32(200, 1);
+        file_manager.write(&block, &page)?;
+        file_manager.sync("testfile")?;
+
+        page.set_i32(80, 2);
+        page.set_i32(200, 2);
+        file_manager.write(&block, &page)?;
+        file_manager
+            .get_faults()
+            .lock()
+            .unwrap()
+            .tear_last_write(50, 100);
+        file_manager.drop_unsynced_writes()?;
+
+        // Only the torn range reaches the disk, which the checksum tells
+        let bytes = std::fs::read(temp_dir.join("testfile"))?;
+        assert_eq!(&bytes[80..84], &2i32.to_le_bytes());
+        assert_eq!(&bytes[200..204], &1i32.to_le_bytes());
+        let mut file_manager = FileManager::new(temp_dir, 256);
+        let error = file_manager.read(&block, &mut page).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fail_io() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let mut file_manager = FileManager::new(temp_dir, 256);
+        let block = file_manager.append_block("testfile")?;
+        let mut page = Page::new(256);
+
+        let faults = file_manager.get_faults();
+        let num_ios = faults.lock().unwrap().get_num_ios();
+        faults.lock().unwrap().fail_io(2);
+        file_manager.write(&block, &page)?;
+        let error = file_manager.read(&block, &mut page).unwrap_err();
+        assert_eq!(error.to_string(), "injected I/O failure");
+        file_manager.read(&block, &mut page)?;
+        assert_eq!(faults.lock().unwrap().get_num_ios(), num_ios + 3);
+        Ok(())
+    }
+
+    const BLOCK_SIZE: usize = 1024;
+    // Few enough for the buffers of unfinished transactions to be written
+    const NUM_BUFFERS: usize = 10;
+    const NUM_ROUNDS: usize = 6;
+
+    // Small generator with a fixed seed, so that a failing run can be replayed
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    // Values of table `t` by key
+    type Rows = BTreeMap<i32, i32>;
+
+    struct Workload {
+        random: Random,
+        // Rows that the committed transactions left
+        rows: Rows,
+        // Rows if the transaction whose commit did not return is durable
+        rows_if_committed: Option<Rows>,
+        next_key: i32,
+    }
+
+    impl Workload {
+        fn execute(
+            db: &SimpleDB,
+            tx: &Arc<Mutex<Transaction>>,
+            command: &str,
+        ) -> std::result::Result<usize, ExecutionError> {
+            db.planner
+                .lock()
+                .unwrap()
+                .execute_update(command, tx.clone())
+        }
+
+        fn read_rows(db: &SimpleDB) -> std::result::Result<Rows, ExecutionError> {
+            let tx = Arc::new(Mutex::new(db.new_transaction()?));
+            let mut plan = db
+                .planner
+                .lock()
+                .unwrap()
+                .create_query_plan("select k, v from t", tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut rows = Rows::new();
+            while scan.next()? {
+                rows.insert(scan.get_i32("k")?.unwrap(), scan.get_i32("v")?.unwrap());
+            }
+            drop(scan);
+            tx.lock().unwrap().commit()?;
+            Ok(rows)
+        }
+
+        // Checks that the database holds the rows of the committed
+        // transactions only.
+        fn check(&mut self, db: &SimpleDB) -> std::result::Result<(), ExecutionError> {
+            let rows = Self::read_rows(db)?;
+            if Some(&rows) != self.rows_if_committed.as_ref() {
+                assert_eq!(rows, self.rows);
+            }
+            self.rows = rows;
+            self.rows_if_committed = None;
+            Ok(())
+        }
+
+        // Runs transactions that insert, update and delete rows, until one
+        // is left unfinished for the crash.
+        fn run(&mut self, db: &SimpleDB) -> std::result::Result<(), ExecutionError> {
+            loop {
+                let tx = Arc::new(Mutex::new(db.new_transaction()?));
+                let mut rows = self.rows.clone();
+                for _ in 0..1 + self.random.next(6) {
+                    let keys: Vec<_> = rows.keys().copied().collect();
+                    let value = self.random.next(1000) as i32;
+                    match self.random.next(4) {
+                        2 if !keys.is_empty() => {
+                            let key = keys[self.random.next(keys.len())];
+                            let command = format!("update t set v = {} where k = {}", value, key);
+                            Self::execute(db, &tx, &command)?;
+                            rows.insert(key, value);
+                        }
+                        3 if !keys.is_empty() => {
+                            let key = keys[self.random.next(keys.len())];
+                            Self::execute(db, &tx, &format!("delete from t where k = {}", key))?;
+                            rows.remove(&key);
+                        }
+                        _ => {
+                            let key = self.next_key;
+                            self.next_key += 1;
+                            let command =
+                                format!("insert into t (k, v) values ({}, {})", key, value);
+                            Self::execute(db, &tx, &command)?;
+                            rows.insert(key, value);
+                        }
+                    }
+                    match self.random.next(8) {
+                        0 => write_nonquiescent_checkpoint(
+                            &db.log_manager,
+                            &db.buffer_manager,
+                            next_transaction_id(),
+                        )?,
+                        1 => db
+                            .buffer_manager
+                            .flush_all_modified()
+                            .map_err(TransactionError::from)?,
+                        _ => {}
+                    }
+                }
+
+                match self.random.next(5) {
+                    0 => return Ok(()),
+                    1 => tx.lock().unwrap().rollback()?,
+                    _ => {
+                        self.rows_if_committed = Some(rows);
+                        tx.lock().unwrap().commit()?;
+                        self.rows = self.rows_if_committed.take().unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_torn_page() -> std::result::Result<(), ExecutionError> {
+        let directory = tempfile::tempdir().unwrap().into_path().join("directory");
+        let db = SimpleDB::new(directory.clone(), BLOCK_SIZE, NUM_BUFFERS)?;
+        for command in [
+            "create table t (k I32, v I32)",
+            "insert into t (k, v) values (1, 10)",
+        ] {
+            let tx = Arc::new(Mutex::new(db.new_transaction()?));
+            Workload::execute(&db, &tx, command)?;
+            tx.lock().unwrap().commit()?;
+        }
+        // Only a write over synced blocks is torn, as a checkpoint syncs them
+        db.buffer_manager
+            .flush_all_modified()
+            .map_err(TransactionError::from)?;
+        db.buffer_manager
+            .sync_written_blocks()
+            .map_err(TransactionError::from)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+        Workload::execute(&db, &tx, "insert into t (k, v) values (2, 20)")?;
+        tx.lock().unwrap().commit()?;
+
+        // The last page written keeps only its header and first record
+        let faults = db.file_manager.lock().unwrap().get_faults();
+        faults.lock().unwrap().tear_last_write(0, 16);
+        db.buffer_manager
+            .flush_all_modified()
+            .map_err(TransactionError::from)?;
+        drop(db);
+        faults
+            .lock()
+            .unwrap()
+            .crash(&directory)
+            .map_err(TransactionError::from)?;
+
+        let mut file_manager = FileManager::new(directory.clone(), BLOCK_SIZE);
+        let mut page = Page::new(BLOCK_SIZE);
+        let mut num_torn_blocks = 0;
+        for file_name in file_manager
+            .get_file_names()
+            .map_err(TransactionError::from)?
+        {
+            let num_blocks = file_manager
+                .get_num_blocks(&file_name)
+                .map_err(TransactionError::from)?;
+            for block_slot in 0..num_blocks {
+                let block = BlockId::new(&file_name, block_slot);
+                if let Err(error) = file_manager.read(&block, &mut page) {
+                    assert_eq!(error.kind(), ErrorKind::InvalidData);
+                    num_torn_blocks += 1;
+                }
+            }
+        }
+        assert_eq!(num_torn_blocks, 1);
+        drop(file_manager);
+
+        // Recovery rebuilds the page from its image in the log
+        let db = SimpleDB::new(directory, BLOCK_SIZE, NUM_BUFFERS)?;
+        assert_eq!(Workload::read_rows(&db)?, Rows::from([(1, 10), (2, 20)]));
+        Ok(())
+    }
+
+    // Runs a workload with a crash in every round, dropping the writes not
+    // synced and possibly tearing the last one, keeping any range of it, or
+    // failing an I/O before.
+    fn crash_and_recover(seed: u64) -> std::result::Result<(), ExecutionError> {
+        let directory = tempfile::tempdir().unwrap().into_path().join("directory");
+        let mut workload = Workload {
+            random: Random(seed),
+            rows: Rows::new(),
+            rows_if_committed: None,
+            next_key: 0,
+        };
+        {
+            let db = SimpleDB::new(directory.clone(), BLOCK_SIZE, NUM_BUFFERS)?;
+            let tx = Arc::new(Mutex::new(db.new_transaction()?));
+            Workload::execute(&db, &tx, "create table t (k I32, v I32)")?;
+            tx.lock().unwrap().commit()?;
+        }
+
+        for _ in 0..NUM_ROUNDS {
+            let db = SimpleDB::new(directory.clone(), BLOCK_SIZE, NUM_BUFFERS)?;
+            workload.check(&db)?;
+
+            let faults = db.file_manager.lock().unwrap().get_faults();
+            match workload.random.next(3) {
+                0 => {}
+                1 => {
+                    let start = workload.random.next(BLOCK_SIZE);
+                    let end = start + 1 + workload.random.next(BLOCK_SIZE - start);
+                    faults.lock().unwrap().tear_last_write(start, end);
+                }
+                _ => faults
+                    .lock()
+                    .unwrap()
+                    .fail_io(1 + workload.random.next(500)),
+            }
+            // An injected failure surfaces as an error, after which the
+            // database is not used again
+            let _ = workload.run(&db);
+            drop(db);
+            faults
+                .lock()
+                .unwrap()
+                .crash(&directory)
+                .map_err(TransactionError::from)?;
+        }
+
+        let db = SimpleDB::new(directory, BLOCK_SIZE, NUM_BUFFERS)?;
+        workload.check(&db)
+    }
+
+    #[test]
+    fn test_crash_and_recover() -> std::result::Result<(), ExecutionError> {
+        for seed in 1..=20 {
+            crash_and_recover(seed)?;
+        }
+        Ok(())
+    }
+}
@@ -1,39 +1,140 @@
-use std::fs::{read_dir, remove_file, File};
-use std::io::{Result, Seek, SeekFrom, Write};
+use std::fs::{create_dir_all, read_dir, remove_file, rename, File};
+use std::io::{Error, ErrorKind, Result, Seek, SeekFrom, Write};
 
-use std::sync::{Arc, Mutex, RwLock};
-use std::{collections::HashMap, path::PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
+#[cfg(test)]
+use crate::fault_injection::FaultInjector;
 use crate::page::Page;
 
 const DUMMY_BLOCK_SIZE: usize = usize::MAX;
 
+/// Version of the layout of the blocks and the log, stored in the format
+/// file of the database directory. It changes whenever a database written
+/// before can no longer be read.
+pub const FORMAT_VERSION: u32 = 1;
+const FORMAT_FILE: &str = "format";
+
+/// Bytes at the end of each block holding the checksum of the rest of it,
+/// which tells a block torn by a crash from one written whole.
+pub const BLOCK_CHECKSUM_SIZE: usize = 4;
+
+// Fletcher-32 checksum, which is 0 for zeros so that appended blocks are
+// valid.
+fn checksum(bytes: &[u8]) -> u32 {
+    let (mut sum1, mut sum2) = (0u32, 0u32);
+    for &byte in bytes {
+        sum1 = (sum1 + byte as u32) % 65535;
+        sum2 = (sum2 + sum1) % 65535;
+    }
+    (sum2 << 16) | sum1
+}
+
+/// Interned file name. Block ids hold it instead of the name so that they
+/// are compared and hashed without touching strings.
+///
+/// Only the part before a trailing number is interned, so that the names
+/// of temporary files and log segments, which are numbered without end,
+/// do not grow the table.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId {
+    prefix: u32,
+    number: Option<u64>,
+}
+
+#[derive(Default)]
+struct FileNameTable {
+    ids: HashMap<&'static str, u32>,
+    prefixes: Vec<&'static str>,
+}
+
+// Prefixes are never removed, as there are only a few per table or index.
+static FILE_NAMES: LazyLock<RwLock<FileNameTable>> =
+    LazyLock::new(|| RwLock::new(FileNameTable::default()));
+
+impl FileId {
+    pub fn new(file_name: &str) -> Self {
+        let (prefix, number) = split_number(file_name);
+        FileId {
+            prefix: intern(prefix),
+            number,
+        }
+    }
+
+    pub fn name(&self) -> Cow<'static, str> {
+        let prefix = FILE_NAMES.read().unwrap().prefixes[self.prefix as usize];
+        match self.number {
+            Some(number) => Cow::Owned(format!("{}{}", prefix, number)),
+            None => Cow::Borrowed(prefix),
+        }
+    }
+}
+
+// Splits off the trailing number of the name, unless written with leading
+// zeros, which the number would not keep.
+fn split_number(file_name: &str) -> (&str, Option<u64>) {
+    let prefix = file_name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &file_name[prefix.len()..];
+    if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+        return (file_name, None);
+    }
+    match digits.parse() {
+        Ok(number) => (prefix, Some(number)),
+        Err(_) => (file_name, None),
+    }
+}
+
+fn intern(prefix: &str) -> u32 {
+    if let Some(&id) = FILE_NAMES.read().unwrap().ids.get(prefix) {
+        return id;
+    }
+
+    let mut table = FILE_NAMES.write().unwrap();
+    if let Some(&id) = table.ids.get(prefix) {
+        return id;
+    }
+    let id = table.prefixes.len() as u32;
+    let prefix: &'static str = Box::leak(prefix.to_string().into_boxed_str());
+    table.prefixes.push(prefix);
+    table.ids.insert(prefix, id);
+    id
+}
+
+impl std::fmt::Debug for FileId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.name())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BlockId {
-    pub file_name: String,
+    pub file_id: FileId,
     pub block_slot: usize,
 }
 
 impl BlockId {
     pub fn new(file_name: &str, block_slot: usize) -> Self {
         BlockId {
-            file_name: file_name.to_string(),
+            file_id: FileId::new(file_name),
             block_slot,
         }
     }
 
     pub fn create_dummy(file_name: &str) -> Self {
-        BlockId {
-            file_name: file_name.to_string(),
-            block_slot: DUMMY_BLOCK_SIZE,
-        }
+        BlockId::new(file_name, DUMMY_BLOCK_SIZE)
     }
 
     pub fn get_first_block(file_name: &str) -> Self {
-        BlockId {
-            file_name: file_name.to_string(),
-            block_slot: 0,
-        }
+        BlockId::new(file_name, 0)
+    }
+
+    pub fn file_name(&self) -> Cow<'static, str> {
+        self.file_id.name()
     }
 
     pub fn get_previous_block(&self) -> Option<BlockId> {
@@ -41,20 +142,21 @@ impl BlockId {
             return None;
         }
         Some(BlockId {
-            file_name: self.file_name.clone(),
+            file_id: self.file_id,
             block_slot: self.block_slot - 1,
         })
     }
 
     pub fn get_next_block(&self) -> BlockId {
         BlockId {
-            file_name: self.file_name.clone(),
+            file_id: self.file_id,
             block_slot: self.block_slot + 1,
         }
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let file_name_bytes = self.file_name.as_bytes();
+        let file_name = self.file_name();
+        let file_name_bytes = file_name.as_bytes();
         let file_name_length = file_name_bytes.len();
 
         let block_slot_bytes = self.block_slot.to_le_bytes().to_vec();
@@ -77,14 +179,8 @@ impl BlockId {
             bytes[8], bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15],
         ]);
 
-        let file_name = String::from_utf8(bytes[16..total_length].to_vec()).unwrap();
-        (
-            total_length,
-            BlockId {
-                file_name,
-                block_slot,
-            },
-        )
+        let file_name = std::str::from_utf8(&bytes[16..total_length]).unwrap();
+        (total_length, BlockId::new(file_name, block_slot))
     }
 }
 
@@ -107,23 +203,47 @@ impl FileAccessStats {
     }
 }
 
+/// When the writes to the files are forced to the disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Every write is synced
+    Full,
+    /// The log is synced when it is flushed, the data files before a
+    /// checkpoint and the directory when a file is created, so committed
+    /// transactions survive a power loss
+    #[default]
+    Normal,
+    /// Nothing is synced, so committed transactions survive only a crash of
+    /// the process
+    Off,
+}
+
 pub struct FileManager {
     directory: PathBuf,
     pub block_size: usize,
     opened_files: RwLock<HashMap<String, Arc<Mutex<File>>>>,
     pub is_new: bool,
     pub file_access_stats: FileAccessStats,
+    durability: Durability,
+    // Files written since they were last synced
+    written_files: HashSet<String>,
+    #[cfg(test)]
+    faults: Arc<Mutex<FaultInjector>>,
 }
 
 impl FileManager {
     pub fn new(directory: PathBuf, block_size: usize) -> Self {
-        let is_new = if !directory.exists() {
+        Self::with_durability(directory, block_size, Durability::default())
+    }
+
+    pub fn with_durability(directory: PathBuf, block_size: usize, durability: Durability) -> Self {
+        if !directory.exists() {
             std::fs::create_dir_all(&directory).unwrap();
-            true
-        } else {
-            false
-        };
+        }
 
+        // A directory without data files, such as one created empty or left
+        // by a crash before anything but the format was written, is new
+        let mut is_new = true;
         for entry in read_dir(&directory).unwrap() {
             let entry = entry.unwrap();
             let path = entry.path();
@@ -131,6 +251,8 @@ impl FileManager {
                 if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                     if name.starts_with("temp_") {
                         remove_file(path).unwrap();
+                    } else if name != FORMAT_FILE {
+                        is_new = false;
                     }
                 }
             }
@@ -142,70 +264,248 @@ impl FileManager {
             opened_files: RwLock::new(HashMap::new()),
             is_new,
             file_access_stats: FileAccessStats::new(),
+            durability,
+            written_files: HashSet::new(),
+            #[cfg(test)]
+            faults: Arc::new(Mutex::new(FaultInjector::default())),
         }
     }
 
+    /// Writes the page to the block, except its last bytes, which hold the
+    /// checksum instead.
     pub fn write(&mut self, block: &BlockId, page: &Page) -> Result<()> {
-        let binding = self.load_and_cache_file(&block.file_name);
+        let file_name = block.file_name();
+        let binding = self.load_and_cache_file(&file_name)?;
         let mut file = binding.lock().unwrap();
-        file.seek(SeekFrom::Start((block.block_slot * self.block_size) as u64))?;
-        page.write_to_file(&mut file)?;
+        let offset = (block.block_slot * self.block_size) as u64;
+        let mut bytes = page.as_bytes()[..self.block_size - BLOCK_CHECKSUM_SIZE].to_vec();
+        bytes.extend(checksum(&bytes).to_le_bytes());
+        #[cfg(test)]
+        self.faults
+            .lock()
+            .unwrap()
+            .before_write(&file_name, &mut file, offset, &bytes)?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&bytes)?;
         self.file_access_stats.write_count += 1;
+        drop(file);
+        if self.durability == Durability::Full {
+            self.sync(&file_name)?;
+        } else if !self.written_files.contains(&*file_name) {
+            self.written_files.insert(file_name.into_owned());
+        }
+        Ok(())
+    }
+
+    /// Forces the writes to the file to the disk, unless durability is off.
+    pub fn sync(&mut self, file_name: &str) -> Result<()> {
+        if self.durability == Durability::Off {
+            return Ok(());
+        }
+        #[cfg(test)]
+        self.faults.lock().unwrap().before_io()?;
+        let binding = self.load_and_cache_file(file_name)?;
+        binding.lock().unwrap().sync_data()?;
+        self.written_files.remove(file_name);
+        #[cfg(test)]
+        self.faults.lock().unwrap().file_synced(file_name);
+        Ok(())
+    }
+
+    /// Forces the writes to every file written since it was last synced to
+    /// the disk, unless durability is off.
+    pub fn sync_written_files(&mut self) -> Result<()> {
+        let written_files: Vec<_> = self.written_files.iter().cloned().collect();
+        for file_name in written_files {
+            self.sync(&file_name)?;
+        }
         Ok(())
     }
 
+    /// Reads the block into the page, failing with `InvalidData` if its
+    /// checksum does not match, as when a crash tore its last write.
     pub fn read(&mut self, block: &BlockId, page: &mut Page) -> Result<()> {
-        let binding = self.load_and_cache_file(&block.file_name);
+        #[cfg(test)]
+        self.faults.lock().unwrap().before_io()?;
+        let file_name = block.file_name();
+        let binding = self.load_and_cache_file(&file_name)?;
         let mut file = binding.lock().unwrap();
         file.seek(SeekFrom::Start((block.block_slot * self.block_size) as u64))?;
         page.read_from_file(&mut file)?;
         self.file_access_stats.read_count += 1;
+
+        let (bytes, stored_checksum) = page
+            .as_bytes()
+            .split_at(self.block_size - BLOCK_CHECKSUM_SIZE);
+        if checksum(bytes).to_le_bytes() != stored_checksum {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("torn block {} of {}", block.block_slot, file_name),
+            ));
+        }
         Ok(())
     }
 
-    pub fn get_last_block(&mut self, file_name: &str) -> BlockId {
-        let num_blocks = self.get_num_blocks(file_name);
-        BlockId::new(file_name, num_blocks - 1)
+    pub fn get_last_block(&mut self, file_name: &str) -> Result<BlockId> {
+        let num_blocks = self.get_num_blocks(file_name)?;
+        Ok(BlockId::new(file_name, num_blocks - 1))
     }
 
-    pub fn get_num_blocks(&mut self, file_name: &str) -> usize {
-        let binding = self.load_and_cache_file(file_name);
+    pub fn get_num_blocks(&mut self, file_name: &str) -> Result<usize> {
+        let binding = self.load_and_cache_file(file_name)?;
         let file = binding.lock().unwrap();
-        file.metadata().unwrap().len() as usize / self.block_size
+        Ok(file.metadata()?.len() as usize / self.block_size)
     }
 
     pub fn append_block(&mut self, file_name: &str) -> Result<BlockId> {
-        let binding = self.load_and_cache_file(file_name);
+        let binding = self.load_and_cache_file(file_name)?;
         let mut file = binding.lock().unwrap();
-        let num_blocks = file.metadata().unwrap().len() as usize / self.block_size;
+        let num_blocks = file.metadata()?.len() as usize / self.block_size;
         let new_block_contents = vec![0; self.block_size];
+        #[cfg(test)]
+        {
+            let file_length = file.metadata()?.len();
+            self.faults.lock().unwrap().before_write(
+                file_name,
+                &mut file,
+                file_length,
+                &new_block_contents,
+            )?;
+        }
         file.seek(SeekFrom::End(0))?;
-        file.write(new_block_contents.as_slice())?;
+        file.write_all(new_block_contents.as_slice())?;
         self.file_access_stats.write_count += 1;
+        drop(file);
+
+        // A block lost by a power loss is rebuilt from the page image logged
+        // before its first update, so only full durability syncs it here.
+        // The file must still be in the directory once the log is synced.
+        if self.durability == Durability::Full {
+            self.sync(file_name)?;
+        } else if !self.written_files.contains(file_name) {
+            self.written_files.insert(file_name.to_string());
+        }
+        if num_blocks == 0 && self.durability != Durability::Off {
+            #[cfg(test)]
+            self.faults.lock().unwrap().before_io()?;
+            File::open(&self.directory)?.sync_all()?;
+            #[cfg(test)]
+            self.faults.lock().unwrap().directory_synced();
+        }
         Ok(BlockId::new(file_name, num_blocks))
     }
 
-    fn load_and_cache_file(&mut self, file_name: &str) -> Arc<Mutex<File>> {
+    /// Writes the format version into a new directory, synced before any
+    /// other file so that a database is never found without it. The version
+    /// is renamed into place, so the format file is never found partly written.
+    pub fn write_format_version(&mut self) -> Result<()> {
+        let temp_path = self.directory.join(format!("temp_{}", FORMAT_FILE));
+        let mut file = File::create(&temp_path)?;
+        file.write_all(format!("{}\n", FORMAT_VERSION).as_bytes())?;
+        if self.durability != Durability::Off {
+            file.sync_all()?;
+        }
+        rename(&temp_path, self.directory.join(FORMAT_FILE))?;
+        if self.durability != Durability::Off {
+            File::open(&self.directory)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the format version of the directory, or None if it was
+    /// written before the version was stored.
+    pub fn read_format_version(&self) -> Result<Option<u32>> {
+        let path = self.directory.join(FORMAT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)?;
+        let version = contents.trim().parse().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid format version {:?}", contents),
+            )
+        })?;
+        Ok(Some(version))
+    }
+
+    /// Returns the names of the files in the database directory.
+    pub fn get_file_names(&self) -> Result<Vec<String>> {
+        let mut file_names = vec![];
+        for entry in read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path.is_file() {
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    file_names.push(name.to_string());
+                }
+            }
+        }
+        Ok(file_names)
+    }
+
+    /// Closes the file, which is opened again when it is accessed.
+    pub fn close_file(&mut self, file_name: &str) {
+        self.opened_files.get_mut().unwrap().remove(file_name);
+    }
+
+    pub fn remove_file(&mut self, file_name: &str) -> Result<()> {
+        self.close_file(file_name);
+        #[cfg(test)]
+        self.faults.lock().unwrap().file_removed(file_name);
+        remove_file(self.directory.join(file_name))
+    }
+
+    /// Moves the file into `directory`, which is relative to the database
+    /// directory unless absolute.
+    pub fn move_file(&mut self, file_name: &str, directory: &Path) -> Result<()> {
+        self.close_file(file_name);
+        #[cfg(test)]
+        self.faults.lock().unwrap().file_removed(file_name);
+        let directory = self.directory.join(directory);
+        create_dir_all(&directory)?;
+        rename(self.directory.join(file_name), directory.join(file_name))
+    }
+
+    fn load_and_cache_file(&mut self, file_name: &str) -> Result<Arc<Mutex<File>>> {
         if let Some(file) = self.opened_files.try_read().unwrap().get(file_name) {
-            return file.clone();
+            return Ok(file.clone());
         }
 
         // Check if the file is added before acquiring the write lock
         let mut hash_map = self.opened_files.try_write().unwrap();
         if hash_map.contains_key(file_name) {
-            return hash_map.get(file_name).unwrap().clone();
+            return Ok(hash_map.get(file_name).unwrap().clone());
         }
 
         let file_path = self.directory.join(file_name);
+        #[cfg(test)]
+        if !file_path.exists() {
+            self.faults.lock().unwrap().file_created(file_name);
+        }
         let file = std::fs::OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
-            .open(file_path)
-            .unwrap();
+            .open(file_path)?;
         let value = Arc::new(Mutex::new(file));
         hash_map.insert(file_name.to_string(), value.clone());
-        return value;
+        Ok(value)
+    }
+
+    /// Returns the faults injected into the files, which outlive the file
+    /// manager to crash after it is dropped.
+    #[cfg(test)]
+    pub fn get_faults(&self) -> Arc<Mutex<FaultInjector>> {
+        self.faults.clone()
+    }
+
+    /// Undoes the writes that were not synced, as a power loss would, and
+    /// closes the files.
+    #[cfg(test)]
+    pub fn drop_unsynced_writes(&mut self) -> Result<()> {
+        self.opened_files.get_mut().unwrap().clear();
+        self.written_files.clear();
+        self.faults.lock().unwrap().crash(&self.directory)
     }
 }
 
@@ -242,11 +542,11 @@ mod tests {
         let block_size = 256;
         let mut file_manager = FileManager::new(temp_dir, block_size);
 
-        assert_eq!(file_manager.get_num_blocks("testfile"), 0);
+        assert_eq!(file_manager.get_num_blocks("testfile")?, 0);
         for i in 0..10 {
             let block = file_manager.append_block("testfile")?;
             assert_eq!(block, BlockId::new("testfile", i));
-            assert_eq!(file_manager.get_num_blocks("testfile"), i + 1);
+            assert_eq!(file_manager.get_num_blocks("testfile")?, i + 1);
         }
         assert_eq!(file_manager.file_access_stats.write_count, 10);
         assert_eq!(file_manager.file_access_stats.read_count, 0);
@@ -268,4 +568,86 @@ mod tests {
         assert!(!temp_dir.join("temp_dummy").exists());
         Ok(())
     }
+
+    #[test]
+    fn test_file_id() {
+        let block0 = BlockId::new("testfile", 0);
+        let block1 = block0.get_next_block();
+        assert_eq!(block0.file_id, block1.file_id);
+        assert_eq!(block1.file_name(), "testfile");
+        assert_ne!(block0.file_id, FileId::new("otherfile"));
+
+        let bytes = block1.to_bytes();
+        assert_eq!(BlockId::from_bytes(&bytes), (bytes.len(), block1));
+
+        // Numbered names share the interned prefix
+        let temp1 = FileId::new("temp_12");
+        let temp2 = FileId::new("temp_13");
+        assert_eq!(temp1.prefix, temp2.prefix);
+        assert_ne!(temp1, temp2);
+        assert_eq!(temp2.name(), "temp_13");
+        assert_eq!(FileId::new("log.007").name(), "log.007");
+        assert_eq!(FileId::new("t1.tbl").name(), "t1.tbl");
+    }
+
+    #[test]
+    fn test_drop_unsynced_writes() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let mut file_manager = FileManager::new(temp_dir.clone(), 256);
+
+        let block = file_manager.append_block("testfile")?;
+        let mut page = Page::new(256);
+        page.set_i32(80, 1);
+        file_manager.write(&block, &page)?;
+        file_manager.sync("testfile")?;
+
+        // Neither the write nor the block appended to the other file is synced,
+        // though the other file is in the directory
+        page.set_i32(80, 2);
+        file_manager.write(&block, &page)?;
+        let other_block = file_manager.append_block("otherfile")?;
+        file_manager.write(&other_block, &page)?;
+        file_manager.drop_unsynced_writes()?;
+
+        let mut file_manager = FileManager::new(temp_dir, 256);
+        file_manager.read(&block, &mut page)?;
+        assert_eq!(page.get_i32(80), 1);
+        assert_eq!(file_manager.get_num_blocks("otherfile")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let mut file_manager = FileManager::new(temp_dir.clone(), 256);
+
+        // Appended blocks are valid
+        let block = file_manager.append_block("testfile")?;
+        let mut page = Page::new(256);
+        file_manager.read(&block, &mut page)?;
+
+        page.set_i32(80, 1);
+        file_manager.write(&block, &page)?;
+        file_manager.close_file("testfile");
+        let mut bytes = std::fs::read(temp_dir.join("testfile"))?;
+        bytes[200] = 1;
+        std::fs::write(temp_dir.join("testfile"), bytes)?;
+        let error = file_manager.read(&block, &mut page).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        Ok(())
+    }
+
+    #[test]
+    fn test_durability_off() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let mut file_manager = FileManager::with_durability(temp_dir.clone(), 256, Durability::Off);
+
+        file_manager.append_block("testfile")?;
+        file_manager.sync("testfile")?;
+        file_manager.drop_unsynced_writes()?;
+
+        let mut file_manager = FileManager::new(temp_dir, 256);
+        assert_eq!(file_manager.get_num_blocks("testfile")?, 0);
+        Ok(())
+    }
 }
@@ -8,6 +8,7 @@ use crate::{
     record::{
         field::{Type, Value},
         layout::Layout,
+        record_page::Slot,
     },
     tx::transaction::Transaction,
 };
@@ -27,7 +28,7 @@ impl BTreeDirectory {
         block: BlockId,
         layout: Layout,
     ) -> Result<Self, TransactionError> {
-        let file_name = block.file_name.clone();
+        let file_name = block.file_name().to_string();
         let contents = BTreePage::new(tx.clone(), block, layout.clone())?;
         Ok(Self {
             tx,
@@ -38,23 +39,35 @@ impl BTreeDirectory {
     }
 
     pub(crate) fn search(&mut self, search_key: &Value) -> Result<usize, TransactionError> {
+        Ok(self.find_leaf(search_key)?.0)
+    }
+
+    /// Returns the slot of the leaf block where `search_key` belongs, along
+    /// with the first key of the following leaf block if there is one.
+    pub(crate) fn find_leaf(
+        &self,
+        search_key: &Value,
+    ) -> Result<(usize, Option<Value>), TransactionError> {
         let mut current_contents = BTreePage::new(
             self.tx.clone(),
             self.contents.block.clone(),
             self.layout.clone(),
         )?;
-        while current_contents.get_flag()? > 0 {
-            let child_block_slot = self.find_child_block_slot(search_key)?;
-            let child_block = BlockId {
-                file_name: self.file_name.clone(),
-                block_slot: child_block_slot,
-            };
-            current_contents =
-                BTreePage::new(self.tx.clone(), child_block.clone(), self.layout.clone())?;
+        let mut next_key = None;
+        loop {
+            let slot_index = Self::find_child_slot_index(&current_contents, search_key)?;
+            // The entry after the child on the deepest level bounds its subtree
+            // most tightly
+            if slot_index + 1 < current_contents.get_num_records()? {
+                next_key = Some(current_contents.get_data_value(slot_index + 1)?);
+            }
+            let child_block_slot = current_contents.get_child_block_slot(slot_index)?;
+            if current_contents.get_flag()? == 0 {
+                return Ok((child_block_slot, next_key));
+            }
+            let child_block = BlockId::new(&self.file_name, child_block_slot);
+            current_contents = BTreePage::new(self.tx.clone(), child_block, self.layout.clone())?;
         }
-
-        assert_eq!(current_contents.get_flag()?, 0);
-        self.find_child_block_slot(search_key)
     }
 
     pub(crate) fn make_new_root(
@@ -85,10 +98,7 @@ impl BTreeDirectory {
             let child_block_slot = self.find_child_block_slot(&directory_entry.data_value)?;
             let mut child_btree_directory = BTreeDirectory::new(
                 self.tx.clone(),
-                BlockId {
-                    file_name: self.file_name.clone(),
-                    block_slot: child_block_slot,
-                },
+                BlockId::new(&self.file_name, child_block_slot),
                 self.layout.clone(),
             )?;
             let returned_entry = child_btree_directory.insert(directory_entry)?;
@@ -128,13 +138,25 @@ impl BTreeDirectory {
     }
 
     fn find_child_block_slot(&self, search_key: &Value) -> Result<usize, TransactionError> {
-        let slot = self.contents.find_slot_before(search_key, true)?;
-        let mut slot_index = slot.index();
+        let slot_index = Self::find_child_slot_index(&self.contents, search_key)?;
+        self.contents.get_child_block_slot(slot_index)
+    }
 
-        if self.contents.get_data_value(slot_index + 1)? == *search_key {
-            slot_index += 1;
+    fn find_child_slot_index(
+        contents: &BTreePage,
+        search_key: &Value,
+    ) -> Result<usize, TransactionError> {
+        match contents.find_slot_before(search_key, true)? {
+            Slot::Index(index)
+                if index + 1 < contents.get_num_records()?
+                    && contents.get_data_value(index + 1)? == *search_key =>
+            {
+                Ok(index + 1)
+            }
+            Slot::Index(index) => Ok(index),
+            // The first entry equals the search key
+            _ => Ok(0),
         }
-        self.contents.get_child_block_slot(slot_index)
     }
 
     #[allow(dead_code)]
@@ -154,10 +176,7 @@ impl BTreeDirectory {
             eprintln!("{}{}: {:?} {}", spaces, level, data_value, block_slot);
 
             if level > 0 {
-                let child_block = BlockId {
-                    file_name: self.file_name.clone(),
-                    block_slot,
-                };
+                let child_block = BlockId::new(&self.file_name, block_slot);
                 let child_btree_directory =
                     BTreeDirectory::new(self.tx.clone(), child_block, self.layout.clone())?;
                 child_btree_directory.debug_print(
@@ -166,10 +185,7 @@ impl BTreeDirectory {
                     depth + 1,
                 )?;
             } else {
-                let child_block = BlockId {
-                    file_name: btree_leaf_file_name.to_string(),
-                    block_slot,
-                };
+                let child_block = BlockId::new(btree_leaf_file_name, block_slot);
 
                 let dummy_search_key =
                     match btree_leaf_layout.schema.get_field_type(INDEX_VALUE_COLUMN) {
@@ -1,4 +1,7 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    ops::Bound,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
     errors::TransactionError,
@@ -8,6 +11,7 @@ use crate::{
     record::{
         field::{Spec, Value},
         layout::Layout,
+        record_page::Slot,
         schema::Schema,
     },
     scan::RecordId,
@@ -23,6 +27,41 @@ pub struct BTreeIndex {
     directory_root_block: BlockId,
     btree_leaf: Option<BTreeLeaf>,
     btree_leaf_file_name: String,
+    range: Option<RangeCursor>,
+}
+
+/// Position of a range lookup among the leaf blocks.
+///
+/// Leaf blocks are not linked to each other, so the directory is searched
+/// again with the first key of the next leaf once a leaf and its overflow
+/// blocks are exhausted. Records come in key order, except that the overflow
+/// blocks of a leaf follow the rest of that leaf.
+struct RangeCursor {
+    low: Bound<Value>,
+    high: Bound<Value>,
+    contents: Option<BTreePage>,
+    current_slot: Slot,
+    next_leaf_key: Option<Value>,
+}
+
+impl RangeCursor {
+    fn is_below_high(&self, value: &Value) -> bool {
+        match &self.high {
+            Bound::Included(high) => value <= high,
+            Bound::Excluded(high) => value < high,
+            Bound::Unbounded => true,
+        }
+    }
+
+    fn contains(&self, value: &Value) -> bool {
+        let is_above_low = match &self.low {
+            Bound::Included(low) => value >= low,
+            Bound::Excluded(low) => value > low,
+            Bound::Unbounded => true,
+        };
+        // NULL sorts last but is never part of a range
+        *value != Value::Null && is_above_low && self.is_below_high(value)
+    }
 }
 
 impl BTreeIndex {
@@ -35,7 +74,7 @@ impl BTreeIndex {
         {
             if tx.lock().unwrap().get_num_blocks(&leaf_table)? == 0 {
                 let block = tx.lock().unwrap().append_block(&leaf_table)?;
-                let node = BTreePage::new(tx.clone(), block, leaf_layout.clone()).unwrap();
+                let node = BTreePage::new(tx.clone(), block, leaf_layout.clone())?;
                 node.format(-1)?;
             }
         }
@@ -77,9 +116,71 @@ impl BTreeIndex {
             directory_root_block,
             btree_leaf: None,
             btree_leaf_file_name: leaf_table,
+            range: None,
         })
     }
 
+    fn min_value(&self) -> Value {
+        match self.leaf_layout.schema.get_field_spec(INDEX_VALUE_COLUMN) {
+            Spec::I32 => Value::I32(i32::MIN),
+            Spec::VarChar(_) => Value::String("".to_string()),
+        }
+    }
+
+    fn open_leaf(&self, leaf_key: &Value) -> Result<(BTreePage, Option<Value>), TransactionError> {
+        let root = BTreeDirectory::new(
+            self.tx.clone(),
+            self.directory_root_block.clone(),
+            self.directory_layout.clone(),
+        )?;
+        let (leaf_block_slot, next_leaf_key) = root.find_leaf(leaf_key)?;
+        let leaf_block = BlockId::new(&self.btree_leaf_file_name, leaf_block_slot);
+        let contents = BTreePage::new(self.tx.clone(), leaf_block, self.leaf_layout.clone())?;
+        Ok((contents, next_leaf_key))
+    }
+
+    fn next_in_range(&mut self) -> Result<bool, TransactionError> {
+        loop {
+            let range = self.range.as_mut().unwrap();
+            let Some(contents) = &range.contents else {
+                return Ok(false);
+            };
+            range.current_slot = range.current_slot.next();
+            let index = range.current_slot.index();
+            if index < contents.get_num_records()? {
+                if range.contains(&contents.get_data_value(index)?) {
+                    return Ok(true);
+                }
+                continue;
+            }
+
+            let overflow_pointer = contents.get_flag()?;
+            if overflow_pointer >= 0 {
+                let overflow_block =
+                    BlockId::new(&self.btree_leaf_file_name, overflow_pointer as usize);
+                range.contents = Some(BTreePage::new(
+                    self.tx.clone(),
+                    overflow_block,
+                    self.leaf_layout.clone(),
+                )?);
+                range.current_slot = Slot::Start;
+                continue;
+            }
+
+            range.contents = None;
+            match range.next_leaf_key.take() {
+                Some(leaf_key) if range.is_below_high(&leaf_key) => {
+                    let (contents, next_leaf_key) = self.open_leaf(&leaf_key)?;
+                    let range = self.range.as_mut().unwrap();
+                    range.contents = Some(contents);
+                    range.current_slot = Slot::Start;
+                    range.next_leaf_key = next_leaf_key;
+                }
+                _ => return Ok(false),
+            }
+        }
+    }
+
     pub(crate) fn get_search_cost(num_blocks: usize, records_per_block: usize) -> usize {
         1 + (((num_blocks + 1) as f64).log2().ceil()
             / ((records_per_block + 1) as f64).log2().ceil()) as usize
@@ -102,6 +203,7 @@ impl BTreeIndex {
 
 impl IndexControl for BTreeIndex {
     fn before_first(&mut self, search_key: &Value) -> Result<(), TransactionError> {
+        self.range = None;
         let mut root = BTreeDirectory::new(
             self.tx.clone(),
             self.directory_root_block.clone(),
@@ -120,12 +222,40 @@ impl IndexControl for BTreeIndex {
         Ok(())
     }
 
+    fn before_range(
+        &mut self,
+        low: Bound<&Value>,
+        high: Bound<&Value>,
+    ) -> Result<(), TransactionError> {
+        self.btree_leaf = None;
+        let leaf_key = match low {
+            Bound::Included(low) | Bound::Excluded(low) => low.clone(),
+            Bound::Unbounded => self.min_value(),
+        };
+        let (contents, next_leaf_key) = self.open_leaf(&leaf_key)?;
+        self.range = Some(RangeCursor {
+            low: low.cloned(),
+            high: high.cloned(),
+            contents: Some(contents),
+            current_slot: Slot::Start,
+            next_leaf_key,
+        });
+        Ok(())
+    }
+
     fn next(&mut self) -> Result<bool, TransactionError> {
+        if self.range.is_some() {
+            return self.next_in_range();
+        }
         let leaf_page = self.btree_leaf.as_mut().unwrap();
         leaf_page.next()
     }
 
     fn get(&self) -> Result<RecordId, TransactionError> {
+        if let Some(range) = &self.range {
+            let contents = range.contents.as_ref().unwrap();
+            return contents.get_data_record_id(range.current_slot.index());
+        }
         let leaf_page = self.btree_leaf.as_ref().unwrap();
         leaf_page.get_data_record_id()
     }
@@ -165,7 +295,10 @@ impl IndexControl for BTreeIndex {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, Mutex};
+    use std::{
+        ops::RangeBounds,
+        sync::{Arc, Mutex},
+    };
 
     use crate::{db::SimpleDB, record::schema::Schema};
 
@@ -375,4 +508,93 @@ mod tests {
         tx.lock().unwrap().commit()?;
         Ok(())
     }
+
+    #[test]
+    fn test_b_tree_index_range() -> Result<(), anyhow::Error> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 256;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+        let metadata_manager = db.metadata_manager.clone();
+
+        let mut schema = Schema::new();
+        schema.add_i32_field("A");
+
+        metadata_manager
+            .lock()
+            .unwrap()
+            .create_table("test_table", &schema, tx.clone())?;
+
+        metadata_manager.lock().unwrap().create_index(
+            "test_index",
+            "test_table",
+            "A",
+            tx.clone(),
+        )?;
+
+        let mut index = metadata_manager
+            .lock()
+            .unwrap()
+            .get_index_info("test_table", tx.clone())?
+            .get("A")
+            .unwrap()
+            .open()?;
+
+        // Enough keys for a directory with several levels, inserted out of
+        // order, and a duplicated key that needs overflow blocks
+        let n = 3000;
+        let mut entries = vec![];
+        for i in 0..n {
+            entries.push((Value::I32((i * 7 % n) as i32), i));
+        }
+        for i in n..n + 100 {
+            entries.push((Value::I32(1500), i));
+        }
+        entries.push((Value::Null, n + 100));
+        for (value, i) in &entries {
+            index.insert(value, &RecordId(DUMMY_BLOCK_SLOT, *i))?;
+        }
+
+        let mut check_range = |low: Bound<&Value>, high: Bound<&Value>| -> anyhow::Result<()> {
+            let mut expected_record_ids: Vec<usize> = entries
+                .iter()
+                .filter(|(value, _)| *value != Value::Null && (low, high).contains(value))
+                .map(|(_, i)| *i)
+                .collect();
+            expected_record_ids.sort();
+
+            index.before_range(low, high)?;
+            let mut actual_record_ids = vec![];
+            while index.next()? {
+                let record_id = index.get()?;
+                assert_eq!(record_id.0, DUMMY_BLOCK_SLOT);
+                actual_record_ids.push(record_id.1);
+            }
+            actual_record_ids.sort();
+            assert_eq!(actual_record_ids, expected_record_ids);
+            Ok(())
+        };
+
+        check_range(
+            Bound::Included(&Value::I32(100)),
+            Bound::Included(&Value::I32(200)),
+        )?;
+        check_range(
+            Bound::Excluded(&Value::I32(1400)),
+            Bound::Excluded(&Value::I32(1600)),
+        )?;
+        check_range(Bound::Unbounded, Bound::Included(&Value::I32(10)))?;
+        check_range(Bound::Included(&Value::I32(2990)), Bound::Unbounded)?;
+        check_range(Bound::Unbounded, Bound::Unbounded)?;
+        check_range(
+            Bound::Included(&Value::I32(5000)),
+            Bound::Included(&Value::I32(6000)),
+        )?;
+
+        drop(index);
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
 }
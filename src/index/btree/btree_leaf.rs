@@ -136,7 +136,7 @@ impl BTreeLeaf {
             Ok(false)
         } else {
             let next_block =
-                BlockId::new(&self.contents.block.file_name, overflow_pointer as usize);
+                BlockId::new(&self.contents.block.file_name(), overflow_pointer as usize);
             self.contents = BTreePage::new(
                 self.contents.tx.clone(),
                 next_block,
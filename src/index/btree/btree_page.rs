@@ -97,7 +97,7 @@ impl BTreePage {
             .tx
             .lock()
             .unwrap()
-            .append_block(&self.block.file_name)?;
+            .append_block(&self.block.file_name())?;
 
         let mut new_btree_page =
             BTreePage::new(self.tx.clone(), new_block.clone(), self.layout.clone())?;
@@ -2,11 +2,20 @@ pub mod btree;
 pub mod plan;
 pub mod scan;
 
+use std::ops::Bound;
+
 use btree::btree_index::BTreeIndex;
 use enum_dispatch::enum_dispatch;
 
 use crate::{errors::TransactionError, record::field::Value, scan::RecordId};
 
+/// Keys looked up in an index by an index select.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchKey {
+    Equal(Value),
+    Range(Bound<Value>, Bound<Value>),
+}
+
 #[enum_dispatch]
 pub enum Index {
     BTree(BTreeIndex),
@@ -15,6 +24,13 @@ pub enum Index {
 #[enum_dispatch(Index)]
 pub trait IndexControl {
     fn before_first(&mut self, search_key: &Value) -> Result<(), TransactionError>;
+    /// Positions the index before the records whose keys lie between `low` and
+    /// `high`. NULL keys are never part of a range.
+    fn before_range(
+        &mut self,
+        low: Bound<&Value>,
+        high: Bound<&Value>,
+    ) -> Result<(), TransactionError>;
     fn next(&mut self) -> Result<bool, TransactionError>;
     fn get(&self) -> Result<RecordId, TransactionError>;
     fn insert(&mut self, value: &Value, record_id: &RecordId) -> Result<(), TransactionError>;
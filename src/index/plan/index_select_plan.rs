@@ -2,8 +2,9 @@ use std::sync::{Arc, Mutex};
 
 use crate::{
     errors::TransactionError,
-    index::scan::index_select_scan::IndexSelectScan,
+    index::{scan::index_select_scan::IndexSelectScan, SearchKey},
     metadata::index_manager::IndexInfo,
+    parser::predicate::RANGE_REDUCTION_FACTOR,
     plan::{table_plan::TablePlan, PlanControl},
     record::{field::Value, schema::Schema},
     scan::Scan,
@@ -14,26 +15,49 @@ use crate::{
 pub struct IndexSelectPlan {
     table_plan: TablePlan,
     index_info: IndexInfo,
-    value: Value,
+    search_keys: Vec<SearchKey>,
 }
 
 impl IndexSelectPlan {
     pub fn new(table_plan: TablePlan, index_info: &IndexInfo, value: &Value) -> Self {
+        Self::new_with_keys(
+            table_plan,
+            index_info,
+            vec![SearchKey::Equal(value.clone())],
+        )
+    }
+
+    /// Selects the records matching any of `search_keys`, which should not
+    /// overlap.
+    pub fn new_with_keys(
+        table_plan: TablePlan,
+        index_info: &IndexInfo,
+        search_keys: Vec<SearchKey>,
+    ) -> Self {
         IndexSelectPlan {
             table_plan,
             index_info: index_info.clone(),
-            value: value.clone(),
+            search_keys,
         }
     }
 }
 
 impl PlanControl for IndexSelectPlan {
     fn get_num_accessed_blocks(&self) -> usize {
-        self.index_info.get_num_accessed_blocks() + self.get_num_output_records()
+        self.index_info.get_num_accessed_blocks() * self.search_keys.len()
+            + self.get_num_output_records()
     }
 
     fn get_num_output_records(&self) -> usize {
-        self.index_info.get_num_records()
+        self.search_keys
+            .iter()
+            .map(|search_key| match search_key {
+                SearchKey::Equal(_) => self.index_info.get_num_records(),
+                SearchKey::Range(_, _) => {
+                    self.table_plan.get_num_output_records() / RANGE_REDUCTION_FACTOR
+                }
+            })
+            .sum()
     }
 
     fn num_distinct_values(&self, field_name: &str) -> usize {
@@ -46,12 +70,11 @@ impl PlanControl for IndexSelectPlan {
 
     fn open(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
         let scan = self.table_plan.open(tx.clone())?;
-        let value = self.value.clone();
         match scan {
-            Scan::TableScan(table_scan) => Ok(Scan::from(IndexSelectScan::new(
+            Scan::TableScan(table_scan) => Ok(Scan::from(IndexSelectScan::new_with_keys(
                 table_scan,
                 self.index_info.open()?,
-                value,
+                self.search_keys.clone(),
             )?)),
             _ => panic!("Expected TableScan"),
         }
@@ -1,14 +1,17 @@
 use crate::{
     errors::TransactionError,
-    index::{Index, IndexControl},
+    index::{Index, IndexControl, SearchKey},
     record::field::Value,
     scan::{table_scan::TableScan, RecordPointer, ScanControl},
 };
 
+/// Reads the records of a table found by probing an index with each of the
+/// search keys in turn.
 pub struct IndexSelectScan {
     table_scan: TableScan,
     index: Index,
-    value: Value,
+    search_keys: Vec<SearchKey>,
+    current_key: usize,
 }
 
 impl IndexSelectScan {
@@ -16,29 +19,56 @@ impl IndexSelectScan {
         table_scan: TableScan,
         index: Index,
         value: Value,
+    ) -> Result<Self, TransactionError> {
+        Self::new_with_keys(table_scan, index, vec![SearchKey::Equal(value)])
+    }
+
+    /// The search keys should not overlap, or records are read more than once.
+    pub fn new_with_keys(
+        table_scan: TableScan,
+        index: Index,
+        search_keys: Vec<SearchKey>,
     ) -> Result<Self, TransactionError> {
         let mut scan = IndexSelectScan {
             table_scan,
             index,
-            value,
+            search_keys,
+            current_key: 0,
         };
         scan.before_first()?;
         Ok(scan)
     }
+
+    fn probe(&mut self) -> Result<(), TransactionError> {
+        match &self.search_keys[self.current_key] {
+            SearchKey::Equal(value) => self.index.before_first(value),
+            SearchKey::Range(low, high) => self.index.before_range(low.as_ref(), high.as_ref()),
+        }
+    }
 }
 
 impl ScanControl for IndexSelectScan {
     fn before_first(&mut self) -> Result<(), TransactionError> {
-        self.index.before_first(&self.value)
+        self.current_key = 0;
+        if self.search_keys.is_empty() {
+            return Ok(());
+        }
+        self.probe()
     }
 
     fn next(&mut self) -> Result<bool, TransactionError> {
-        let has_next = self.index.next()?;
-        if has_next {
-            let record_pointer = RecordPointer::from(self.index.get()?);
-            self.table_scan.move_to_record_pointer(&record_pointer)?;
+        while self.current_key < self.search_keys.len() {
+            if self.index.next()? {
+                let record_pointer = RecordPointer::from(self.index.get()?);
+                self.table_scan.move_to_record_pointer(&record_pointer)?;
+                return Ok(true);
+            }
+            self.current_key += 1;
+            if self.current_key < self.search_keys.len() {
+                self.probe()?;
+            }
         }
-        Ok(has_next)
+        Ok(false)
     }
 
     fn get_i32(&mut self, field_name: &str) -> Result<Option<i32>, TransactionError> {
@@ -71,7 +101,10 @@ mod tests {
     };
 
     use super::*;
-    use std::sync::{Arc, Mutex};
+    use std::{
+        ops::Bound,
+        sync::{Arc, Mutex},
+    };
 
     #[test]
     fn test_index_select_scan() -> Result<(), TransactionError> {
@@ -141,6 +174,37 @@ mod tests {
         assert_eq!(expected_values, actual_values);
         assert!(!select_scan.next()?);
         drop(select_scan);
+
+        // Probe the index once per key, as for `B IN ('0', '2')`, and with a
+        // range, as for `B >= '3'`
+        let table_scan = TableScan::new(tx.clone(), test_table_name, layout.clone())?;
+        let index = db
+            .metadata_manager
+            .lock()
+            .unwrap()
+            .get_index_info(test_table_name, tx.clone())?
+            .get("B")
+            .unwrap()
+            .open()?;
+        let search_keys = vec![
+            SearchKey::Equal(Value::String("0".to_owned())),
+            SearchKey::Equal(Value::String("2".to_owned())),
+            SearchKey::Range(
+                Bound::Included(Value::String("3".to_owned())),
+                Bound::Unbounded,
+            ),
+        ];
+        let mut select_scan = IndexSelectScan::new_with_keys(table_scan, index, search_keys)?;
+        let mut actual_values = vec![];
+        while select_scan.next()? {
+            actual_values.push(select_scan.get_i32("A")?.unwrap());
+        }
+        actual_values.sort();
+        assert_eq!(
+            actual_values,
+            (0..50).filter(|i| i % 4 != 1).collect::<Vec<_>>()
+        );
+        drop(select_scan);
         tx.lock().unwrap().commit()?;
         Ok(())
     }
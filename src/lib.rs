@@ -1,8 +1,11 @@
+pub mod background;
 pub mod buffer;
 pub mod config;
 pub mod db;
 pub mod driver;
 pub mod errors;
+#[cfg(test)]
+pub mod fault_injection;
 pub mod file;
 pub mod index;
 pub mod log;
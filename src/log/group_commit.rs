@@ -0,0 +1,208 @@
+use std::{
+    io::Result,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::manager::LogManager;
+
+struct GroupCommitState {
+    // LSN up to which the log is known to be on the disk
+    saved_log_sequence_number: usize,
+    is_flushing: bool,
+    num_flushes: u64,
+}
+
+/// Flushes the log for committing transactions in groups. A committer waits
+/// for its commit record to be on the disk. The first one to find no flush
+/// in progress becomes the leader: while other transactions are active, it
+/// waits up to `max_delay` for them to commit, and then writes and syncs the
+/// log once for all of them, while later committers wait for the next flush.
+///
+/// The log is forced in LSN order, so a commit is never on the disk before
+/// the records preceding it.
+pub struct GroupCommit {
+    log_manager: Arc<Mutex<LogManager>>,
+    max_delay: Duration,
+    state: Mutex<GroupCommitState>,
+    condvar: Condvar,
+}
+
+impl GroupCommit {
+    pub fn new(log_manager: Arc<Mutex<LogManager>>, max_delay: Duration) -> Self {
+        let saved_log_sequence_number = log_manager
+            .lock()
+            .unwrap()
+            .get_last_saved_log_sequence_number();
+        GroupCommit {
+            log_manager,
+            max_delay,
+            state: Mutex::new(GroupCommitState {
+                saved_log_sequence_number,
+                is_flushing: false,
+                num_flushes: 0,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Returns once the log is on the disk up to the LSN.
+    pub fn flush(&self, log_sequence_number: usize) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        // A leader waiting for others to commit checks again
+        self.condvar.notify_all();
+        loop {
+            if state.saved_log_sequence_number >= log_sequence_number {
+                return Ok(());
+            }
+            if state.is_flushing {
+                state = self.condvar.wait(state).unwrap();
+                continue;
+            }
+
+            state.is_flushing = true;
+            let deadline = Instant::now() + self.max_delay;
+            loop {
+                let now = Instant::now();
+                if now >= deadline || !self.has_active_transactions() {
+                    break;
+                }
+                state = self.condvar.wait_timeout(state, deadline - now).unwrap().0;
+            }
+            drop(state);
+            let result = {
+                let mut log_manager = self.log_manager.lock().unwrap();
+                let latest_log_sequence_number = log_manager.get_latest_log_sequence_number();
+                log_manager
+                    .flush(latest_log_sequence_number)
+                    .map(|_| log_manager.get_last_saved_log_sequence_number())
+            };
+
+            state = self.state.lock().unwrap();
+            state.is_flushing = false;
+            // Waiters retry the flush themselves if it failed
+            self.condvar.notify_all();
+            let saved_log_sequence_number = result?;
+            state.saved_log_sequence_number = state
+                .saved_log_sequence_number
+                .max(saved_log_sequence_number);
+            state.num_flushes += 1;
+        }
+    }
+
+    // Whether a transaction other than the committers may commit soon
+    fn has_active_transactions(&self) -> bool {
+        !self
+            .log_manager
+            .lock()
+            .unwrap()
+            .get_active_transactions()
+            .is_empty()
+    }
+
+    /// Returns how many times the log has been flushed for committers.
+    pub fn get_num_flushes(&self) -> u64 {
+        self.state.lock().unwrap().num_flushes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread};
+
+    use crate::{file::FileManager, log::record::LogRecord};
+
+    use super::*;
+
+    fn new_log_manager(block_size: usize) -> Result<Arc<Mutex<LogManager>>> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, block_size)));
+        Ok(Arc::new(Mutex::new(LogManager::new(
+            file_manager,
+            "log".into(),
+        )?)))
+    }
+
+    #[test]
+    fn test_group_commit() -> Result<()> {
+        let log_manager = new_log_manager(400)?;
+        let group_commit = Arc::new(GroupCommit::new(
+            log_manager.clone(),
+            Duration::from_millis(50),
+        ));
+        let num_committers = 8;
+        let barrier = Arc::new(Barrier::new(num_committers));
+
+        let handles: Vec<_> = (0..num_committers)
+            .map(|transaction_id| {
+                let log_manager = log_manager.clone();
+                let group_commit = group_commit.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || -> Result<(usize, usize)> {
+                    log_manager
+                        .lock()
+                        .unwrap()
+                        .append_record(&LogRecord::Savepoint(transaction_id, "s".into()))?;
+                    barrier.wait();
+                    let log_sequence_number = log_manager
+                        .lock()
+                        .unwrap()
+                        .append_record(&LogRecord::Commit(transaction_id))?;
+                    group_commit.flush(log_sequence_number)?;
+                    let saved_log_sequence_number = log_manager
+                        .lock()
+                        .unwrap()
+                        .get_last_saved_log_sequence_number();
+                    Ok((log_sequence_number, saved_log_sequence_number))
+                })
+            })
+            .collect();
+        for handle in handles {
+            // Every record up to the commit is on the disk when it returns
+            let (log_sequence_number, saved_log_sequence_number) = handle.join().unwrap()?;
+            assert!(saved_log_sequence_number >= log_sequence_number);
+        }
+        // The committers arriving during the delay share a flush
+        assert!(group_commit.get_num_flushes() < num_committers as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_commit_across_blocks() -> Result<()> {
+        let log_manager = new_log_manager(80)?;
+        let group_commit = GroupCommit::new(log_manager.clone(), Duration::ZERO);
+
+        let mut log_sequence_numbers = vec![];
+        for transaction_id in 0..20 {
+            let log_sequence_number = log_manager
+                .lock()
+                .unwrap()
+                .append_record(&LogRecord::Commit(transaction_id))?;
+            log_sequence_numbers.push(log_sequence_number);
+        }
+        // A flush for a later commit covers the earlier ones
+        group_commit.flush(log_sequence_numbers[19])?;
+        let num_flushes = group_commit.get_num_flushes();
+        for &log_sequence_number in log_sequence_numbers.iter() {
+            group_commit.flush(log_sequence_number)?;
+        }
+        assert_eq!(group_commit.get_num_flushes(), num_flushes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_commit_alone() -> Result<()> {
+        let log_manager = new_log_manager(400)?;
+        let group_commit = GroupCommit::new(log_manager.clone(), Duration::from_secs(60));
+
+        // A committer does not wait when no other transaction is active
+        let start = Instant::now();
+        let log_sequence_number = log_manager
+            .lock()
+            .unwrap()
+            .append_record(&LogRecord::Commit(0))?;
+        group_commit.flush(log_sequence_number)?;
+        assert!(start.elapsed() < Duration::from_secs(10));
+        Ok(())
+    }
+}
@@ -1,62 +1,180 @@
 use std::{
-    io::Result,
+    collections::HashMap,
+    io::{ErrorKind, Result},
     mem,
+    path::PathBuf,
     sync::{Arc, Mutex, MutexGuard},
 };
 
 use crate::{
-    file::{BlockId, FileManager},
+    file::{BlockId, FileManager, BLOCK_CHECKSUM_SIZE},
     page::Page,
 };
 
 use super::record::LogRecord;
 
+/// Number of blocks in a log segment file
+pub const DEFAULT_LOG_SEGMENT_SIZE: usize = 64;
+
+/// What happens to the log segments that recovery no longer needs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LogRetention {
+    #[default]
+    Delete,
+    /// Moves them into the directory, relative to the database directory
+    /// unless absolute
+    Archive(PathBuf),
+}
+
 /// The log manager is responsible for writing log records to the log file.
 /// The log file is a sequence of blocks, and the log manager appends log records to the last block.
 /// The log records are written to the block file from right to left.
 /// The first byte of the block contains the offset to the most recent log record.
 ///
+/// A block is not written again once it is synced, so that a crash can only
+/// tear a block whose records were never on the disk, which recovery drops.
+///
+/// The blocks are split into segment files named after the log file and the
+/// number of the first block of the segment in the whole log, e.g. `log.64`.
+/// Segments before the last checkpoint can be truncated.
+///
+/// The log sequence number (LSN) of a record is its distance from the start
+/// of the log, counting each block only up to the end of the record, so
+/// LSNs grow with every record and survive restarts. LSN 0 is never used.
+///
 /// This struct is expected to be a singleton. Hence, it is not thread-safe.
 /// Other structs that use this struct should wrap it with Arc<Mutex<>>.
 pub struct LogManager {
     file_manager: Arc<Mutex<FileManager>>,
     log_file: String,
     log_page: Page,
+    // First block of each segment in the whole log, oldest first
+    segments: Vec<usize>,
+    segment_size: usize,
+    retention: LogRetention,
     current_block: BlockId,
     latest_log_sequence_number: usize,
     last_saved_log_sequence_number: usize,
+    // Whether the current block was synced, so the next record starts a new
+    // block
+    is_current_block_saved: bool,
+    // First LSN of each transaction whose records are not yet followed by a
+    // commit or rollback
+    active_transactions: HashMap<usize, usize>,
 }
 
 impl LogManager {
     pub fn new(file_manager: Arc<Mutex<FileManager>>, log_file: String) -> Result<Self> {
+        Self::with_segments(
+            file_manager,
+            log_file,
+            DEFAULT_LOG_SEGMENT_SIZE,
+            LogRetention::default(),
+        )
+    }
+
+    pub fn with_segments(
+        file_manager: Arc<Mutex<FileManager>>,
+        log_file: String,
+        segment_size: usize,
+        retention: LogRetention,
+    ) -> Result<Self> {
         let mut file_manager_guard = file_manager.lock().unwrap();
-        let num_blocks = file_manager_guard.get_num_blocks(&log_file);
-        let mut log_page = Page::new(file_manager_guard.block_size);
-        let current_block = if num_blocks == 0 {
-            // Create the first block of the file
-            let block_id = file_manager_guard.append_block(&log_file)?;
+        let block_size = file_manager_guard.block_size;
+        let block_end = block_size - BLOCK_CHECKSUM_SIZE;
+        let mut log_page = Page::new(block_size);
+        let segment_prefix = format!("{}.", log_file);
+        let mut segments: Vec<usize> = file_manager_guard
+            .get_file_names()?
+            .iter()
+            .filter_map(|name| name.strip_prefix(&segment_prefix)?.parse().ok())
+            .collect();
+        segments.sort();
+        if segments.is_empty() {
+            segments.push(0);
+        }
+
+        let last_segment = *segments.last().unwrap();
+        let segment_file = segment_file_name(&log_file, last_segment);
+        let current_block = if file_manager_guard.get_num_blocks(&segment_file)? == 0 {
+            // Create the first block of the segment
+            let block_id = file_manager_guard.append_block(&segment_file)?;
 
             // Currently, page cannot store usize values
-            log_page.set_i32(0, file_manager_guard.block_size as i32);
+            log_page.set_i32(0, block_end as i32);
             file_manager_guard.write(&block_id, &log_page)?;
             block_id
         } else {
-            // Read the last block of the file
-            let current_block = file_manager_guard.get_last_block(&log_file);
-            file_manager_guard.read(&current_block, &mut log_page)?;
+            // Read the last block of the segment
+            let current_block = file_manager_guard.get_last_block(&segment_file)?;
+            match file_manager_guard.read(&current_block, &mut log_page) {
+                // The block was torn before it was synced, so none of its
+                // records were on the disk
+                Err(error) if error.kind() == ErrorKind::InvalidData => {
+                    log_page = Page::new(block_size);
+                }
+                result => result?,
+            }
+            // A block is synced when it is appended, but its boundary only
+            // with the first records, so a crash can leave it zeroed
+            if log_page.get_i32(0) == 0 {
+                log_page.set_i32(0, block_end as i32);
+            }
             current_block
         };
+        // The records of the block may have been synced
+        let is_current_block_saved = log_page.get_i32(0) < block_end as i32;
+        let latest_log_sequence_number = log_sequence_number(
+            last_segment + current_block.block_slot,
+            log_page.get_i32(0) as usize,
+            block_size,
+        );
 
         Ok(Self {
             file_manager: file_manager.clone(),
             log_file,
             log_page,
+            segments,
+            segment_size,
+            retention,
             current_block,
-            latest_log_sequence_number: 0,
-            last_saved_log_sequence_number: 0,
+            latest_log_sequence_number,
+            last_saved_log_sequence_number: latest_log_sequence_number,
+            is_current_block_saved,
+            active_transactions: HashMap::new(),
         })
     }
 
+    pub fn get_latest_log_sequence_number(&self) -> usize {
+        self.latest_log_sequence_number
+    }
+
+    /// Returns the transactions that logged a record since the log manager
+    /// was created and have not committed or rolled back yet, with the LSN of
+    /// their first record, ordered by transaction id.
+    pub fn get_active_transactions(&self) -> Vec<(usize, usize)> {
+        let mut active_transactions: Vec<_> = self
+            .active_transactions
+            .iter()
+            .map(|(&transaction_id, &log_sequence_number)| (transaction_id, log_sequence_number))
+            .collect();
+        active_transactions.sort();
+        active_transactions
+    }
+
+    pub fn get_first_log_sequence_number(&self, transaction_id: usize) -> Option<usize> {
+        self.active_transactions.get(&transaction_id).copied()
+    }
+
+    /// Returns the size of the largest record that fits in a log block.
+    pub fn get_max_record_size(&self) -> usize {
+        let block_size = self.file_manager.lock().unwrap().block_size;
+        block_size
+            - BLOCK_CHECKSUM_SIZE
+            - mem::size_of::<i32>()
+            - self.log_page.get_required_size(&[])
+    }
+
     pub fn append_record(&mut self, log_record: &LogRecord) -> Result<usize> {
         let mut boundary = self.log_page.get_i32(0) as usize;
         let record_bytes = log_record.to_bytes();
@@ -65,18 +183,33 @@ impl LogManager {
         let boundary_size = mem::size_of::<i32>();
         let mut file_manager = self.file_manager.lock().unwrap();
 
-        if boundary < record_size + boundary_size {
-            // The record does not fit in the current block
+        if self.is_current_block_saved || boundary < record_size + boundary_size {
+            // The record does not fit in the current block, or the block is
+            // synced and not written again
 
             // Save the current page into the file
-            file_manager.write(&self.current_block, &self.log_page)?;
-            self.last_saved_log_sequence_number = self.latest_log_sequence_number;
+            if !self.is_current_block_saved {
+                file_manager.write(&self.current_block, &self.log_page)?;
+            }
 
-            // Create a new block
-            let new_block = file_manager.append_block(&self.log_file)?;
-            self.log_page.set_i32(0, file_manager.block_size as i32);
+            // Create a new block, in a new segment if the current one is full
+            let new_block = if self.current_block.block_slot + 1 >= self.segment_size {
+                let segment = self.current_segment() + self.current_block.block_slot + 1;
+                self.segments.push(segment);
+                // Segments are many, so only the current one is kept open.
+                // Flushes sync only the current one.
+                file_manager.sync(&self.current_block.file_name())?;
+                self.last_saved_log_sequence_number = self.latest_log_sequence_number;
+                file_manager.close_file(&self.current_block.file_name());
+                file_manager.append_block(&segment_file_name(&self.log_file, segment))?
+            } else {
+                file_manager.append_block(&self.current_block.file_name())?
+            };
+            self.log_page
+                .set_i32(0, (file_manager.block_size - BLOCK_CHECKSUM_SIZE) as i32);
             file_manager.write(&new_block, &mut self.log_page)?;
             self.current_block = new_block;
+            self.is_current_block_saved = false;
             boundary = self.log_page.get_i32(0) as usize;
         }
 
@@ -90,11 +223,34 @@ impl LogManager {
         let record_position = boundary - record_size;
         self.log_page.set_bytes(record_position, &record_bytes);
         self.log_page.set_i32(0, record_position as i32);
-        self.latest_log_sequence_number += 1;
+        self.latest_log_sequence_number = log_sequence_number(
+            self.current_segment() + self.current_block.block_slot,
+            record_position,
+            file_manager.block_size,
+        );
+        match log_record {
+            LogRecord::Commit(transaction_id) | LogRecord::Rollback(transaction_id) => {
+                self.active_transactions.remove(transaction_id);
+            }
+            LogRecord::SetI32(transaction_id, ..)
+            | LogRecord::SetString(transaction_id, ..)
+            | LogRecord::Savepoint(transaction_id, _) => {
+                self.active_transactions
+                    .entry(*transaction_id)
+                    .or_insert(self.latest_log_sequence_number);
+            }
+            _ => {}
+        }
         Ok(self.latest_log_sequence_number)
     }
 
-    // Flushes the log records up to the least_log_sequence_number.
+    /// Returns the LSN up to which the log is on the disk.
+    pub fn get_last_saved_log_sequence_number(&self) -> usize {
+        self.last_saved_log_sequence_number
+    }
+
+    // Flushes the log records up to the least_log_sequence_number, and forces
+    // them to the disk.
     pub fn flush(&mut self, least_log_sequence_number: usize) -> Result<()> {
         if least_log_sequence_number >= self.last_saved_log_sequence_number {
             self.do_flush()?;
@@ -102,54 +258,118 @@ impl LogManager {
         Ok(())
     }
 
-    pub fn get_backward_iter(&mut self) -> Result<BackwardLogIterator> {
-        self.do_flush()?;
+    pub fn get_backward_iter(&mut self) -> Result<BackwardLogIterator<'_>> {
+        // The iterator reads the current block from the file, which does not
+        // need to be on the disk
         let mut file_manager = self.file_manager.lock().unwrap();
+        file_manager.write(&self.current_block, &self.log_page)?;
         let mut page = Page::new(file_manager.block_size);
-        file_manager.read(&self.current_block, &mut page).unwrap();
+        file_manager.read(&self.current_block, &mut page)?;
         Ok(BackwardLogIterator {
             file_manager,
+            log_file: self.log_file.clone(),
+            segments: self.segments.clone(),
             current_block: self.current_block.clone(),
             current_position: self.log_page.get_i32(0) as usize,
             page,
         })
     }
 
+    /// Deletes or archives the segments whose records all precede the LSN,
+    /// except the current segment, and returns how many there were.
+    pub fn truncate(&mut self, log_sequence_number: usize) -> Result<usize> {
+        let mut file_manager = self.file_manager.lock().unwrap();
+        let block_size = file_manager.block_size;
+        let mut num_truncated = 0;
+        // A segment ends where the next one starts
+        while self.segments.len() > 1 && self.segments[1] * block_size <= log_sequence_number {
+            let segment_file = segment_file_name(&self.log_file, self.segments.remove(0));
+            match &self.retention {
+                LogRetention::Delete => file_manager.remove_file(&segment_file)?,
+                LogRetention::Archive(directory) => {
+                    file_manager.move_file(&segment_file, directory)?
+                }
+            }
+            num_truncated += 1;
+        }
+        Ok(num_truncated)
+    }
+
+    fn current_segment(&self) -> usize {
+        *self.segments.last().unwrap()
+    }
+
     fn do_flush(&mut self) -> Result<()> {
-        self.file_manager
-            .lock()
-            .unwrap()
-            .write(&self.current_block, &self.log_page)?;
+        let mut file_manager = self.file_manager.lock().unwrap();
+        file_manager.write(&self.current_block, &self.log_page)?;
+        file_manager.sync(&self.current_block.file_name())?;
         self.last_saved_log_sequence_number = self.latest_log_sequence_number;
+        self.is_current_block_saved = true;
         Ok(())
     }
 }
 
+fn segment_file_name(log_file: &str, segment: usize) -> String {
+    format!("{}.{}", log_file, segment)
+}
+
+// `block_number` counts the blocks of the whole log
+fn log_sequence_number(block_number: usize, record_position: usize, block_size: usize) -> usize {
+    block_number * block_size + (block_size - record_position)
+}
+
 pub struct BackwardLogIterator<'a> {
     file_manager: MutexGuard<'a, FileManager>,
+    log_file: String,
+    // Segments not read yet, and the one being read last
+    segments: Vec<usize>,
     current_position: usize,
     current_block: BlockId,
     page: Page,
 }
 
-impl<'a> Iterator for BackwardLogIterator<'a> {
-    type Item = LogRecord;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_position == self.file_manager.block_size {
-            if let Some(next_block) = self.current_block.get_previous_block() {
-                self.file_manager.read(&next_block, &mut self.page).unwrap();
-                self.current_position = self.page.get_i32(0) as usize;
-                self.current_block = next_block;
-            } else {
-                return None;
-            }
+impl BackwardLogIterator<'_> {
+    /// Returns the next record along with its log sequence number.
+    pub fn next_with_log_sequence_number(&mut self) -> Result<Option<(usize, LogRecord)>> {
+        while self.current_position == self.file_manager.block_size - BLOCK_CHECKSUM_SIZE {
+            let next_block = match self.current_block.get_previous_block() {
+                Some(next_block) => next_block,
+                None => {
+                    // Move on to the last block of the previous segment
+                    let segment_file = self.current_block.file_name();
+                    self.file_manager.close_file(&segment_file);
+                    self.segments.pop();
+                    let Some(&segment) = self.segments.last() else {
+                        return Ok(None);
+                    };
+                    let segment_file = segment_file_name(&self.log_file, segment);
+                    self.file_manager.get_last_block(&segment_file)?
+                }
+            };
+            self.file_manager.read(&next_block, &mut self.page)?;
+            self.current_position = self.page.get_i32(0) as usize;
+            self.current_block = next_block;
         }
 
+        let lsn = log_sequence_number(
+            self.segments.last().unwrap() + self.current_block.block_slot,
+            self.current_position,
+            self.file_manager.block_size,
+        );
         let (bytes, length) = self.page.get_bytes(self.current_position);
         let log_record = LogRecord::from_bytes(bytes);
         self.current_position += length;
-        return Some(log_record);
+        Ok(Some((lsn, log_record)))
+    }
+}
+
+impl<'a> Iterator for BackwardLogIterator<'a> {
+    type Item = Result<LogRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_log_sequence_number()
+            .transpose()
+            .map(|result| result.map(|(_, log_record)| log_record))
     }
 }
 
@@ -185,17 +405,126 @@ mod tests {
         log_manager.flush(lsn)?;
 
         let mut iter = log_manager.get_backward_iter()?;
-        assert_eq!(iter.next(), Some(LogRecord::Commit(2)));
+        assert_eq!(iter.next().transpose()?, Some(LogRecord::Commit(2)));
         assert_eq!(
-            iter.next(),
+            iter.next().transpose()?,
             Some(LogRecord::SetI32(2, block.clone(), 0, 0, 0))
         );
-        assert_eq!(iter.next(), Some(LogRecord::Start(2)));
-        assert_eq!(iter.next(), Some(LogRecord::Commit(1)));
-        assert_eq!(iter.next(), Some(LogRecord::Start(1)));
-        assert_eq!(iter.next(), Some(LogRecord::Commit(0)));
-        assert_eq!(iter.next(), Some(LogRecord::Start(0)));
-        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next().transpose()?, Some(LogRecord::Start(2)));
+        assert_eq!(iter.next().transpose()?, Some(LogRecord::Commit(1)));
+        assert_eq!(iter.next().transpose()?, Some(LogRecord::Start(1)));
+        assert_eq!(iter.next().transpose()?, Some(LogRecord::Commit(0)));
+        assert_eq!(iter.next().transpose()?, Some(LogRecord::Start(0)));
+        assert_eq!(iter.next().transpose()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_sequence_numbers() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, 80)));
+        let mut log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+
+        let mut lsns = vec![];
+        for transaction_id in 0..6 {
+            lsns.push(log_manager.append_record(&LogRecord::Start(transaction_id))?);
+        }
+        assert!(lsns.windows(2).all(|pair| pair[0] < pair[1]));
+        let mut iter = log_manager.get_backward_iter()?;
+        for (transaction_id, &lsn) in lsns.iter().enumerate().rev() {
+            assert_eq!(
+                iter.next_with_log_sequence_number()?,
+                Some((lsn, LogRecord::Start(transaction_id)))
+            );
+        }
+        drop(iter);
+
+        // LSNs continue from the end of the log after a restart
+        let mut log_manager = LogManager::new(file_manager, "log".into())?;
+        assert_eq!(log_manager.get_latest_log_sequence_number(), lsns[5]);
+        assert!(log_manager.append_record(&LogRecord::Start(6))? > lsns[5]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_segments() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir.clone(), 80)));
+        let archive = LogRetention::Archive("archive".into());
+        let mut log_manager =
+            LogManager::with_segments(file_manager.clone(), "log".into(), 2, archive.clone())?;
+
+        let mut lsns = vec![];
+        for transaction_id in 0..40 {
+            lsns.push(log_manager.append_record(&LogRecord::Start(transaction_id))?);
+        }
+        let num_segments = log_manager.segments.len();
+        assert!(num_segments > 2);
+        assert!(temp_dir.join("log.0").exists());
+        assert!(temp_dir.join("log.2").exists());
+
+        // The iterator reads all the segments
+        let records = log_manager
+            .get_backward_iter()?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(records.len(), 40);
+        assert_eq!(records[39], LogRecord::Start(0));
+
+        // Only the segments entirely before the LSN are archived
+        let num_truncated = log_manager.truncate(lsns[20])?;
+        assert!(num_truncated > 0);
+        assert_eq!(log_manager.segments.len(), num_segments - num_truncated);
+        assert!(log_manager.segments[0] * 80 <= lsns[20]);
+        assert!(!temp_dir.join("log.0").exists());
+        assert!(temp_dir.join("archive").join("log.0").exists());
+        let mut iter = log_manager.get_backward_iter()?;
+        let mut lsn = 0;
+        while let Some((log_sequence_number, _)) = iter.next_with_log_sequence_number()? {
+            lsn = log_sequence_number;
+        }
+        assert!(lsn <= lsns[20]);
+        drop(iter);
+
+        // The LSNs continue in the last segment after a restart
+        let mut log_manager =
+            LogManager::with_segments(file_manager, "log".into(), 2, LogRetention::Delete)?;
+        assert_eq!(log_manager.get_latest_log_sequence_number(), lsns[39]);
+        for transaction_id in 40..60 {
+            assert!(log_manager.append_record(&LogRecord::Start(transaction_id))? > lsns[39]);
+        }
+        let last_segment = *log_manager.segments.last().unwrap();
+        log_manager.truncate(last_segment * 80)?;
+        assert_eq!(log_manager.segments, vec![last_segment]);
+        assert_eq!(
+            log_manager.get_backward_iter()?.next().transpose()?,
+            Some(LogRecord::Start(59))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_torn_block() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let file_manager = Arc::new(Mutex::new(FileManager::new(temp_dir, 80)));
+        let mut log_manager = LogManager::new(file_manager.clone(), "log".into())?;
+        let lsn = log_manager.append_record(&LogRecord::Start(0))?;
+        log_manager.flush(lsn)?;
+
+        // The synced block is not written again
+        let block = log_manager.current_block.clone();
+        log_manager.append_record(&LogRecord::Start(1))?;
+        assert_eq!(log_manager.current_block, block.get_next_block());
+
+        // A crash tears the block that was not synced, whose record is lost
+        drop(log_manager.get_backward_iter()?);
+        let faults = file_manager.lock().unwrap().get_faults();
+        faults.lock().unwrap().tear_last_write(0, 10);
+        file_manager.lock().unwrap().drop_unsynced_writes()?;
+        let mut log_manager = LogManager::new(file_manager, "log".into())?;
+        let records = log_manager
+            .get_backward_iter()?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(records, vec![LogRecord::Start(0)]);
         Ok(())
     }
 }
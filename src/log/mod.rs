@@ -1,2 +1,3 @@
+pub mod group_commit;
 pub mod manager;
 pub mod record;
@@ -12,6 +12,16 @@ pub enum LogRecord {
     Rollback(usize),
     SetI32(usize, BlockId, usize, i32, i32),
     SetString(usize, BlockId, usize, String, String),
+    // Marks where a transaction can be rolled back to by the savepoint name
+    Savepoint(usize, String),
+    // Part of the contents of a block from the offset, logged before its
+    // first update since it was written so that a torn write can be redone
+    PageImage(usize, BlockId, usize, Vec<u8>),
+    // Checkpoint written while transactions run: the latest LSN when it
+    // started, the active transactions with their first LSN and the modified
+    // blocks with the LSN of their first update since they were written. A
+    // checkpoint too large for a log block is split into adjacent records.
+    NonQuiescentCheckpoint(usize, usize, Vec<(usize, usize)>, Vec<(BlockId, usize)>),
 }
 
 fn from_ne_bytes_to_usize(bytes: &[u8]) -> usize {
@@ -73,6 +83,46 @@ impl LogRecord {
                 bytes.extend_from_slice(&block_id.to_bytes());
                 bytes
             }
+            LogRecord::Savepoint(transaction_id, name) => {
+                let mut bytes = Vec::new();
+                bytes.push(b'P');
+                bytes.extend_from_slice(&transaction_id.to_ne_bytes());
+                bytes.extend_from_slice(&name.len().to_ne_bytes());
+                bytes.extend_from_slice(name.as_bytes());
+                bytes
+            }
+            LogRecord::PageImage(transaction_id, block_id, offset, contents) => {
+                let mut bytes = Vec::new();
+                bytes.push(b'G');
+                bytes.extend_from_slice(&transaction_id.to_ne_bytes());
+                bytes.extend_from_slice(&offset.to_ne_bytes());
+                bytes.extend_from_slice(&contents.len().to_ne_bytes());
+                bytes.extend_from_slice(contents);
+                bytes.extend_from_slice(&block_id.to_bytes());
+                bytes
+            }
+            LogRecord::NonQuiescentCheckpoint(
+                transaction_id,
+                log_sequence_number,
+                active_transactions,
+                dirty_blocks,
+            ) => {
+                let mut bytes = Vec::new();
+                bytes.push(b'N');
+                bytes.extend_from_slice(&transaction_id.to_ne_bytes());
+                bytes.extend_from_slice(&log_sequence_number.to_ne_bytes());
+                bytes.extend_from_slice(&active_transactions.len().to_ne_bytes());
+                for (active_transaction_id, first_log_sequence_number) in active_transactions {
+                    bytes.extend_from_slice(&active_transaction_id.to_ne_bytes());
+                    bytes.extend_from_slice(&first_log_sequence_number.to_ne_bytes());
+                }
+                bytes.extend_from_slice(&dirty_blocks.len().to_ne_bytes());
+                for (block, first_log_sequence_number) in dirty_blocks {
+                    bytes.extend_from_slice(&first_log_sequence_number.to_ne_bytes());
+                    bytes.extend_from_slice(&block.to_bytes());
+                }
+                bytes
+            }
         }
     }
 
@@ -88,6 +138,19 @@ impl LogRecord {
             LogRecord::Rollback(transaction_id) => *transaction_id,
             LogRecord::SetI32(transaction_id, _, _, _, _) => *transaction_id,
             LogRecord::SetString(transaction_id, _, _, _, _) => *transaction_id,
+            LogRecord::Savepoint(transaction_id, _) => *transaction_id,
+            LogRecord::PageImage(transaction_id, _, _, _) => *transaction_id,
+            LogRecord::NonQuiescentCheckpoint(transaction_id, _, _, _) => *transaction_id,
+        }
+    }
+
+    /// Returns the block updated by the record, if it is an update.
+    pub fn get_block(&self) -> Option<&BlockId> {
+        match self {
+            LogRecord::SetI32(_, block, _, _, _)
+            | LogRecord::SetString(_, block, _, _, _)
+            | LogRecord::PageImage(_, block, _, _) => Some(block),
+            _ => None,
         }
     }
 
@@ -138,6 +201,52 @@ impl LogRecord {
                 );
                 LogRecord::SetString(transaction_id, block, offset, old_string, new_string)
             }
+            'P' => {
+                let transaction_id = from_ne_bytes_to_usize(&current_position[1..9]);
+                let name_length = from_ne_bytes_to_usize(&current_position[9..17]);
+                let name =
+                    String::from_utf8(current_position[17..17 + name_length].to_vec()).unwrap();
+                LogRecord::Savepoint(transaction_id, name)
+            }
+            'G' => {
+                let transaction_id = from_ne_bytes_to_usize(&current_position[1..9]);
+                let offset = from_ne_bytes_to_usize(&current_position[9..17]);
+                let length = from_ne_bytes_to_usize(&current_position[17..25]);
+                let contents = current_position[25..25 + length].to_vec();
+                let (_, block) = BlockId::from_bytes(&current_position[25 + length..]);
+                LogRecord::PageImage(transaction_id, block, offset, contents)
+            }
+            'N' => {
+                let transaction_id = from_ne_bytes_to_usize(&current_position[1..9]);
+                let log_sequence_number = from_ne_bytes_to_usize(&current_position[9..17]);
+                let num_active_transactions = from_ne_bytes_to_usize(&current_position[17..25]);
+                let mut position = 25;
+                let mut active_transactions = vec![];
+                for _ in 0..num_active_transactions {
+                    active_transactions.push((
+                        from_ne_bytes_to_usize(&current_position[position..position + 8]),
+                        from_ne_bytes_to_usize(&current_position[position + 8..position + 16]),
+                    ));
+                    position += 16;
+                }
+                let num_dirty_blocks =
+                    from_ne_bytes_to_usize(&current_position[position..position + 8]);
+                position += 8;
+                let mut dirty_blocks = vec![];
+                for _ in 0..num_dirty_blocks {
+                    let first_log_sequence_number =
+                        from_ne_bytes_to_usize(&current_position[position..position + 8]);
+                    let (length, block) = BlockId::from_bytes(&current_position[position + 8..]);
+                    dirty_blocks.push((block, first_log_sequence_number));
+                    position += 8 + length;
+                }
+                LogRecord::NonQuiescentCheckpoint(
+                    transaction_id,
+                    log_sequence_number,
+                    active_transactions,
+                    dirty_blocks,
+                )
+            }
             _ => panic!("Invalid log record"),
         }
     }
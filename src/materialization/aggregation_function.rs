@@ -1,6 +1,14 @@
 use enum_dispatch::enum_dispatch;
 
-use crate::{errors::TransactionError, record::field::Value, scan::Scan};
+use crate::{
+    errors::TransactionError,
+    parser::expression::Expression,
+    record::{
+        field::{Spec, Value},
+        schema::Schema,
+    },
+    scan::Scan,
+};
 
 use super::{
     avg_function::AvgFn, count_function::CountFn, max_function::MaxFn, min_function::MinFn,
@@ -22,5 +30,45 @@ pub trait AggregationFnControl {
     fn process_first(&mut self, scan: &mut Scan) -> Result<(), TransactionError>;
     fn process_next(&mut self, scan: &mut Scan) -> Result<(), TransactionError>;
     fn get_field_name(&self) -> &str;
+    fn set_field_name(&mut self, field_name: &str);
+    // None for COUNT(*)
+    fn get_argument(&self) -> Option<&Expression>;
+    fn get_argument_mut(&mut self) -> Option<&mut Expression>;
     fn get_value(&self) -> Option<Value>;
 }
+
+impl AggregationFn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregationFn::Max(_) => "MAX",
+            AggregationFn::Count(_) => "COUNT",
+            AggregationFn::Min(_) => "MIN",
+            AggregationFn::Sum(_) => "SUM",
+            AggregationFn::Avg(_) => "AVG",
+        }
+    }
+
+    /// Checks the type of the argument over records of `schema` and returns
+    /// the spec of the result.
+    pub fn output_spec(&self, schema: &Schema) -> Result<Spec, String> {
+        let argument_spec = match self.get_argument() {
+            Some(Expression::NullConstant) | None => None,
+            Some(argument) => Some(argument.infer_spec(schema)?),
+        };
+        match (self, argument_spec) {
+            (AggregationFn::Max(_) | AggregationFn::Min(_), Some(spec)) => Ok(spec),
+            (AggregationFn::Sum(_) | AggregationFn::Avg(_), Some(Spec::VarChar(_))) => {
+                Err(format!("argument of {} must be an integer", self.name()))
+            }
+            _ => Ok(Spec::I32),
+        }
+    }
+
+    /// Value of the function over no records.
+    pub fn get_empty_value(&self) -> Value {
+        match self {
+            AggregationFn::Count(_) => Value::I32(0),
+            _ => Value::Null,
+        }
+    }
+}
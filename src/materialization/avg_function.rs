@@ -1,7 +1,5 @@
 use crate::{
-    errors::TransactionError,
-    record::field::Value,
-    scan::{Scan, ScanControl},
+    errors::TransactionError, parser::expression::Expression, record::field::Value, scan::Scan,
 };
 
 use super::aggregation_function::AggregationFnControl;
@@ -10,15 +8,24 @@ use super::aggregation_function::AggregationFnControl;
 pub struct AvgFn {
     sum: i64,
     count: i32,
-    field_name: String,
+    argument: Expression,
+    field_name: String, // Name of the output field
 }
 
 impl AvgFn {
     pub fn new(field_name: &str) -> Self {
+        Self::with_argument(Expression::Field(field_name.to_string()))
+    }
+
+    /// The output field is named after the argument if it is a field, and
+    /// after the function otherwise.
+    pub fn with_argument(argument: Expression) -> Self {
+        let field_name = argument.try_get_field().unwrap_or("avg").to_string();
         Self {
             sum: 0,
             count: 0,
-            field_name: field_name.to_string(),
+            argument,
+            field_name,
         }
     }
 }
@@ -27,25 +34,25 @@ impl AggregationFnControl for AvgFn {
     fn process_first(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
         self.sum = 0;
         self.count = 0;
-        match scan.get_value(&self.field_name)? {
+        match self.argument.evaluate(scan)? {
             Value::I32(i) => {
                 self.sum = i as i64;
                 self.count = 1;
             }
             Value::Null => {}
-            _ => panic!("Field {} is not an integer", self.field_name),
+            _ => panic!("Type mismatch in AVG"),
         }
         Ok(())
     }
 
     fn process_next(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
-        match scan.get_value(&self.field_name)? {
+        match self.argument.evaluate(scan)? {
             Value::I32(i) => {
                 self.sum += i as i64;
                 self.count += 1;
             }
             Value::Null => {}
-            _ => panic!("Field {} is not an integer", self.field_name),
+            _ => panic!("Type mismatch in AVG"),
         }
         Ok(())
     }
@@ -54,6 +61,18 @@ impl AggregationFnControl for AvgFn {
         &self.field_name
     }
 
+    fn set_field_name(&mut self, field_name: &str) {
+        self.field_name = field_name.to_string();
+    }
+
+    fn get_argument(&self) -> Option<&Expression> {
+        Some(&self.argument)
+    }
+
+    fn get_argument_mut(&mut self) -> Option<&mut Expression> {
+        Some(&mut self.argument)
+    }
+
     fn get_value(&self) -> Option<Value> {
         if self.count > 0 {
             Some(Value::I32((self.sum / self.count as i64) as i32))
@@ -1,7 +1,5 @@
 use crate::{
-    errors::TransactionError,
-    record::field::Value,
-    scan::{Scan, ScanControl},
+    errors::TransactionError, parser::expression::Expression, record::field::Value, scan::Scan,
 };
 
 use super::aggregation_function::AggregationFnControl;
@@ -9,14 +7,39 @@ use super::aggregation_function::AggregationFnControl;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CountFn {
     count: i32,
-    field_name: String,
+    argument: Option<Expression>, // None counts every record, as in COUNT(*)
+    field_name: String,           // Name of the output field
 }
 
 impl CountFn {
     pub fn new(field_name: &str) -> Self {
+        Self::with_argument(Expression::Field(field_name.to_string()))
+    }
+
+    /// The output field is named after the argument if it is a field, and
+    /// after the function otherwise.
+    pub fn with_argument(argument: Expression) -> Self {
+        let field_name = argument.try_get_field().unwrap_or("count").to_string();
+        Self {
+            count: 0,
+            argument: Some(argument),
+            field_name,
+        }
+    }
+
+    /// `COUNT(*)`, which counts records regardless of NULLs.
+    pub fn count_records() -> Self {
         Self {
             count: 0,
-            field_name: field_name.to_string(),
+            argument: None,
+            field_name: "count".to_string(),
+        }
+    }
+
+    fn counts(&self, scan: &mut Scan) -> Result<bool, TransactionError> {
+        match &self.argument {
+            Some(argument) => Ok(argument.evaluate(scan)? != Value::Null),
+            None => Ok(true),
         }
     }
 }
@@ -24,16 +47,14 @@ impl CountFn {
 impl AggregationFnControl for CountFn {
     fn process_first(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
         self.count = 0;
-        let value = scan.get_value(&self.field_name)?;
-        if value != Value::Null {
+        if self.counts(scan)? {
             self.count = 1;
         }
         Ok(())
     }
 
     fn process_next(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
-        let value = scan.get_value(&self.field_name)?;
-        if value != Value::Null {
+        if self.counts(scan)? {
             self.count += 1;
         }
         Ok(())
@@ -43,6 +64,18 @@ impl AggregationFnControl for CountFn {
         &self.field_name
     }
 
+    fn set_field_name(&mut self, field_name: &str) {
+        self.field_name = field_name.to_string();
+    }
+
+    fn get_argument(&self) -> Option<&Expression> {
+        self.argument.as_ref()
+    }
+
+    fn get_argument_mut(&mut self) -> Option<&mut Expression> {
+        self.argument.as_mut()
+    }
+
     fn get_value(&self) -> Option<Value> {
         Some(Value::I32(self.count))
     }
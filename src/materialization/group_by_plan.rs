@@ -15,6 +15,9 @@ use super::{
     sort_plan::SortPlan,
 };
 
+/// Groups records by the values of `group_fields` and computes the
+/// aggregation functions over each group. Without group fields, all records
+/// form a single group, which is output even if there are no records.
 #[derive(Clone)]
 pub struct GroupByPlan {
     sort_plan: Box<Plan>,
@@ -35,8 +38,10 @@ impl GroupByPlan {
             schema.add_field(field_name, &plan.schema().get_field_spec(field_name));
         }
         for aggregation_fn in &aggregation_functions {
-            let field_name = aggregation_fn.get_field_name();
-            schema.add_field(&field_name, &plan.schema().get_field_spec(field_name));
+            let spec = aggregation_fn
+                .output_spec(plan.schema())
+                .unwrap_or_else(|e| panic!("Invalid aggregation function: {}", e));
+            schema.add_field(aggregation_fn.get_field_name(), &spec);
         }
 
         let comparator = Arc::new(RecordComparator::new(&group_fields));
@@ -17,6 +17,9 @@ pub struct GroupByScan {
     aggregation_functions: Vec<AggregationFn>,
     more_group: bool,
     group_values: HashMap<String, Value>,
+    // Without group fields, a single group is output even without records
+    pending_empty_group: bool,
+    in_empty_group: bool,
 }
 
 impl GroupByScan {
@@ -31,6 +34,8 @@ impl GroupByScan {
             aggregation_functions: aggregation_functions.clone(),
             more_group: false,
             group_values: HashMap::new(),
+            pending_empty_group: false,
+            in_empty_group: false,
         };
         group_by_scan.before_first()?;
         Ok(group_by_scan)
@@ -41,12 +46,16 @@ impl ScanControl for GroupByScan {
     fn before_first(&mut self) -> Result<(), TransactionError> {
         self.scan.before_first()?;
         self.more_group = self.scan.next()?;
+        self.pending_empty_group = !self.more_group && self.group_fields.is_empty();
+        self.in_empty_group = false;
         Ok(())
     }
 
     fn next(&mut self) -> Result<bool, TransactionError> {
         if !self.more_group {
-            return Ok(false);
+            self.in_empty_group = self.pending_empty_group;
+            self.pending_empty_group = false;
+            return Ok(self.in_empty_group);
         }
 
         for aggregation_fn in &mut self.aggregation_functions {
@@ -101,6 +110,9 @@ impl ScanControl for GroupByScan {
         } else {
             for aggregation_fn in &self.aggregation_functions {
                 if aggregation_fn.get_field_name() == field_name {
+                    if self.in_empty_group {
+                        return Ok(aggregation_fn.get_empty_value());
+                    }
                     return Ok(aggregation_fn.get_value().unwrap_or(Value::Null));
                 }
             }
@@ -132,6 +144,7 @@ mod tests {
             avg_function::AvgFn, count_function::CountFn, max_function::MaxFn, min_function::MinFn,
             record_comparator::RecordComparator, sum_function::SumFn, temp_table::TempTable,
         },
+        parser::expression::Expression,
         record::schema::Schema,
     };
 
@@ -347,4 +360,62 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_group_by_scan_without_group_fields() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let mut schema = Schema::new();
+        schema.add_i32_field("A");
+        schema.add_i32_field("B");
+
+        let double_a = Expression::Mul(
+            Box::new(Expression::Field("A".to_string())),
+            Box::new(Expression::I32Constant(2)),
+        );
+        let mut sum = AggregationFn::from(SumFn::with_argument(double_a));
+        sum.set_field_name("S");
+        let aggregation_functions = vec![
+            AggregationFn::from(CountFn::count_records()),
+            AggregationFn::from(CountFn::new("B")),
+            sum,
+        ];
+
+        // All records form one group, and so does an empty input
+        for num_records in [10, 0] {
+            let temp_table = TempTable::new(tx.clone(), &schema);
+            {
+                let mut scan = temp_table.open()?;
+                scan.before_first()?;
+                for i in 0..num_records {
+                    scan.insert()?;
+                    scan.set_i32("A", i)?;
+                    if i % 2 == 0 {
+                        scan.set_value("B", &Value::Null)?;
+                    } else {
+                        scan.set_i32("B", i)?;
+                    }
+                }
+            }
+
+            let sort_scan =
+                SortScan::new(vec![temp_table], Arc::new(RecordComparator::new(&vec![])))?;
+            let mut group_by_scan = GroupByScan::new(sort_scan, &vec![], &aggregation_functions)?;
+            for _ in 0..2 {
+                group_by_scan.before_first()?;
+                assert!(group_by_scan.next()?);
+                assert_eq!(group_by_scan.get_i32("count")?, Some(num_records));
+                assert_eq!(group_by_scan.get_i32("B")?, Some(num_records / 2));
+                let expected_sum = (num_records > 0).then_some(num_records * (num_records - 1));
+                assert_eq!(group_by_scan.get_i32("S")?, expected_sum);
+                assert!(!group_by_scan.next()?);
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -1,7 +1,5 @@
 use crate::{
-    errors::TransactionError,
-    record::field::Value,
-    scan::{Scan, ScanControl},
+    errors::TransactionError, parser::expression::Expression, record::field::Value, scan::Scan,
 };
 
 use super::aggregation_function::AggregationFnControl;
@@ -9,21 +7,30 @@ use super::aggregation_function::AggregationFnControl;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MaxFn {
     max_value: Option<Value>,
-    field_name: String,
+    argument: Expression,
+    field_name: String, // Name of the output field
 }
 
 impl MaxFn {
     pub fn new(field_name: &str) -> Self {
+        Self::with_argument(Expression::Field(field_name.to_string()))
+    }
+
+    /// The output field is named after the argument if it is a field, and
+    /// after the function otherwise.
+    pub fn with_argument(argument: Expression) -> Self {
+        let field_name = argument.try_get_field().unwrap_or("max").to_string();
         MaxFn {
             max_value: None,
-            field_name: field_name.to_string(),
+            argument,
+            field_name,
         }
     }
 }
 
 impl AggregationFnControl for MaxFn {
     fn process_first(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
-        let value = scan.get_value(&self.field_name)?;
+        let value = self.argument.evaluate(scan)?;
         if value == Value::Null {
             self.max_value = None;
         } else {
@@ -33,7 +40,7 @@ impl AggregationFnControl for MaxFn {
     }
 
     fn process_next(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
-        let new_value = scan.get_value(&self.field_name)?;
+        let new_value = self.argument.evaluate(scan)?;
         if new_value == Value::Null {
             return Ok(());
         }
@@ -51,6 +58,18 @@ impl AggregationFnControl for MaxFn {
         &self.field_name
     }
 
+    fn set_field_name(&mut self, field_name: &str) {
+        self.field_name = field_name.to_string();
+    }
+
+    fn get_argument(&self) -> Option<&Expression> {
+        Some(&self.argument)
+    }
+
+    fn get_argument_mut(&mut self) -> Option<&mut Expression> {
+        Some(&mut self.argument)
+    }
+
     fn get_value(&self) -> Option<Value> {
         self.max_value.clone()
     }
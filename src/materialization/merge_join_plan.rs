@@ -12,7 +12,9 @@ use crate::{
 };
 
 use super::{
-    merge_join_scan::MergeJoinScan, record_comparator::RecordComparator, sort_plan::SortPlan,
+    merge_join_scan::MergeJoinScan,
+    record_comparator::{NullOrdering, RecordComparator, SortDirection, SortKey},
+    sort_plan::SortPlan,
 };
 
 #[derive(Clone)]
@@ -22,6 +24,7 @@ pub struct MergeJoinPlan {
     schema: Schema,
     field_name1: String,
     field_name2: String,
+    comparator: Arc<RecordComparator>,
 }
 
 impl MergeJoinPlan {
@@ -32,11 +35,42 @@ impl MergeJoinPlan {
         field_name1: &str,
         field_name2: &str,
     ) -> Self {
-        let comparator = Arc::new(RecordComparator::new(&vec![field_name1.to_string()]));
-        let p1 = SortPlan::new(p1, tx.clone(), comparator);
+        MergeJoinPlan::new_with_order(
+            tx,
+            p1,
+            p2,
+            field_name1,
+            field_name2,
+            SortDirection::Asc,
+            None,
+        )
+    }
 
-        let comparator = Arc::new(RecordComparator::new(&vec![field_name2.to_string()]));
-        let p2 = SortPlan::new(p2, tx.clone(), comparator);
+    /// Creates a merge join whose inputs are sorted by their join fields in
+    /// the given direction and NULL ordering, which both share so that they
+    /// can be merged.
+    pub fn new_with_order(
+        tx: Arc<Mutex<Transaction>>,
+        p1: Plan,
+        p2: Plan,
+        field_name1: &str,
+        field_name2: &str,
+        direction: SortDirection,
+        nulls: Option<NullOrdering>,
+    ) -> Self {
+        let key1 = SortKey::new(field_name1, direction, nulls);
+        let key2 = SortKey::new(field_name2, direction, nulls);
+        let field_name1 = field_name1.to_string();
+        let field_name2 = field_name2.to_string();
+
+        let comparator = Arc::new(RecordComparator::new_with_keys(vec![key1]));
+        let p1 = SortPlan::new(p1, tx.clone(), comparator.clone());
+
+        let p2 = SortPlan::new(
+            p2,
+            tx.clone(),
+            Arc::new(RecordComparator::new_with_keys(vec![key2])),
+        );
 
         let mut schema = Schema::new();
         schema.add_all(&p1.schema());
@@ -45,8 +79,9 @@ impl MergeJoinPlan {
             p1,
             p2,
             schema,
-            field_name1: field_name1.to_string(),
-            field_name2: field_name2.to_string(),
+            field_name1,
+            field_name2,
+            comparator,
         }
     }
 }
@@ -84,6 +119,7 @@ impl PlanControl for MergeJoinPlan {
                 s2,
                 &self.field_name1,
                 &self.field_name2,
+                self.comparator.clone(),
             )?)),
             _ => panic!("Expected SortScan"),
         }
@@ -97,6 +133,7 @@ mod tests {
     use crate::{
         db::SimpleDB,
         errors::TransactionError,
+        materialization::record_comparator::SortDirection,
         plan::{table_plan::TablePlan, Plan, PlanControl},
         record::schema::Schema,
         scan::ScanControl,
@@ -174,4 +211,62 @@ mod tests {
         assert_eq!(expected_values, actual_values);
         Ok(())
     }
+
+    #[test]
+    fn test_merge_join_plan_descending() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let mut schema1 = Schema::new();
+        schema1.add_i32_field("A");
+        let mut schema2 = Schema::new();
+        schema2.add_i32_field("B");
+
+        db.metadata_manager
+            .lock()
+            .unwrap()
+            .create_table("test_table1", &schema1, tx.clone())?;
+        let mut p1 = TablePlan::new(tx.clone(), "test_table1", db.metadata_manager.clone())?;
+        {
+            let mut table_scan = p1.open(tx.clone())?;
+            for i in 0..10 {
+                table_scan.insert()?;
+                table_scan.set_i32("A", i)?;
+            }
+        }
+
+        db.metadata_manager
+            .lock()
+            .unwrap()
+            .create_table("test_table2", &schema2, tx.clone())?;
+        let mut p2 = TablePlan::new(tx.clone(), "test_table2", db.metadata_manager.clone())?;
+        {
+            let mut table_scan = p2.open(tx.clone())?;
+            for i in (5..15).rev() {
+                table_scan.insert()?;
+                table_scan.set_i32("B", i)?;
+            }
+        }
+
+        let mut merge_join_plan = MergeJoinPlan::new_with_order(
+            tx.clone(),
+            Plan::from(p1),
+            Plan::from(p2),
+            "A",
+            "B",
+            SortDirection::Desc,
+            None,
+        );
+        let mut merge_join_scan = merge_join_plan.open(tx.clone())?;
+        for i in (5..10).rev() {
+            assert!(merge_join_scan.next()?);
+            assert_eq!(merge_join_scan.get_i32("A")?, Some(i));
+            assert_eq!(merge_join_scan.get_i32("B")?, Some(i));
+        }
+        assert!(!merge_join_scan.next()?);
+        Ok(())
+    }
 }
@@ -1,12 +1,16 @@
+use std::sync::Arc;
+
 use crate::{errors::TransactionError, record::field::Value, scan::ScanControl};
 
-use super::sort_scan::SortScan;
+use super::{record_comparator::RecordComparator, sort_scan::SortScan};
 
 pub struct MergeJoinScan {
     s1: SortScan,
     s2: SortScan,
     field_name1: String,
     field_name2: String,
+    // Compares join values in the order both inputs are sorted in
+    comparator: Arc<RecordComparator>,
     join_value: Option<Value>,
 }
 
@@ -16,12 +20,14 @@ impl MergeJoinScan {
         s2: SortScan,
         field_name1: &str,
         field_name2: &str,
+        comparator: Arc<RecordComparator>,
     ) -> Result<Self, TransactionError> {
         let mut scan = MergeJoinScan {
             s1,
             s2,
             field_name1: field_name1.to_string(),
             field_name2: field_name2.to_string(),
+            comparator,
             join_value: None,
         };
         scan.before_first()?;
@@ -50,7 +56,10 @@ impl ScanControl for MergeJoinScan {
         while has_next1 && has_next2 {
             let value1 = self.s1.get_value(&self.field_name1)?;
             let value2 = self.s2.get_value(&self.field_name2)?;
-            match value1.cmp(&value2) {
+            match self
+                .comparator
+                .compare_values(std::slice::from_ref(&value1), std::slice::from_ref(&value2))
+            {
                 std::cmp::Ordering::Less => {
                     has_next1 = self.s1.next()?;
                 }
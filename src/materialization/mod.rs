@@ -9,7 +9,16 @@ pub mod merge_join_plan;
 pub mod merge_join_scan;
 pub mod min_function;
 pub mod record_comparator;
+pub mod recursive_plan;
+pub mod set_operation_plan;
+pub mod set_operation_scan;
 pub mod sort_plan;
 pub mod sort_scan;
 pub mod sum_function;
 pub mod temp_table;
+pub mod top_n_plan;
+pub mod top_n_scan;
+pub mod window_function;
+pub mod window_plan;
+pub mod window_scan;
+pub mod working_table_plan;
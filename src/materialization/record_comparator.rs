@@ -1,27 +1,164 @@
 use std::cmp::Ordering;
 
-use crate::scan::{Scan, ScanControl};
+use crate::{
+    record::field::Value,
+    scan::{Scan, ScanControl},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullOrdering {
+    First,
+    Last,
+}
+
+impl SortDirection {
+    /// NULLs are treated as larger than any other value, so they come last in
+    /// ascending order and first in descending order unless stated otherwise.
+    pub fn default_null_ordering(&self) -> NullOrdering {
+        match self {
+            SortDirection::Asc => NullOrdering::Last,
+            SortDirection::Desc => NullOrdering::First,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    pub field_name: String,
+    pub direction: SortDirection,
+    pub nulls: NullOrdering,
+}
+
+impl SortKey {
+    pub fn new(field_name: &str, direction: SortDirection, nulls: Option<NullOrdering>) -> Self {
+        SortKey {
+            field_name: field_name.to_string(),
+            direction,
+            nulls: nulls.unwrap_or(direction.default_null_ordering()),
+        }
+    }
+
+    pub fn ascending(field_name: &str) -> Self {
+        SortKey::new(field_name, SortDirection::Asc, None)
+    }
+
+    fn compare(&self, v1: &Value, v2: &Value) -> Ordering {
+        let null_ordering = match self.nulls {
+            NullOrdering::First => Ordering::Less,
+            NullOrdering::Last => Ordering::Greater,
+        };
+        match (v1, v2) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => null_ordering,
+            (_, Value::Null) => null_ordering.reverse(),
+            _ => match self.direction {
+                SortDirection::Asc => v1.cmp(v2),
+                SortDirection::Desc => v1.cmp(v2).reverse(),
+            },
+        }
+    }
+}
 
 pub struct RecordComparator {
-    field_names: Vec<String>,
+    keys: Vec<SortKey>,
 }
 
 impl RecordComparator {
+    /// Creates a comparator sorting the given fields in ascending order.
     pub fn new(field_names: &Vec<String>) -> Self {
-        RecordComparator {
-            field_names: field_names.clone(),
-        }
+        RecordComparator::new_with_keys(
+            field_names
+                .iter()
+                .map(|field_name| SortKey::ascending(field_name))
+                .collect(),
+        )
+    }
+
+    pub fn new_with_keys(keys: Vec<SortKey>) -> Self {
+        RecordComparator { keys }
+    }
+
+    pub fn get_keys(&self) -> &[SortKey] {
+        &self.keys
     }
 
     pub fn compare(&self, s1: &mut Scan, s2: &mut Scan) -> Ordering {
-        for field_name in &self.field_names {
-            let v1 = s1.get_value(field_name).unwrap();
-            let v2 = s2.get_value(field_name).unwrap();
-            let cmp = v1.cmp(&v2);
+        for key in &self.keys {
+            let v1 = s1.get_value(&key.field_name).unwrap();
+            let v2 = s2.get_value(&key.field_name).unwrap();
+            let cmp = key.compare(&v1, &v2);
             if cmp != std::cmp::Ordering::Equal {
                 return cmp;
             }
         }
         Ordering::Equal
     }
+
+    /// Compares two in-memory sort keys whose values are aligned with the
+    /// comparator's keys.
+    pub fn compare_values(&self, k1: &[Value], k2: &[Value]) -> Ordering {
+        for ((key, v1), v2) in self.keys.iter().zip(k1.iter()).zip(k2.iter()) {
+            let cmp = key.compare(v1, v2);
+            if cmp != std::cmp::Ordering::Equal {
+                return cmp;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_values() {
+        let asc = RecordComparator::new(&vec!["A".to_string()]);
+        let desc =
+            RecordComparator::new_with_keys(vec![SortKey::new("A", SortDirection::Desc, None)]);
+        let desc_nulls_last = RecordComparator::new_with_keys(vec![SortKey::new(
+            "A",
+            SortDirection::Desc,
+            Some(NullOrdering::Last),
+        )]);
+
+        let one = [Value::I32(1)];
+        let two = [Value::I32(2)];
+        let null = [Value::Null];
+
+        assert_eq!(asc.compare_values(&one, &two), Ordering::Less);
+        assert_eq!(asc.compare_values(&null, &two), Ordering::Greater);
+        assert_eq!(desc.compare_values(&one, &two), Ordering::Greater);
+        assert_eq!(desc.compare_values(&null, &two), Ordering::Less);
+        assert_eq!(
+            desc_nulls_last.compare_values(&one, &two),
+            Ordering::Greater
+        );
+        assert_eq!(
+            desc_nulls_last.compare_values(&null, &two),
+            Ordering::Greater
+        );
+        assert_eq!(
+            desc_nulls_last.compare_values(&null, &null),
+            Ordering::Equal
+        );
+
+        let multi = RecordComparator::new_with_keys(vec![
+            SortKey::ascending("A"),
+            SortKey::new("B", SortDirection::Desc, None),
+        ]);
+        assert_eq!(
+            multi.compare_values(
+                &[Value::I32(1), Value::String("a".to_string())],
+                &[Value::I32(1), Value::String("b".to_string())]
+            ),
+            Ordering::Greater
+        );
+    }
 }
@@ -0,0 +1,170 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    errors::TransactionError,
+    plan::{union_all_plan::set_operation_schema, Plan, PlanControl},
+    record::{field::Value, schema::Schema},
+    scan::{Scan, ScanControl},
+    tx::transaction::Transaction,
+};
+
+use super::{temp_table::TempTable, working_table_plan::WorkingTable};
+
+/// Maximum number of times the recursive part of a query is evaluated.
+pub const MAX_RECURSION_ITERATIONS: usize = 1000;
+
+/// Evaluates `anchor UNION [ALL] recursive` for a recursive common table.
+///
+/// The recursive plan reads the common table through a
+/// [`WorkingTablePlan`](super::working_table_plan::WorkingTablePlan) bound to
+/// `working_table`. Evaluation is semi-naive: each iteration only sees the
+/// records produced by the previous one, and stops once an iteration produces
+/// no records. With `UNION`, records that were already produced are dropped,
+/// which also ends the recursion on cyclic data. `UNION ALL` keeps every
+/// record, so cycles are only stopped by [`MAX_RECURSION_ITERATIONS`].
+#[derive(Clone)]
+pub struct RecursivePlan {
+    name: String,
+    anchor: Box<Plan>,
+    recursive: Box<Plan>,
+    anchor_fields: Vec<String>,
+    recursive_fields: Vec<String>,
+    distinct: bool,
+    working_table: WorkingTable,
+    schema: Schema,
+}
+
+impl RecursivePlan {
+    /// `anchor_fields` and `recursive_fields` list the output fields of each
+    /// plan in the order they are matched with each other.
+    pub fn new(
+        name: &str,
+        anchor: Plan,
+        recursive: Plan,
+        anchor_fields: Vec<String>,
+        recursive_fields: Vec<String>,
+        distinct: bool,
+        working_table: WorkingTable,
+    ) -> Self {
+        let schema = set_operation_schema(
+            anchor.schema(),
+            &anchor_fields,
+            recursive.schema(),
+            &recursive_fields,
+        );
+        RecursivePlan {
+            name: name.to_string(),
+            anchor: Box::new(anchor),
+            recursive: Box::new(recursive),
+            anchor_fields,
+            recursive_fields,
+            distinct,
+            working_table,
+            schema,
+        }
+    }
+
+    /// Copies the records of `src` into a new working table and into
+    /// `result_scan`, returning the new working table and the number of records
+    /// copied.
+    fn copy_records(
+        &self,
+        tx: Arc<Mutex<Transaction>>,
+        src: &mut Scan,
+        src_fields: &[String],
+        result_scan: &mut Scan,
+        seen: &mut BTreeSet<Vec<Value>>,
+    ) -> Result<(TempTable, usize), TransactionError> {
+        let working_table = TempTable::new(tx, &self.schema);
+        let mut working_scan = Scan::from(working_table.open()?);
+        let mut num_records = 0;
+        while src.next()? {
+            let mut record = vec![];
+            for field_name in src_fields {
+                record.push(src.get_value(field_name)?);
+            }
+            if self.distinct && !seen.insert(record.clone()) {
+                continue;
+            }
+            working_scan.insert()?;
+            result_scan.insert()?;
+            for (field_name, value) in self.anchor_fields.iter().zip(&record) {
+                working_scan.set_value(field_name, value)?;
+                result_scan.set_value(field_name, value)?;
+            }
+            num_records += 1;
+        }
+        Ok((working_table, num_records))
+    }
+}
+
+impl PlanControl for RecursivePlan {
+    fn get_num_accessed_blocks(&self) -> usize {
+        self.anchor.get_num_accessed_blocks() + self.recursive.get_num_accessed_blocks()
+    }
+
+    fn get_num_output_records(&self) -> usize {
+        self.anchor.get_num_output_records() + self.recursive.get_num_output_records()
+    }
+
+    fn num_distinct_values(&self, field_name: &str) -> usize {
+        let index = self
+            .anchor_fields
+            .iter()
+            .position(|f| f == field_name)
+            .unwrap();
+        self.anchor.num_distinct_values(field_name)
+            + self
+                .recursive
+                .num_distinct_values(&self.recursive_fields[index])
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn open(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
+        let result = TempTable::new(tx.clone(), &self.schema);
+        let mut result_scan = Scan::from(result.open()?);
+        let mut seen = BTreeSet::new();
+
+        let mut anchor_scan = self.anchor.open(tx.clone())?;
+        let (mut working_table, mut num_records) = self.copy_records(
+            tx.clone(),
+            &mut anchor_scan,
+            &self.anchor_fields,
+            &mut result_scan,
+            &mut seen,
+        )?;
+        drop(anchor_scan);
+
+        let mut num_iterations = 0;
+        while num_records > 0 {
+            if num_iterations == MAX_RECURSION_ITERATIONS {
+                *self.working_table.lock().unwrap() = None;
+                return Err(TransactionError::RecursionLimitExceeded(
+                    self.name.clone(),
+                    MAX_RECURSION_ITERATIONS,
+                ));
+            }
+            num_iterations += 1;
+
+            *self.working_table.lock().unwrap() = Some(working_table);
+            let mut recursive_scan = self.recursive.open(tx.clone())?;
+            (working_table, num_records) = self.copy_records(
+                tx.clone(),
+                &mut recursive_scan,
+                &self.recursive_fields,
+                &mut result_scan,
+                &mut seen,
+            )?;
+        }
+        *self.working_table.lock().unwrap() = None;
+
+        result_scan.before_first()?;
+        Ok(result_scan)
+    }
+}
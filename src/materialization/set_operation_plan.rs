@@ -0,0 +1,185 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::TransactionError,
+    parser::statement::SetOperator,
+    plan::{union_all_plan::set_operation_schema, Plan, PlanControl},
+    record::schema::Schema,
+    scan::Scan,
+    tx::transaction::Transaction,
+};
+
+use super::{
+    record_comparator::RecordComparator, set_operation_scan::SetOperationScan, sort_plan::SortPlan,
+};
+
+/// Computes `UNION`, `INTERSECT` or `EXCEPT` of two plans by sorting both on
+/// all of their fields and merging the sorted records.
+///
+/// `UNION ALL` needs no sorting and is handled by
+/// [`UnionAllPlan`](crate::plan::union_all_plan::UnionAllPlan) instead.
+#[derive(Clone)]
+pub struct SetOperationPlan {
+    operator: SetOperator,
+    p1: SortPlan,
+    p2: SortPlan,
+    fields1: Vec<String>,
+    fields2: Vec<String>,
+    schema: Schema,
+    comparator: Arc<RecordComparator>,
+}
+
+impl SetOperationPlan {
+    /// `fields1` and `fields2` list the output fields of each plan in the
+    /// order they are matched with each other.
+    pub fn new(
+        tx: Arc<Mutex<Transaction>>,
+        operator: SetOperator,
+        p1: Plan,
+        p2: Plan,
+        fields1: Vec<String>,
+        fields2: Vec<String>,
+    ) -> Self {
+        assert_ne!(operator, SetOperator::UnionAll);
+        let schema = set_operation_schema(p1.schema(), &fields1, p2.schema(), &fields2);
+
+        let comparator = Arc::new(RecordComparator::new(&fields1));
+        let p1 = SortPlan::new(p1, tx.clone(), comparator.clone());
+        let p2 = SortPlan::new(p2, tx, Arc::new(RecordComparator::new(&fields2)));
+        SetOperationPlan {
+            operator,
+            p1,
+            p2,
+            fields1,
+            fields2,
+            schema,
+            comparator,
+        }
+    }
+}
+
+impl PlanControl for SetOperationPlan {
+    fn get_num_accessed_blocks(&self) -> usize {
+        self.p1.get_num_accessed_blocks() + self.p2.get_num_accessed_blocks()
+    }
+
+    fn get_num_output_records(&self) -> usize {
+        let r1 = self.p1.get_num_output_records();
+        let r2 = self.p2.get_num_output_records();
+        match self.operator {
+            SetOperator::Intersect => std::cmp::min(r1, r2),
+            SetOperator::Except => r1,
+            _ => r1 + r2,
+        }
+    }
+
+    fn num_distinct_values(&self, field_name: &str) -> usize {
+        let index = self.fields1.iter().position(|f| f == field_name).unwrap();
+        let d1 = self.p1.num_distinct_values(field_name);
+        let d2 = self.p2.num_distinct_values(&self.fields2[index]);
+        match self.operator {
+            SetOperator::Intersect => std::cmp::min(d1, d2),
+            SetOperator::Except => d1,
+            _ => d1 + d2,
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn open(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
+        let s1 = self.p1.open(tx.clone())?;
+        let s2 = self.p2.open(tx)?;
+        Ok(Scan::from(SetOperationScan::new(
+            self.operator,
+            s1,
+            s2,
+            self.fields1.clone(),
+            self.fields2.clone(),
+            self.comparator.clone(),
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        db::SimpleDB, plan::table_plan::TablePlan, record::field::Value, scan::ScanControl,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_set_operation_plan() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+        let db = SimpleDB::new(temp_dir, block_size, 8)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let mut schema1 = Schema::new();
+        schema1.add_i32_field("A");
+        let mut schema2 = Schema::new();
+        schema2.add_i32_field("B");
+        {
+            let mut md = db.metadata_manager.lock().unwrap();
+            md.create_table("table1", &schema1, tx.clone())?;
+            md.create_table("table2", &schema2, tx.clone())?;
+        }
+
+        let mut p1 = TablePlan::new(tx.clone(), "table1", db.metadata_manager.clone())?;
+        let mut p2 = TablePlan::new(tx.clone(), "table2", db.metadata_manager.clone())?;
+        {
+            let mut s1 = p1.open(tx.clone())?;
+            s1.before_first()?;
+            for value in [
+                Value::I32(3),
+                Value::I32(1),
+                Value::Null,
+                Value::I32(3),
+                Value::I32(2),
+            ] {
+                s1.insert()?;
+                s1.set_value("A", &value)?;
+            }
+            let mut s2 = p2.open(tx.clone())?;
+            s2.before_first()?;
+            for value in [Value::I32(4), Value::I32(3), Value::Null, Value::I32(3)] {
+                s2.insert()?;
+                s2.set_value("B", &value)?;
+            }
+        }
+
+        let cases = [
+            (
+                SetOperator::Union,
+                vec![
+                    Value::I32(1),
+                    Value::I32(2),
+                    Value::I32(3),
+                    Value::I32(4),
+                    Value::Null,
+                ],
+            ),
+            (SetOperator::Intersect, vec![Value::I32(3), Value::Null]),
+            (SetOperator::Except, vec![Value::I32(1), Value::I32(2)]),
+        ];
+        for (operator, expected) in cases {
+            let mut plan = SetOperationPlan::new(
+                tx.clone(),
+                operator,
+                Plan::from(p1.clone()),
+                Plan::from(p2.clone()),
+                vec!["A".to_string()],
+                vec!["B".to_string()],
+            );
+            let mut scan = plan.open(tx.clone())?;
+            let mut values = vec![];
+            while scan.next()? {
+                values.push(scan.get_value("A")?);
+            }
+            assert_eq!(values, expected, "{:?}", operator);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use crate::{
+    errors::TransactionError,
+    parser::statement::SetOperator,
+    record::field::Value,
+    scan::{Scan, ScanControl},
+};
+
+use super::record_comparator::RecordComparator;
+
+/// Merges two scans sorted on all of their fields, returning each distinct
+/// record of the union, intersection or difference once.
+pub struct SetOperationScan {
+    operator: SetOperator,
+    s1: Box<Scan>,
+    s2: Box<Scan>,
+    fields1: Vec<String>,
+    fields2: Vec<String>,
+    // Compares records whose values are listed in the order of `fields1`
+    comparator: Arc<RecordComparator>,
+    record1: Option<Vec<Value>>,
+    record2: Option<Vec<Value>>,
+    current: Option<Vec<Value>>,
+}
+
+impl SetOperationScan {
+    pub fn new(
+        operator: SetOperator,
+        s1: Scan,
+        s2: Scan,
+        fields1: Vec<String>,
+        fields2: Vec<String>,
+        comparator: Arc<RecordComparator>,
+    ) -> Result<Self, TransactionError> {
+        assert_ne!(operator, SetOperator::UnionAll);
+        let mut scan = SetOperationScan {
+            operator,
+            s1: Box::new(s1),
+            s2: Box::new(s2),
+            fields1,
+            fields2,
+            comparator,
+            record1: None,
+            record2: None,
+            current: None,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+
+    fn read_next(
+        scan: &mut Scan,
+        fields: &[String],
+    ) -> Result<Option<Vec<Value>>, TransactionError> {
+        if !scan.next()? {
+            return Ok(None);
+        }
+        let mut record = vec![];
+        for field_name in fields {
+            record.push(scan.get_value(field_name)?);
+        }
+        Ok(Some(record))
+    }
+
+    fn advance1(&mut self) -> Result<Option<Vec<Value>>, TransactionError> {
+        let next = SetOperationScan::read_next(&mut self.s1, &self.fields1)?;
+        Ok(std::mem::replace(&mut self.record1, next))
+    }
+
+    fn advance2(&mut self) -> Result<Option<Vec<Value>>, TransactionError> {
+        let next = SetOperationScan::read_next(&mut self.s2, &self.fields2)?;
+        Ok(std::mem::replace(&mut self.record2, next))
+    }
+
+    /// Returns the next record produced by the operator, possibly equal to the
+    /// previous one.
+    fn next_candidate(&mut self) -> Result<Option<Vec<Value>>, TransactionError> {
+        loop {
+            let ordering = match (&self.record1, &self.record2) {
+                (None, None) => return Ok(None),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(r1), Some(r2)) => self.comparator.compare_values(r1, r2),
+            };
+            match (self.operator, ordering) {
+                (SetOperator::Union, Ordering::Less) => return self.advance1(),
+                (SetOperator::Union, Ordering::Greater) => return self.advance2(),
+                (SetOperator::Union, Ordering::Equal) => {
+                    self.advance2()?;
+                    return self.advance1();
+                }
+                (SetOperator::Intersect, _) if self.record1.is_none() => return Ok(None),
+                (SetOperator::Intersect, _) if self.record2.is_none() => return Ok(None),
+                (SetOperator::Intersect, Ordering::Less) => {
+                    self.advance1()?;
+                }
+                (SetOperator::Intersect, Ordering::Greater) => {
+                    self.advance2()?;
+                }
+                (SetOperator::Intersect, Ordering::Equal) => {
+                    self.advance2()?;
+                    return self.advance1();
+                }
+                (SetOperator::Except, _) if self.record1.is_none() => return Ok(None),
+                (SetOperator::Except, Ordering::Less) => return self.advance1(),
+                (SetOperator::Except, Ordering::Greater) => {
+                    self.advance2()?;
+                }
+                (SetOperator::Except, Ordering::Equal) => {
+                    // Later duplicates of the record must be removed as well,
+                    // so the second scan is not advanced yet.
+                    self.advance1()?;
+                }
+                (SetOperator::UnionAll, _) => unreachable!(),
+            }
+        }
+    }
+}
+
+impl ScanControl for SetOperationScan {
+    fn before_first(&mut self) -> Result<(), TransactionError> {
+        self.s1.before_first()?;
+        self.s2.before_first()?;
+        self.current = None;
+        self.advance1()?;
+        self.advance2()?;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<bool, TransactionError> {
+        while let Some(record) = self.next_candidate()? {
+            let is_duplicate = self
+                .current
+                .as_ref()
+                .is_some_and(|current| self.comparator.compare_values(current, &record).is_eq());
+            if !is_duplicate {
+                self.current = Some(record);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_i32(&mut self, field_name: &str) -> Result<Option<i32>, TransactionError> {
+        match self.get_value(field_name)? {
+            Value::I32(i) => Ok(Some(i)),
+            Value::Null => Ok(None),
+            _ => panic!("Field {} is not an integer", field_name),
+        }
+    }
+
+    fn get_string(&mut self, field_name: &str) -> Result<Option<String>, TransactionError> {
+        match self.get_value(field_name)? {
+            Value::String(s) => Ok(Some(s)),
+            Value::Null => Ok(None),
+            _ => panic!("Field {} is not a string", field_name),
+        }
+    }
+
+    fn get_value(&mut self, field_name: &str) -> Result<Value, TransactionError> {
+        let index = match self.fields1.iter().position(|f| f == field_name) {
+            Some(index) => index,
+            None => panic!("Field {} not found", field_name),
+        };
+        Ok(self.current.as_ref().unwrap()[index].clone())
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.fields1.iter().any(|f| f == field_name)
+    }
+}
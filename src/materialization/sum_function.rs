@@ -1,7 +1,5 @@
 use crate::{
-    errors::TransactionError,
-    record::field::Value,
-    scan::{Scan, ScanControl},
+    errors::TransactionError, parser::expression::Expression, record::field::Value, scan::Scan,
 };
 
 use super::aggregation_function::AggregationFnControl;
@@ -9,15 +7,24 @@ use super::aggregation_function::AggregationFnControl;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SumFn {
     sum: i64,
-    field_name: String,
+    argument: Expression,
+    field_name: String, // Name of the output field
     has_value: bool,
 }
 
 impl SumFn {
     pub fn new(field_name: &str) -> Self {
+        Self::with_argument(Expression::Field(field_name.to_string()))
+    }
+
+    /// The output field is named after the argument if it is a field, and
+    /// after the function otherwise.
+    pub fn with_argument(argument: Expression) -> Self {
+        let field_name = argument.try_get_field().unwrap_or("sum").to_string();
         Self {
             sum: 0,
-            field_name: field_name.to_string(),
+            argument,
+            field_name,
             has_value: false,
         }
     }
@@ -27,25 +34,25 @@ impl AggregationFnControl for SumFn {
     fn process_first(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
         self.sum = 0;
         self.has_value = false;
-        match scan.get_value(&self.field_name)? {
+        match self.argument.evaluate(scan)? {
             Value::I32(i) => {
                 self.sum = i as i64;
                 self.has_value = true;
             }
             Value::Null => {}
-            _ => panic!("Field {} is not an integer", self.field_name),
+            _ => panic!("Type mismatch in SUM"),
         }
         Ok(())
     }
 
     fn process_next(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
-        match scan.get_value(&self.field_name)? {
+        match self.argument.evaluate(scan)? {
             Value::I32(i) => {
                 self.sum += i as i64;
                 self.has_value = true;
             }
             Value::Null => {}
-            _ => panic!("Field {} is not an integer", self.field_name),
+            _ => panic!("Type mismatch in SUM"),
         }
         Ok(())
     }
@@ -54,6 +61,18 @@ impl AggregationFnControl for SumFn {
         &self.field_name
     }
 
+    fn set_field_name(&mut self, field_name: &str) {
+        self.field_name = field_name.to_string();
+    }
+
+    fn get_argument(&self) -> Option<&Expression> {
+        Some(&self.argument)
+    }
+
+    fn get_argument_mut(&mut self) -> Option<&mut Expression> {
+        Some(&mut self.argument)
+    }
+
     fn get_value(&self) -> Option<Value> {
         if self.has_value {
             Some(Value::I32(self.sum as i32))
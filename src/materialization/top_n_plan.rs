@@ -0,0 +1,177 @@
+use std::{
+    cmp::Ordering,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    errors::TransactionError,
+    plan::{Plan, PlanControl},
+    record::{field::Value, schema::Schema},
+    scan::{Scan, ScanControl},
+    tx::transaction::Transaction,
+};
+
+use super::{record_comparator::RecordComparator, top_n_scan::TopNScan};
+
+/// Largest `limit + offset` for which the planner keeps records in memory
+/// instead of running a full external sort.
+pub const MAX_TOP_N_RECORDS: usize = 10_000;
+
+/// Sorts the output of the base plan but keeps only the first `limit` records
+/// after skipping `offset` of them.
+///
+/// Unlike [`SortPlan`](super::sort_plan::SortPlan), no runs are written to temp
+/// tables: at most `limit + offset` records are held in memory at once.
+#[derive(Clone)]
+pub struct TopNPlan {
+    base_plan: Box<Plan>,
+    comparator: Arc<RecordComparator>,
+    limit: usize,
+    offset: usize,
+}
+
+impl TopNPlan {
+    pub fn new(
+        base_plan: Plan,
+        comparator: Arc<RecordComparator>,
+        limit: usize,
+        offset: usize,
+    ) -> Self {
+        Self {
+            base_plan: Box::new(base_plan),
+            comparator,
+            limit,
+            offset,
+        }
+    }
+}
+
+impl PlanControl for TopNPlan {
+    fn get_num_accessed_blocks(&self) -> usize {
+        self.base_plan.get_num_accessed_blocks()
+    }
+
+    fn get_num_output_records(&self) -> usize {
+        let num_records = self.base_plan.get_num_output_records();
+        std::cmp::min(self.limit, num_records.saturating_sub(self.offset))
+    }
+
+    fn num_distinct_values(&self, field_name: &str) -> usize {
+        std::cmp::min(
+            self.base_plan.num_distinct_values(field_name),
+            self.get_num_output_records(),
+        )
+    }
+
+    fn schema(&self) -> &Schema {
+        self.base_plan.schema()
+    }
+
+    fn open(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
+        let fields = self.schema().get_fields();
+        let capacity = self.limit.saturating_add(self.offset);
+
+        // Sorted by key; records with equal keys keep their input order.
+        let mut records: Vec<(Vec<Value>, Vec<Value>)> = vec![];
+        let mut base_scan = self.base_plan.open(tx)?;
+        base_scan.before_first()?;
+        while capacity > 0 && base_scan.next()? {
+            let mut key = vec![];
+            for sort_key in self.comparator.get_keys() {
+                key.push(base_scan.get_value(&sort_key.field_name)?);
+            }
+            let position = records.partition_point(|(k, _)| {
+                self.comparator.compare_values(k, &key) != Ordering::Greater
+            });
+            if position >= capacity {
+                continue;
+            }
+
+            let mut record = vec![];
+            for field_name in &fields {
+                record.push(base_scan.get_value(field_name)?);
+            }
+            records.insert(position, (key, record));
+            records.truncate(capacity);
+        }
+
+        let records = records
+            .into_iter()
+            .skip(self.offset)
+            .map(|(_, record)| record)
+            .collect();
+        Ok(Scan::from(TopNScan::new(fields, records)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        db::SimpleDB,
+        errors::TransactionError,
+        materialization::record_comparator::RecordComparator,
+        plan::{table_plan::TablePlan, Plan, PlanControl},
+        record::{field::Value, schema::Schema},
+        scan::ScanControl,
+    };
+
+    use super::TopNPlan;
+
+    #[test]
+    fn test_top_n_plan() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+        let db = SimpleDB::new(temp_dir, block_size, 3)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let mut schema = Schema::new();
+        schema.add_i32_field("A");
+        schema.add_string_field("B", 20);
+
+        let table_name = "test_table";
+        db.metadata_manager
+            .lock()
+            .unwrap()
+            .create_table(table_name, &schema, tx.clone())?;
+
+        let mut base_plan = TablePlan::new(tx.clone(), table_name, db.metadata_manager.clone())?;
+        {
+            let mut base_scan = base_plan.open(tx.clone())?;
+            base_scan.before_first()?;
+            for i in 0..50 {
+                base_scan.insert()?;
+                base_scan.set_i32("A", (i * 7) % 50)?;
+                base_scan.set_string("B", &format!("B{}", (i * 7) % 50))?;
+            }
+            base_scan.insert()?;
+            base_scan.set_value("A", &Value::Null)?;
+            base_scan.set_string("B", "null")?;
+        }
+
+        let comparator = Arc::new(RecordComparator::new(&vec!["A".to_string()]));
+        let mut plan = TopNPlan::new(Plan::from(base_plan.clone()), comparator.clone(), 5, 3);
+        let mut scan = plan.open(tx.clone())?;
+        scan.before_first()?;
+        for i in 3..8 {
+            assert!(scan.next()?);
+            assert_eq!(scan.get_i32("A")?, Some(i));
+            assert_eq!(scan.get_string("B")?, Some(format!("B{}", i)));
+        }
+        assert!(!scan.next()?);
+
+        // Records sorted last (NULLs) are reachable when the limit covers them.
+        let mut plan = TopNPlan::new(Plan::from(base_plan), comparator, 10, 48);
+        let mut scan = plan.open(tx.clone())?;
+        scan.before_first()?;
+        assert!(scan.next()?);
+        assert_eq!(scan.get_i32("A")?, Some(48));
+        assert!(scan.next()?);
+        assert_eq!(scan.get_i32("A")?, Some(49));
+        assert!(scan.next()?);
+        assert_eq!(scan.get_value("A")?, Value::Null);
+        assert!(!scan.next()?);
+        Ok(())
+    }
+}
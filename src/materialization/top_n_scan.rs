@@ -0,0 +1,88 @@
+use crate::{errors::TransactionError, record::field::Value, scan::ScanControl};
+
+/// Scan over the records kept in memory by a [`TopNPlan`](super::top_n_plan::TopNPlan).
+///
+/// Window functions also use it to run aggregates over the rows of a frame.
+pub struct TopNScan {
+    fields: Vec<String>,
+    records: Vec<Vec<Value>>,
+    current: Option<usize>,
+}
+
+impl TopNScan {
+    pub fn new(fields: Vec<String>, records: Vec<Vec<Value>>) -> Self {
+        TopNScan {
+            fields,
+            records,
+            current: None,
+        }
+    }
+
+    fn field_index(&self, field_name: &str) -> usize {
+        match self.fields.iter().position(|f| f == field_name) {
+            Some(index) => index,
+            None => panic!("Field {} not found", field_name),
+        }
+    }
+}
+
+impl ScanControl for TopNScan {
+    fn before_first(&mut self) -> Result<(), TransactionError> {
+        self.current = None;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<bool, TransactionError> {
+        let next = self.current.map_or(0, |i| i + 1);
+        if next < self.records.len() {
+            self.current = Some(next);
+            Ok(true)
+        } else {
+            self.current = Some(self.records.len());
+            Ok(false)
+        }
+    }
+
+    fn after_last(&mut self) -> Result<(), TransactionError> {
+        self.current = Some(self.records.len());
+        Ok(())
+    }
+
+    fn previous(&mut self) -> Result<bool, TransactionError> {
+        match self.current {
+            Some(i) if i > 0 => {
+                self.current = Some(i - 1);
+                Ok(true)
+            }
+            _ => {
+                self.current = None;
+                Ok(false)
+            }
+        }
+    }
+
+    fn get_i32(&mut self, field_name: &str) -> Result<Option<i32>, TransactionError> {
+        match self.get_value(field_name)? {
+            Value::I32(i) => Ok(Some(i)),
+            Value::Null => Ok(None),
+            _ => panic!("Field {} is not an integer", field_name),
+        }
+    }
+
+    fn get_string(&mut self, field_name: &str) -> Result<Option<String>, TransactionError> {
+        match self.get_value(field_name)? {
+            Value::String(s) => Ok(Some(s)),
+            Value::Null => Ok(None),
+            _ => panic!("Field {} is not a string", field_name),
+        }
+    }
+
+    fn get_value(&mut self, field_name: &str) -> Result<Value, TransactionError> {
+        let index = self.field_index(field_name);
+        Ok(self.records[self.current.unwrap()][index].clone())
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.fields.iter().any(|f| f == field_name)
+    }
+}
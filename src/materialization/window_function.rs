@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+
+use crate::{
+    errors::TransactionError,
+    record::{
+        field::{Spec, Value},
+        schema::Schema,
+    },
+    scan::{Scan, ScanControl},
+};
+
+use super::{
+    aggregation_function::{AggregationFn, AggregationFnControl},
+    top_n_scan::TopNScan,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WindowFn {
+    RowNumber,
+    Rank,
+    DenseRank,
+    Lag(String, usize), // field name, offset
+    Lead(String, usize),
+    Aggregate(AggregationFn),
+}
+
+impl WindowFn {
+    /// Name of the output field when the select list gives no alias.
+    pub fn default_field_name(&self) -> &str {
+        match self {
+            WindowFn::RowNumber => "row_number",
+            WindowFn::Rank => "rank",
+            WindowFn::DenseRank => "dense_rank",
+            WindowFn::Lag(_, _) => "lag",
+            WindowFn::Lead(_, _) => "lead",
+            WindowFn::Aggregate(AggregationFn::Max(_)) => "max",
+            WindowFn::Aggregate(AggregationFn::Min(_)) => "min",
+            WindowFn::Aggregate(AggregationFn::Count(_)) => "count",
+            WindowFn::Aggregate(AggregationFn::Sum(_)) => "sum",
+            WindowFn::Aggregate(AggregationFn::Avg(_)) => "avg",
+        }
+    }
+
+    /// Returns the field of the input records read by `LAG` or `LEAD`.
+    /// Aggregates read the fields of their argument expression instead.
+    pub fn get_source_field(&self) -> Option<&str> {
+        match self {
+            WindowFn::Lag(field_name, _) | WindowFn::Lead(field_name, _) => Some(field_name),
+            _ => None,
+        }
+    }
+
+    pub fn output_spec(&self, schema: &Schema) -> Spec {
+        match self {
+            WindowFn::RowNumber | WindowFn::Rank | WindowFn::DenseRank => Spec::I32,
+            WindowFn::Aggregate(aggregation_fn) => aggregation_fn
+                .output_spec(schema)
+                .unwrap_or_else(|e| panic!("Invalid window function: {}", e)),
+            _ => schema.get_field_spec(self.get_source_field().unwrap()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(usize),
+    CurrentRow,
+    Following(usize),
+    UnboundedFollowing,
+}
+
+impl FrameBound {
+    fn position(&self, row: usize, num_rows: usize) -> isize {
+        match self {
+            FrameBound::UnboundedPreceding => -1,
+            FrameBound::Preceding(n) => row as isize - *n as isize,
+            FrameBound::CurrentRow => row as isize,
+            FrameBound::Following(n) => (row + n) as isize,
+            FrameBound::UnboundedFollowing => num_rows as isize,
+        }
+    }
+}
+
+/// `ROWS BETWEEN start AND end`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowFrame {
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+impl WindowFrame {
+    pub fn new(start: FrameBound, end: FrameBound) -> Self {
+        WindowFrame { start, end }
+    }
+
+    /// Returns the range of rows of a partition of `num_rows` rows in the
+    /// frame of `row`, which may be empty.
+    fn rows(&self, row: usize, num_rows: usize) -> std::ops::Range<usize> {
+        let start = self.start.position(row, num_rows).max(0) as usize;
+        let end = (self.end.position(row, num_rows) + 1).clamp(0, num_rows as isize) as usize;
+        start.min(end)..end
+    }
+}
+
+/// A window function evaluated by a [`WindowPlan`](super::window_plan::WindowPlan),
+/// and the name of the field holding its result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowColumn {
+    pub function: WindowFn,
+    pub frame: Option<WindowFrame>, // None means the default frame
+    pub field_name: String,
+}
+
+impl WindowColumn {
+    pub fn new(function: WindowFn, frame: Option<WindowFrame>, field_name: &str) -> Self {
+        WindowColumn {
+            function,
+            frame,
+            field_name: field_name.to_string(),
+        }
+    }
+
+    /// Computes the column for every row of a partition.
+    ///
+    /// `rows` hold the values of `fields` in window order and `peers[i]` is the
+    /// range of rows that are equal to row `i` in that order. Without an
+    /// explicit frame, aggregates cover the partition up to the last peer of
+    /// the current row.
+    pub(crate) fn evaluate(
+        &self,
+        fields: &[String],
+        rows: &[Vec<Value>],
+        peers: &[std::ops::Range<usize>],
+    ) -> Result<Vec<Value>, TransactionError> {
+        let field_index = |field_name: &str| fields.iter().position(|f| f == field_name).unwrap();
+        let num_rows = rows.len();
+        let mut values = Vec::with_capacity(num_rows);
+        let mut dense_rank = 0;
+        for (row, peer) in peers.iter().enumerate() {
+            let value = match &self.function {
+                WindowFn::RowNumber => Value::I32(row as i32 + 1),
+                WindowFn::Rank => Value::I32(peer.start as i32 + 1),
+                WindowFn::DenseRank => {
+                    if peer.start == row {
+                        dense_rank += 1;
+                    }
+                    Value::I32(dense_rank)
+                }
+                WindowFn::Lag(field_name, offset) => match row.checked_sub(*offset) {
+                    Some(other) => rows[other][field_index(field_name)].clone(),
+                    None => Value::Null,
+                },
+                WindowFn::Lead(field_name, offset) => match row.checked_add(*offset) {
+                    Some(other) if other < num_rows => rows[other][field_index(field_name)].clone(),
+                    _ => Value::Null,
+                },
+                WindowFn::Aggregate(aggregation_fn) => {
+                    let frame = match &self.frame {
+                        Some(frame) => frame.rows(row, num_rows),
+                        None => 0..peer.end,
+                    };
+                    aggregate(aggregation_fn.clone(), fields, &rows[frame])?
+                }
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+fn aggregate(
+    mut aggregation_fn: AggregationFn,
+    fields: &[String],
+    rows: &[Vec<Value>],
+) -> Result<Value, TransactionError> {
+    if rows.is_empty() {
+        return Ok(aggregation_fn.get_empty_value());
+    }
+    let mut scan = Scan::from(TopNScan::new(fields.to_vec(), rows.to_vec()));
+    let mut first = true;
+    while scan.next()? {
+        if first {
+            aggregation_fn.process_first(&mut scan)?;
+            first = false;
+        } else {
+            aggregation_fn.process_next(&mut scan)?;
+        }
+    }
+    Ok(aggregation_fn.get_value().unwrap_or(Value::Null))
+}
+
+/// Splits rows sorted by `compare` into groups of equal rows, returning the
+/// group of each row.
+pub(crate) fn peer_groups(
+    rows: &[Vec<Value>],
+    compare: impl Fn(&[Value], &[Value]) -> Ordering,
+) -> Vec<std::ops::Range<usize>> {
+    let mut peers = Vec::with_capacity(rows.len());
+    let mut start = 0;
+    for end in 1..=rows.len() {
+        if end == rows.len() || compare(&rows[start], &rows[end]) != Ordering::Equal {
+            peers.extend(std::iter::repeat_n(start..end, end - start));
+            start = end;
+        }
+    }
+    peers
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::materialization::sum_function::SumFn;
+
+    use super::*;
+
+    #[test]
+    fn test_evaluate_window_column() -> Result<(), TransactionError> {
+        let fields = vec!["A".to_string(), "B".to_string()];
+        let rows: Vec<Vec<Value>> = [(1, 10), (2, 20), (2, 30), (3, 40)]
+            .iter()
+            .map(|(a, b)| vec![Value::I32(*a), Value::I32(*b)])
+            .collect();
+        let peers = peer_groups(&rows, |r1, r2| r1[0].cmp(&r2[0]));
+        assert_eq!(peers, vec![0..1, 1..3, 1..3, 3..4]);
+
+        let evaluate = |function: WindowFn, frame: Option<WindowFrame>| {
+            WindowColumn::new(function, frame, "W").evaluate(&fields, &rows, &peers)
+        };
+        let integers = |values: &[i32]| values.iter().map(|v| Value::I32(*v)).collect::<Vec<_>>();
+
+        assert_eq!(
+            evaluate(WindowFn::RowNumber, None)?,
+            integers(&[1, 2, 3, 4])
+        );
+        assert_eq!(evaluate(WindowFn::Rank, None)?, integers(&[1, 2, 2, 4]));
+        assert_eq!(
+            evaluate(WindowFn::DenseRank, None)?,
+            integers(&[1, 2, 2, 3])
+        );
+        assert_eq!(
+            evaluate(WindowFn::Lag("B".to_string(), 1), None)?,
+            vec![Value::Null, Value::I32(10), Value::I32(20), Value::I32(30)]
+        );
+        assert_eq!(
+            evaluate(WindowFn::Lead("B".to_string(), 2), None)?,
+            vec![Value::I32(30), Value::I32(40), Value::Null, Value::Null]
+        );
+
+        let sum = WindowFn::Aggregate(AggregationFn::from(SumFn::new("B")));
+        // The default frame ends at the last peer of the current row
+        assert_eq!(evaluate(sum.clone(), None)?, integers(&[10, 60, 60, 100]));
+        let running = WindowFrame::new(FrameBound::UnboundedPreceding, FrameBound::CurrentRow);
+        assert_eq!(
+            evaluate(sum.clone(), Some(running))?,
+            integers(&[10, 30, 60, 100])
+        );
+        let sliding = WindowFrame::new(FrameBound::Preceding(1), FrameBound::Following(1));
+        assert_eq!(
+            evaluate(sum.clone(), Some(sliding))?,
+            integers(&[30, 60, 90, 70])
+        );
+        let empty = WindowFrame::new(FrameBound::Following(1), FrameBound::Preceding(1));
+        assert_eq!(
+            evaluate(sum, Some(empty))?,
+            vec![Value::Null, Value::Null, Value::Null, Value::Null]
+        );
+        Ok(())
+    }
+}
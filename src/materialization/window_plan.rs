@@ -0,0 +1,183 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::TransactionError,
+    plan::{Plan, PlanControl},
+    record::schema::Schema,
+    scan::Scan,
+    tx::transaction::Transaction,
+};
+
+use super::{
+    record_comparator::{RecordComparator, SortKey},
+    sort_plan::SortPlan,
+    window_function::WindowColumn,
+    window_scan::WindowScan,
+};
+
+/// Evaluates window functions sharing the same `OVER (PARTITION BY ...
+/// ORDER BY ...)` clause, emitting one record per input record.
+#[derive(Clone)]
+pub struct WindowPlan {
+    sort_plan: Box<Plan>,
+    partition_by: Vec<String>,
+    comparator: Arc<RecordComparator>,
+    columns: Vec<WindowColumn>,
+    schema: Schema,
+}
+
+impl WindowPlan {
+    pub fn new(
+        tx: Arc<Mutex<Transaction>>,
+        plan: Plan,
+        partition_by: Vec<String>,
+        order_by: Vec<SortKey>,
+        columns: Vec<WindowColumn>,
+    ) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(plan.schema());
+        for column in &columns {
+            schema.add_field(
+                &column.field_name,
+                &column.function.output_spec(plan.schema()),
+            );
+        }
+
+        let sort_keys = partition_by
+            .iter()
+            .map(|field_name| SortKey::ascending(field_name))
+            .chain(order_by.iter().cloned())
+            .collect();
+        let sort_plan = Plan::from(SortPlan::new(
+            plan,
+            tx,
+            Arc::new(RecordComparator::new_with_keys(sort_keys)),
+        ));
+
+        WindowPlan {
+            sort_plan: Box::new(sort_plan),
+            partition_by,
+            comparator: Arc::new(RecordComparator::new_with_keys(order_by)),
+            columns,
+            schema,
+        }
+    }
+}
+
+impl PlanControl for WindowPlan {
+    fn get_num_accessed_blocks(&self) -> usize {
+        self.sort_plan.get_num_accessed_blocks()
+    }
+
+    fn get_num_output_records(&self) -> usize {
+        self.sort_plan.get_num_output_records()
+    }
+
+    fn num_distinct_values(&self, field_name: &str) -> usize {
+        if self.sort_plan.schema().has_field(field_name) {
+            self.sort_plan.num_distinct_values(field_name)
+        } else {
+            self.get_num_output_records()
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn open(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
+        let scan = self.sort_plan.open(tx)?;
+        Ok(Scan::from(WindowScan::new(
+            scan,
+            self.sort_plan.schema().get_fields(),
+            &self.partition_by,
+            self.comparator.clone(),
+            self.columns.clone(),
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        db::SimpleDB,
+        materialization::{
+            aggregation_function::AggregationFn,
+            record_comparator::SortDirection,
+            sum_function::SumFn,
+            window_function::{FrameBound, WindowFn, WindowFrame},
+        },
+        plan::table_plan::TablePlan,
+        record::field::Value,
+        scan::ScanControl,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_window_plan() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+        let db = SimpleDB::new(temp_dir, block_size, 8)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let mut schema = Schema::new();
+        schema.add_string_field("Dept", 10);
+        schema.add_i32_field("Salary");
+        db.metadata_manager
+            .lock()
+            .unwrap()
+            .create_table("emp", &schema, tx.clone())?;
+
+        let mut table_plan = TablePlan::new(tx.clone(), "emp", db.metadata_manager.clone())?;
+        {
+            let mut scan = table_plan.open(tx.clone())?;
+            scan.before_first()?;
+            for (dept, salary) in [("a", 10), ("b", 5), ("a", 30), ("a", 20), ("b", 5)] {
+                scan.insert()?;
+                scan.set_string("Dept", dept)?;
+                scan.set_i32("Salary", salary)?;
+            }
+        }
+
+        let running = WindowFrame::new(FrameBound::UnboundedPreceding, FrameBound::CurrentRow);
+        let mut plan = WindowPlan::new(
+            tx.clone(),
+            Plan::from(table_plan),
+            vec!["Dept".to_string()],
+            vec![SortKey::new("Salary", SortDirection::Desc, None)],
+            vec![
+                WindowColumn::new(WindowFn::Rank, None, "R"),
+                WindowColumn::new(
+                    WindowFn::Aggregate(AggregationFn::from(SumFn::new("Salary"))),
+                    Some(running),
+                    "Total",
+                ),
+                WindowColumn::new(WindowFn::Lag("Salary".to_string(), 1), None, "Prev"),
+            ],
+        );
+        assert!(plan.schema().has_field("R"));
+
+        let mut scan = plan.open(tx.clone())?;
+        let mut records = vec![];
+        while scan.next()? {
+            records.push((
+                scan.get_string("Dept")?.unwrap(),
+                scan.get_i32("Salary")?.unwrap(),
+                scan.get_i32("R")?.unwrap(),
+                scan.get_i32("Total")?.unwrap(),
+                scan.get_value("Prev")?,
+            ));
+        }
+        let expected = [
+            ("a", 30, 1, 30, Value::Null),
+            ("a", 20, 2, 50, Value::I32(30)),
+            ("a", 10, 3, 60, Value::I32(20)),
+            ("b", 5, 1, 5, Value::Null),
+            ("b", 5, 1, 10, Value::I32(5)),
+        ]
+        .map(|(d, s, r, t, p)| (d.to_string(), s, r, t, p));
+        assert_eq!(records, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use crate::{
+    errors::TransactionError,
+    record::field::Value,
+    scan::{Scan, ScanControl},
+};
+
+use super::{
+    record_comparator::RecordComparator,
+    window_function::{peer_groups, WindowColumn},
+};
+
+/// Adds the columns of window functions to records sorted by partition and
+/// window order.
+///
+/// One partition is held in memory at a time, as functions such as `LEAD`
+/// or frame aggregates look at the rows following the current one.
+pub struct WindowScan {
+    scan: Box<Scan>,
+    fields: Vec<String>,
+    partition_indexes: Vec<usize>,
+    order_indexes: Vec<usize>,
+    // Compares the values of the window order keys
+    comparator: Arc<RecordComparator>,
+    columns: Vec<WindowColumn>,
+    rows: Vec<Vec<Value>>,
+    column_values: Vec<Vec<Value>>,
+    current: Option<usize>,
+    // First record of the next partition
+    pending: Option<Vec<Value>>,
+}
+
+impl WindowScan {
+    pub fn new(
+        scan: Scan,
+        fields: Vec<String>,
+        partition_by: &[String],
+        comparator: Arc<RecordComparator>,
+        columns: Vec<WindowColumn>,
+    ) -> Result<Self, TransactionError> {
+        let field_index = |field_name: &str| fields.iter().position(|f| f == field_name).unwrap();
+        let partition_indexes = partition_by.iter().map(|f| field_index(f)).collect();
+        let order_indexes = comparator
+            .get_keys()
+            .iter()
+            .map(|key| field_index(&key.field_name))
+            .collect();
+        let mut window_scan = WindowScan {
+            scan: Box::new(scan),
+            fields,
+            partition_indexes,
+            order_indexes,
+            comparator,
+            columns,
+            rows: vec![],
+            column_values: vec![],
+            current: None,
+            pending: None,
+        };
+        window_scan.before_first()?;
+        Ok(window_scan)
+    }
+
+    fn read_record(&mut self) -> Result<Option<Vec<Value>>, TransactionError> {
+        if !self.scan.next()? {
+            return Ok(None);
+        }
+        let mut record = Vec::with_capacity(self.fields.len());
+        for field_name in &self.fields {
+            record.push(self.scan.get_value(field_name)?);
+        }
+        Ok(Some(record))
+    }
+
+    /// Reads the next partition and evaluates the window functions over it.
+    fn load_partition(&mut self) -> Result<(), TransactionError> {
+        self.rows.clear();
+        self.column_values.clear();
+        let Some(first) = self.pending.take() else {
+            return Ok(());
+        };
+        self.rows.push(first);
+        while let Some(record) = self.read_record()? {
+            let same_partition = self
+                .partition_indexes
+                .iter()
+                .all(|&i| record[i] == self.rows[0][i]);
+            if !same_partition {
+                self.pending = Some(record);
+                break;
+            }
+            self.rows.push(record);
+        }
+
+        let order_key = |row: &[Value]| -> Vec<Value> {
+            self.order_indexes.iter().map(|&i| row[i].clone()).collect()
+        };
+        let peers = peer_groups(&self.rows, |r1, r2| {
+            self.comparator
+                .compare_values(&order_key(r1), &order_key(r2))
+        });
+        for column in &self.columns {
+            self.column_values
+                .push(column.evaluate(&self.fields, &self.rows, &peers)?);
+        }
+        Ok(())
+    }
+}
+
+impl ScanControl for WindowScan {
+    fn before_first(&mut self) -> Result<(), TransactionError> {
+        self.scan.before_first()?;
+        self.rows.clear();
+        self.column_values.clear();
+        self.current = None;
+        self.pending = self.read_record()?;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<bool, TransactionError> {
+        let next = self.current.map_or(0, |i| i + 1);
+        if next < self.rows.len() {
+            self.current = Some(next);
+            return Ok(true);
+        }
+        self.load_partition()?;
+        if self.rows.is_empty() {
+            self.current = None;
+            return Ok(false);
+        }
+        self.current = Some(0);
+        Ok(true)
+    }
+
+    fn get_i32(&mut self, field_name: &str) -> Result<Option<i32>, TransactionError> {
+        match self.get_value(field_name)? {
+            Value::I32(i) => Ok(Some(i)),
+            Value::Null => Ok(None),
+            _ => panic!("Field {} is not an integer", field_name),
+        }
+    }
+
+    fn get_string(&mut self, field_name: &str) -> Result<Option<String>, TransactionError> {
+        match self.get_value(field_name)? {
+            Value::String(s) => Ok(Some(s)),
+            Value::Null => Ok(None),
+            _ => panic!("Field {} is not a string", field_name),
+        }
+    }
+
+    fn get_value(&mut self, field_name: &str) -> Result<Value, TransactionError> {
+        let current = self.current.unwrap();
+        if let Some(i) = self.columns.iter().position(|c| c.field_name == field_name) {
+            return Ok(self.column_values[i][current].clone());
+        }
+        match self.fields.iter().position(|f| f == field_name) {
+            Some(i) => Ok(self.rows[current][i].clone()),
+            None => panic!("Field {} not found", field_name),
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.fields.iter().any(|f| f == field_name)
+            || self.columns.iter().any(|c| c.field_name == field_name)
+    }
+}
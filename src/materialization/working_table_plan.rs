@@ -0,0 +1,61 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::TransactionError,
+    plan::{Plan, PlanControl},
+    record::schema::Schema,
+    scan::Scan,
+    tx::transaction::Transaction,
+};
+
+use super::temp_table::TempTable;
+
+/// Temp table holding the records produced by the previous iteration of a
+/// [`RecursivePlan`](super::recursive_plan::RecursivePlan).
+pub type WorkingTable = Arc<Mutex<Option<TempTable>>>;
+
+/// Reads the current contents of a working table.
+///
+/// This is the plan a recursive common table refers to itself by. Statistics
+/// are those of the non-recursive part of the query, as the contents of the
+/// table are not known until the plan is opened.
+#[derive(Clone)]
+pub struct WorkingTablePlan {
+    estimate: Box<Plan>,
+    working_table: WorkingTable,
+}
+
+impl WorkingTablePlan {
+    pub fn new(estimate: Plan, working_table: WorkingTable) -> Self {
+        WorkingTablePlan {
+            estimate: Box::new(estimate),
+            working_table,
+        }
+    }
+}
+
+impl PlanControl for WorkingTablePlan {
+    fn get_num_accessed_blocks(&self) -> usize {
+        self.estimate.get_num_accessed_blocks()
+    }
+
+    fn get_num_output_records(&self) -> usize {
+        self.estimate.get_num_output_records()
+    }
+
+    fn num_distinct_values(&self, field_name: &str) -> usize {
+        self.estimate.num_distinct_values(field_name)
+    }
+
+    fn schema(&self) -> &Schema {
+        self.estimate.schema()
+    }
+
+    fn open(&mut self, _tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
+        let working_table = self.working_table.lock().unwrap();
+        let temp_table = working_table
+            .as_ref()
+            .expect("Working table is only readable while the recursive plan is open");
+        Ok(Scan::from(temp_table.open()?))
+    }
+}
@@ -32,7 +32,7 @@ impl ChunkScan {
         let mut buffers = vec![];
         for block_slot in block_slot_start..block_slot_end {
             let block = BlockId::new(block_file_name, block_slot);
-            let record_page = RecordPage::new(tx.clone(), block, layout.clone());
+            let record_page = RecordPage::new(tx.clone(), block, layout.clone())?;
             buffers.push(record_page);
         }
 
@@ -1,7 +1,4 @@
-use std::{
-    fs::File,
-    io::{Read, Write},
-};
+use std::{fs::File, io::Read};
 
 pub struct Page {
     byte_buffer: Vec<u8>,
@@ -29,6 +26,17 @@ impl Page {
         ])
     }
 
+    pub fn set_u64(&mut self, offset: usize, value: u64) -> usize {
+        self.byte_buffer[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        8
+    }
+
+    pub fn get_u64(&self, offset: usize) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&self.byte_buffer[offset..offset + 8]);
+        u64::from_le_bytes(bytes)
+    }
+
     pub fn set_string(&mut self, offset: usize, string: &str) -> usize {
         self.set_bytes(offset, string.as_bytes())
     }
@@ -55,14 +63,16 @@ impl Page {
         )
     }
 
-    pub fn write_to_file(&self, file: &mut File) -> Result<(), std::io::Error> {
-        file.write(self.byte_buffer.as_slice())?;
-        Ok(())
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.byte_buffer
+    }
+
+    pub fn as_mut_bytes(&mut self) -> &mut [u8] {
+        &mut self.byte_buffer
     }
 
     pub fn read_from_file(&mut self, file: &mut File) -> Result<(), std::io::Error> {
-        file.read_exact(&mut self.byte_buffer).unwrap();
-        Ok(())
+        file.read_exact(&mut self.byte_buffer)
     }
 
     pub fn get_required_size(&self, bytes: &[u8]) -> usize {
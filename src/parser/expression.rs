@@ -1,9 +1,15 @@
 use crate::{
     errors::TransactionError,
-    record::{field::Value, schema::Schema},
+    materialization::top_n_scan::TopNScan,
+    record::{
+        field::{Spec, Value},
+        schema::Schema,
+    },
     scan::{Scan, ScanControl},
 };
 
+use super::{function::ScalarFunction, predicate::Predicate};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expression {
     NullConstant,
@@ -14,6 +20,29 @@ pub enum Expression {
     Sub(Box<Expression>, Box<Expression>),
     Mul(Box<Expression>, Box<Expression>),
     Div(Box<Expression>, Box<Expression>),
+    // CASE WHEN condition THEN result ... [ELSE result] END
+    Case(Vec<(Predicate, Expression)>, Option<Box<Expression>>),
+    Coalesce(Vec<Expression>),
+    NullIf(Box<Expression>, Box<Expression>),
+    Cast(Box<Expression>, Spec),
+    FunctionCall(ScalarFunction, Vec<Expression>),
+    // `$n` or `?` of a prepared statement, replaced by a constant before the
+    // statement is planned
+    Parameter(usize),
+}
+
+/// Converts `value` to the type of `spec`, truncating strings to the length
+/// of a VARCHAR. Returns `None` if a string does not hold an integer.
+fn cast(value: Value, spec: &Spec) -> Option<Value> {
+    match (value, spec) {
+        (Value::Null, _) => Some(Value::Null),
+        (Value::I32(i), Spec::I32) => Some(Value::I32(i)),
+        (Value::String(s), Spec::I32) => s.trim().parse().ok().map(Value::I32),
+        (Value::I32(i), Spec::VarChar(n)) => {
+            Some(Value::String(i.to_string().chars().take(*n).collect()))
+        }
+        (Value::String(s), Spec::VarChar(n)) => Some(Value::String(s.chars().take(*n).collect())),
+    }
 }
 
 impl Expression {
@@ -70,9 +99,138 @@ impl Expression {
                     _ => panic!("Type mismatch in division"),
                 }
             }
+            Expression::Case(branches, else_result) => {
+                for (condition, result) in branches {
+                    if condition.is_satisfied(scan)? {
+                        return result.evaluate(scan);
+                    }
+                }
+                match else_result {
+                    Some(else_result) => else_result.evaluate(scan),
+                    None => Ok(Value::Null),
+                }
+            }
+            Expression::Coalesce(exprs) => {
+                for expr in exprs {
+                    let value = expr.evaluate(scan)?;
+                    if value != Value::Null {
+                        return Ok(value);
+                    }
+                }
+                Ok(Value::Null)
+            }
+            Expression::NullIf(lhs, rhs) => {
+                let l = lhs.evaluate(scan)?;
+                let r = rhs.evaluate(scan)?;
+                Ok(if l == r { Value::Null } else { l })
+            }
+            Expression::Cast(expr, spec) => {
+                let value = expr.evaluate(scan)?;
+                cast(value.clone(), spec).ok_or_else(|| {
+                    TransactionError::InvalidCast(format!("{:?} to {:?}", value, spec))
+                })
+            }
+            Expression::FunctionCall(function, args) => {
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(arg.evaluate(scan)?);
+                }
+                Ok(function.apply(&values))
+            }
+            Expression::Parameter(index) => panic!("Parameter ${} is not bound", index),
+        }
+    }
+
+    /// Evaluates an expression that refers to no fields, such as a value of
+    /// INSERT.
+    pub(crate) fn evaluate_constant(&self) -> Result<Value, TransactionError> {
+        let mut scan = Scan::from(TopNScan::new(vec![], vec![vec![]]));
+        scan.next()?;
+        self.evaluate(&mut scan)
+    }
+
+    /// Returns the spec of the values of the expression over records of
+    /// `schema`.
+    ///
+    /// This is where the types of function arguments are checked, so an error
+    /// describes the first argument, operand or field that does not fit.
+    pub(crate) fn infer_spec(&self, schema: &Schema) -> Result<Spec, String> {
+        match self {
+            Expression::Field(f) => {
+                if schema.has_field(f) {
+                    Ok(schema.get_field_spec(f))
+                } else {
+                    Err(format!("unknown field {}", f))
+                }
+            }
+            Expression::StringConstant(s) => Ok(Spec::VarChar(s.len())),
+            Expression::I32Constant(_) | Expression::NullConstant => Ok(Spec::I32),
+            Expression::Add(lhs, rhs)
+            | Expression::Sub(lhs, rhs)
+            | Expression::Mul(lhs, rhs)
+            | Expression::Div(lhs, rhs) => {
+                for operand in [lhs, rhs] {
+                    if let Spec::VarChar(_) = operand.infer_spec(schema)? {
+                        return Err("arithmetic operands must be integers".to_string());
+                    }
+                }
+                Ok(Spec::I32)
+            }
+            Expression::Case(branches, else_result) => Self::infer_common_spec(
+                branches
+                    .iter()
+                    .map(|(_, result)| result)
+                    .chain(else_result.as_deref()),
+                schema,
+            ),
+            Expression::Coalesce(exprs) => Self::infer_common_spec(exprs.iter(), schema),
+            Expression::NullIf(lhs, rhs) => {
+                rhs.infer_spec(schema)?;
+                lhs.infer_spec(schema)
+            }
+            Expression::Cast(expr, spec) => {
+                expr.infer_spec(schema)?;
+                Ok(spec.clone())
+            }
+            Expression::FunctionCall(function, args) => {
+                let mut arg_specs = Vec::with_capacity(args.len());
+                for arg in args {
+                    arg_specs.push(match arg {
+                        Expression::NullConstant => None,
+                        arg => Some(arg.infer_spec(schema)?),
+                    });
+                }
+                function.result_spec(&arg_specs)
+            }
+            Expression::Parameter(index) => Err(format!("parameter ${} is not bound", index)),
         }
     }
 
+    /// Infers the spec of an expression that takes its value from one of
+    /// `exprs`, which must all have the same type. NULL constants take the
+    /// type of the other expressions and strings are as long as the longest
+    /// one.
+    fn infer_common_spec<'a>(
+        exprs: impl Iterator<Item = &'a Expression>,
+        schema: &Schema,
+    ) -> Result<Spec, String> {
+        let mut result = None;
+        for expr in exprs.filter(|expr| **expr != Expression::NullConstant) {
+            result = match (result, expr.infer_spec(schema)?) {
+                (Some(Spec::VarChar(a)), Spec::VarChar(b)) => Some(Spec::VarChar(a.max(b))),
+                (Some(Spec::I32), Spec::I32) | (None, Spec::I32) => Some(Spec::I32),
+                (None, spec) => Some(spec),
+                (Some(a), b) => {
+                    return Err(format!(
+                        "values of types {:?} and {:?} cannot be combined",
+                        a, b
+                    ));
+                }
+            };
+        }
+        Ok(result.unwrap_or(Spec::I32))
+    }
+
     pub(crate) fn try_get_field(&self) -> Option<&str> {
         match self {
             Expression::Field(field_name) => Some(field_name),
@@ -106,6 +264,29 @@ impl Expression {
                 (Some(Value::Null), _) | (_, Some(Value::Null)) => Some(Value::Null),
                 _ => None,
             },
+            Expression::NullIf(lhs, rhs) => {
+                let l = lhs.try_get_constant()?;
+                let r = rhs.try_get_constant()?;
+                Some(if l == r { Value::Null } else { l })
+            }
+            Expression::Cast(expr, spec) => cast(expr.try_get_constant()?, spec),
+            Expression::FunctionCall(function, args) => {
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(arg.try_get_constant()?);
+                }
+                let specs: Vec<_> = values
+                    .iter()
+                    .map(|value| match value {
+                        Value::Null => None,
+                        Value::I32(_) => Some(Spec::I32),
+                        Value::String(s) => Some(Spec::VarChar(s.len())),
+                    })
+                    .collect();
+                // Calls with mistyped arguments are left to the planner to report
+                function.result_spec(&specs).ok()?;
+                Some(function.apply(&values))
+            }
             _ => None,
         }
     }
@@ -120,8 +301,54 @@ impl Expression {
             Expression::Add(lhs, rhs)
             | Expression::Sub(lhs, rhs)
             | Expression::Mul(lhs, rhs)
-            | Expression::Div(lhs, rhs) => lhs.is_applied_to(schema) && rhs.is_applied_to(schema),
+            | Expression::Div(lhs, rhs)
+            | Expression::NullIf(lhs, rhs) => {
+                lhs.is_applied_to(schema) && rhs.is_applied_to(schema)
+            }
+            Expression::Case(branches, else_result) => {
+                branches.iter().all(|(condition, result)| {
+                    condition.is_applied_to(schema) && result.is_applied_to(schema)
+                }) && else_result
+                    .as_ref()
+                    .is_none_or(|else_result| else_result.is_applied_to(schema))
+            }
+            Expression::Coalesce(exprs) | Expression::FunctionCall(_, exprs) => {
+                exprs.iter().all(|expr| expr.is_applied_to(schema))
+            }
+            Expression::Cast(expr, _) => expr.is_applied_to(schema),
             _ => true,
         }
     }
+
+    /// Calls `f` on the expression and then on the expressions nested in it,
+    /// including those of CASE conditions.
+    pub(crate) fn visit_mut(&mut self, f: &mut impl FnMut(&mut Expression)) {
+        f(self);
+        match self {
+            Expression::Add(lhs, rhs)
+            | Expression::Sub(lhs, rhs)
+            | Expression::Mul(lhs, rhs)
+            | Expression::Div(lhs, rhs)
+            | Expression::NullIf(lhs, rhs) => {
+                lhs.visit_mut(f);
+                rhs.visit_mut(f);
+            }
+            Expression::Case(branches, else_result) => {
+                for (condition, result) in branches {
+                    condition.visit_expressions_mut(f);
+                    result.visit_mut(f);
+                }
+                if let Some(else_result) = else_result {
+                    else_result.visit_mut(f);
+                }
+            }
+            Expression::Coalesce(exprs) | Expression::FunctionCall(_, exprs) => {
+                for expr in exprs {
+                    expr.visit_mut(f);
+                }
+            }
+            Expression::Cast(expr, _) => expr.visit_mut(f),
+            _ => {}
+        }
+    }
 }
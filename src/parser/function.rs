@@ -0,0 +1,286 @@
+use crate::record::field::{Spec, Value};
+
+/// Display length of the longest integer, used when integers are converted to
+/// strings.
+const I32_STRING_LENGTH: usize = 11;
+
+/// Built-in scalar functions callable from expressions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarFunction {
+    Upper,
+    Lower,
+    Length,
+    Substr,
+    Trim,
+    Concat,
+    Abs,
+    Mod,
+    Round,
+}
+
+/// Registry of the scalar functions by name.
+const FUNCTIONS: &[(&str, ScalarFunction)] = &[
+    ("UPPER", ScalarFunction::Upper),
+    ("LOWER", ScalarFunction::Lower),
+    ("LENGTH", ScalarFunction::Length),
+    ("SUBSTR", ScalarFunction::Substr),
+    ("TRIM", ScalarFunction::Trim),
+    ("CONCAT", ScalarFunction::Concat),
+    ("ABS", ScalarFunction::Abs),
+    ("MOD", ScalarFunction::Mod),
+    ("ROUND", ScalarFunction::Round),
+];
+
+impl ScalarFunction {
+    /// Looks up a function by its case-insensitive name.
+    pub fn lookup(name: &str) -> Option<Self> {
+        FUNCTIONS
+            .iter()
+            .find(|(function_name, _)| function_name.eq_ignore_ascii_case(name))
+            .map(|(_, function)| *function)
+    }
+
+    pub fn name(&self) -> &'static str {
+        FUNCTIONS
+            .iter()
+            .find(|(_, function)| function == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+
+    /// Checks the number and types of the arguments and returns the spec of
+    /// the result.
+    ///
+    /// `args` holds the spec of each argument, or `None` for a NULL constant,
+    /// which is accepted in place of any type.
+    pub fn result_spec(&self, args: &[Option<Spec>]) -> Result<Spec, String> {
+        let (min_args, max_args) = match self {
+            ScalarFunction::Upper
+            | ScalarFunction::Lower
+            | ScalarFunction::Length
+            | ScalarFunction::Trim
+            | ScalarFunction::Abs => (1, 1),
+            ScalarFunction::Substr => (2, 3),
+            ScalarFunction::Concat => (1, usize::MAX),
+            ScalarFunction::Mod => (2, 2),
+            ScalarFunction::Round => (1, 2),
+        };
+        if args.len() < min_args || args.len() > max_args {
+            return Err(format!(
+                "wrong number of arguments to {}: {}",
+                self.name(),
+                args.len()
+            ));
+        }
+
+        let string_length = |i: usize| match &args[i] {
+            Some(Spec::VarChar(n)) => Ok(*n),
+            None => Ok(0),
+            Some(Spec::I32) => Err(format!(
+                "argument {} of {} must be a string",
+                i + 1,
+                self.name()
+            )),
+        };
+        let check_i32 = |i: usize| match &args[i] {
+            Some(Spec::I32) | None => Ok(()),
+            Some(Spec::VarChar(_)) => Err(format!(
+                "argument {} of {} must be an integer",
+                i + 1,
+                self.name()
+            )),
+        };
+        match self {
+            ScalarFunction::Upper | ScalarFunction::Lower | ScalarFunction::Trim => {
+                Ok(Spec::VarChar(string_length(0)?))
+            }
+            ScalarFunction::Length => {
+                string_length(0)?;
+                Ok(Spec::I32)
+            }
+            ScalarFunction::Substr => {
+                for i in 1..args.len() {
+                    check_i32(i)?;
+                }
+                Ok(Spec::VarChar(string_length(0)?))
+            }
+            ScalarFunction::Concat => Ok(Spec::VarChar(
+                args.iter()
+                    .map(|arg| match arg {
+                        Some(Spec::VarChar(n)) => *n,
+                        Some(Spec::I32) => I32_STRING_LENGTH,
+                        None => 0,
+                    })
+                    .sum(),
+            )),
+            ScalarFunction::Abs | ScalarFunction::Mod | ScalarFunction::Round => {
+                for i in 0..args.len() {
+                    check_i32(i)?;
+                }
+                Ok(Spec::I32)
+            }
+        }
+    }
+
+    /// Applies the function to the values of its arguments.
+    ///
+    /// As with arithmetic, a NULL argument makes the result NULL, and so does
+    /// a result that is undefined or does not fit in an integer, such as
+    /// `MOD(x, 0)`.
+    pub fn apply(&self, args: &[Value]) -> Value {
+        if args.contains(&Value::Null) {
+            return Value::Null;
+        }
+        let string = |i: usize| match &args[i] {
+            Value::String(s) => s.as_str(),
+            _ => panic!("Type mismatch in {}", self.name()),
+        };
+        let integer = |i: usize| match &args[i] {
+            Value::I32(v) => *v,
+            _ => panic!("Type mismatch in {}", self.name()),
+        };
+        let from_i64 = |v: i64| i32::try_from(v).map_or(Value::Null, Value::I32);
+
+        match self {
+            ScalarFunction::Upper => Value::String(string(0).to_uppercase()),
+            ScalarFunction::Lower => Value::String(string(0).to_lowercase()),
+            ScalarFunction::Length => from_i64(string(0).chars().count() as i64),
+            ScalarFunction::Substr => {
+                // Positions start at 1 and the substring is cut to the part
+                // that overlaps the string
+                let start = integer(1) as i64;
+                let end = match args.get(2) {
+                    Some(_) => start + integer(2).max(0) as i64,
+                    None => i64::MAX,
+                };
+                let substring = string(0)
+                    .chars()
+                    .enumerate()
+                    .filter(|(i, _)| (start..end).contains(&(*i as i64 + 1)))
+                    .map(|(_, c)| c)
+                    .collect();
+                Value::String(substring)
+            }
+            ScalarFunction::Trim => Value::String(string(0).trim().to_string()),
+            ScalarFunction::Concat => Value::String(
+                args.iter()
+                    .map(|arg| match arg {
+                        Value::String(s) => s.clone(),
+                        Value::I32(v) => v.to_string(),
+                        Value::Null => unreachable!(),
+                    })
+                    .collect(),
+            ),
+            ScalarFunction::Abs => from_i64((integer(0) as i64).abs()),
+            ScalarFunction::Mod => match integer(1) {
+                0 => Value::Null,
+                divisor => from_i64(integer(0) as i64 % divisor as i64),
+            },
+            ScalarFunction::Round => {
+                // Integers only change when rounded to tens, hundreds, ...
+                let digits = args.get(1).map_or(0, |_| integer(1));
+                if digits >= 0 {
+                    return Value::I32(integer(0));
+                }
+                match 10i64.checked_pow(digits.unsigned_abs()) {
+                    Some(unit) => {
+                        let value = integer(0) as i64;
+                        let rounded = (value.abs() + unit / 2) / unit * unit;
+                        from_i64(rounded * value.signum())
+                    }
+                    None => Value::I32(0),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_spec() {
+        assert_eq!(ScalarFunction::lookup("upper"), Some(ScalarFunction::Upper));
+        assert_eq!(ScalarFunction::lookup("unknown"), None);
+
+        assert_eq!(
+            ScalarFunction::Upper.result_spec(&[Some(Spec::VarChar(5))]),
+            Ok(Spec::VarChar(5))
+        );
+        assert_eq!(
+            ScalarFunction::Concat.result_spec(&[Some(Spec::VarChar(5)), Some(Spec::I32), None]),
+            Ok(Spec::VarChar(16))
+        );
+        assert_eq!(
+            ScalarFunction::Substr.result_spec(&[Some(Spec::VarChar(5)), None]),
+            Ok(Spec::VarChar(5))
+        );
+        assert!(ScalarFunction::Length
+            .result_spec(&[Some(Spec::I32)])
+            .is_err());
+        assert!(ScalarFunction::Mod.result_spec(&[Some(Spec::I32)]).is_err());
+        assert!(ScalarFunction::Abs
+            .result_spec(&[Some(Spec::VarChar(1))])
+            .is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(ScalarFunction::Lower.apply(&[string("AbC")]), string("abc"));
+        assert_eq!(
+            ScalarFunction::Length.apply(&[string("héllo")]),
+            Value::I32(5)
+        );
+        assert_eq!(
+            ScalarFunction::Trim.apply(&[string("  a b ")]),
+            string("a b")
+        );
+        assert_eq!(
+            ScalarFunction::Substr.apply(&[string("hello"), Value::I32(2), Value::I32(3)]),
+            string("ell")
+        );
+        assert_eq!(
+            ScalarFunction::Substr.apply(&[string("hello"), Value::I32(0), Value::I32(2)]),
+            string("h")
+        );
+        assert_eq!(
+            ScalarFunction::Substr.apply(&[string("hello"), Value::I32(4)]),
+            string("lo")
+        );
+        assert_eq!(
+            ScalarFunction::Concat.apply(&[string("a"), Value::I32(1)]),
+            string("a1")
+        );
+        assert_eq!(
+            ScalarFunction::Concat.apply(&[string("a"), Value::Null]),
+            Value::Null
+        );
+        assert_eq!(ScalarFunction::Abs.apply(&[Value::I32(-3)]), Value::I32(3));
+        assert_eq!(
+            ScalarFunction::Abs.apply(&[Value::I32(i32::MIN)]),
+            Value::Null
+        );
+        assert_eq!(
+            ScalarFunction::Mod.apply(&[Value::I32(-7), Value::I32(3)]),
+            Value::I32(-1)
+        );
+        assert_eq!(
+            ScalarFunction::Mod.apply(&[Value::I32(7), Value::I32(0)]),
+            Value::Null
+        );
+        assert_eq!(
+            ScalarFunction::Round.apply(&[Value::I32(1250), Value::I32(-2)]),
+            Value::I32(1300)
+        );
+        assert_eq!(
+            ScalarFunction::Round.apply(&[Value::I32(-1249), Value::I32(-2)]),
+            Value::I32(-1200)
+        );
+        assert_eq!(
+            ScalarFunction::Round.apply(&[Value::I32(17), Value::I32(1)]),
+            Value::I32(17)
+        );
+    }
+}
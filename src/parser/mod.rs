@@ -1,19 +1,32 @@
 pub mod expression;
+pub mod function;
 pub mod grammar;
+pub mod parameter;
 pub mod predicate;
 pub mod statement;
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        materialization::{aggregation_function::AggregationFn, sum_function::SumFn},
+        materialization::{
+            aggregation_function::{AggregationFn, AggregationFnControl},
+            count_function::CountFn,
+            record_comparator::{NullOrdering, SortDirection},
+            sum_function::SumFn,
+            window_function::{FrameBound, WindowFn, WindowFrame},
+        },
         parser::{
             expression::Expression,
+            function::ScalarFunction,
             grammar,
             predicate::{Predicate, Term},
-            statement::{CreateCommand, FieldDefinition, QueryData, Statement, UpdateCommand},
+            statement::{
+                CommonTableExpression, ConflictAction, CreateCommand, FieldDefinition,
+                InsertSource, OnConflict, OrderByItem, QueryData, SetOperator, Statement,
+                TransactionCommand, UpdateCommand, WindowFunction,
+            },
         },
-        record::field::{Spec, Value},
+        record::field::Spec,
     };
 
     #[test]
@@ -49,6 +62,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_predicate_like_between_in() {
+        let field = |name: &str| Expression::Field(name.to_string());
+        assert_eq!(
+            grammar::TermParser::new().parse("name like 'ab%'").unwrap(),
+            Term::Like(field("name"), Expression::StringConstant("ab%".to_string()))
+        );
+        assert_eq!(
+            grammar::PredicateParser::new()
+                .parse("a BETWEEN 1 AND b + 1 AND c = 2")
+                .unwrap(),
+            Predicate::new(vec![
+                Term::Between(
+                    field("a"),
+                    Expression::I32Constant(1),
+                    Expression::Add(Box::new(field("b")), Box::new(Expression::I32Constant(1)))
+                ),
+                Term::Equality(field("c"), Expression::I32Constant(2)),
+            ])
+        );
+        assert_eq!(
+            grammar::TermParser::new()
+                .parse("a IN (1, 2, NULL)")
+                .unwrap(),
+            Term::In(
+                field("a"),
+                vec![
+                    Expression::I32Constant(1),
+                    Expression::I32Constant(2),
+                    Expression::NullConstant
+                ]
+            )
+        );
+        assert!(grammar::TermParser::new().parse("a IN ()").is_err());
+    }
+
     #[test]
     fn test_insert() {
         assert_eq!(
@@ -58,9 +107,212 @@ mod tests {
             Statement::UpdateCommand(UpdateCommand::Insert(
                 "mytable".to_string(),
                 vec!["aaa".to_string(), "bbb".to_string()],
-                vec![Value::I32(333), Value::String("222".to_string())]
+                InsertSource::Values(vec![vec![
+                    Expression::I32Constant(333),
+                    Expression::StringConstant("222".to_string())
+                ]]),
+                None
+            ))
+        );
+
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("INSERT INTO t (a, b) VALUES (1 + 2, NULL), (-4, UPPER('x'))")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Insert(
+                "t".to_string(),
+                vec!["a".to_string(), "b".to_string()],
+                InsertSource::Values(vec![
+                    vec![
+                        Expression::Add(
+                            Box::new(Expression::I32Constant(1)),
+                            Box::new(Expression::I32Constant(2))
+                        ),
+                        Expression::NullConstant
+                    ],
+                    vec![
+                        Expression::I32Constant(-4),
+                        Expression::FunctionCall(
+                            ScalarFunction::Upper,
+                            vec![Expression::StringConstant("x".to_string())]
+                        )
+                    ]
+                ]),
+                None
             ))
         );
+        // Every row must match the field list and values refer to no fields
+        assert!(grammar::StatementParser::new()
+            .parse("INSERT INTO t (a, b) VALUES (1, 2), (3)")
+            .is_err());
+        assert!(grammar::StatementParser::new()
+            .parse("INSERT INTO t (a) VALUES (b)")
+            .is_err());
+    }
+
+    #[test]
+    fn test_insert_select() {
+        let query = QueryData::new(
+            vec!["c".to_string()],
+            vec!["s".to_string()],
+            Some(Predicate::new(vec![Term::Equality(
+                Expression::Field("c".to_string()),
+                Expression::I32Constant(1),
+            )])),
+        );
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("INSERT INTO t (a) SELECT c FROM s WHERE c = 1")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Insert(
+                "t".to_string(),
+                vec!["a".to_string()],
+                InsertSource::Query(Box::new(query.clone())),
+                None
+            ))
+        );
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("INSERT INTO t SELECT c FROM s WHERE c = 1")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Insert(
+                "t".to_string(),
+                vec![],
+                InsertSource::Query(Box::new(query)),
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn test_insert_on_conflict() {
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("INSERT INTO t (a, b) VALUES (1, 2) ON CONFLICT (a) DO NOTHING")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Insert(
+                "t".to_string(),
+                vec!["a".to_string(), "b".to_string()],
+                InsertSource::Values(vec![vec![
+                    Expression::I32Constant(1),
+                    Expression::I32Constant(2)
+                ]]),
+                Some(OnConflict {
+                    field_name: "a".to_string(),
+                    action: ConflictAction::DoNothing
+                })
+            ))
+        );
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("INSERT INTO t SELECT a, b FROM s ON CONFLICT (a) DO UPDATE SET b = b + EXCLUDED.b")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Insert(
+                "t".to_string(),
+                vec![],
+                InsertSource::Query(Box::new(QueryData::new(
+                    vec!["a".to_string(), "b".to_string()],
+                    vec!["s".to_string()],
+                    None
+                ))),
+                Some(OnConflict {
+                    field_name: "a".to_string(),
+                    action: ConflictAction::DoUpdate(vec![(
+                        "b".to_string(),
+                        Expression::Add(
+                            Box::new(Expression::Field("b".to_string())),
+                            Box::new(Expression::Field("excluded.b".to_string()))
+                        )
+                    )])
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn test_returning() {
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("DELETE FROM t WHERE a = 1 RETURNING a, b + 1 AS c")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Returning(
+                Box::new(UpdateCommand::Delete(
+                    "t".to_string(),
+                    Some(Predicate::new(vec![Term::Equality(
+                        Expression::Field("a".to_string()),
+                        Expression::I32Constant(1)
+                    )]))
+                )),
+                vec![
+                    (Expression::Field("a".to_string()), "a".to_string()),
+                    (
+                        Expression::Add(
+                            Box::new(Expression::Field("b".to_string())),
+                            Box::new(Expression::I32Constant(1))
+                        ),
+                        "c".to_string()
+                    )
+                ]
+            ))
+        );
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("INSERT INTO t (a) VALUES (1) ON CONFLICT (a) DO NOTHING RETURNING *")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Returning(
+                Box::new(UpdateCommand::Insert(
+                    "t".to_string(),
+                    vec!["a".to_string()],
+                    InsertSource::Values(vec![vec![Expression::I32Constant(1)]]),
+                    Some(OnConflict {
+                        field_name: "a".to_string(),
+                        action: ConflictAction::DoNothing
+                    })
+                )),
+                vec![]
+            ))
+        );
+        // Expressions need a name and creation commands return nothing
+        assert!(grammar::StatementParser::new()
+            .parse("UPDATE t SET a = 1 RETURNING a + 1")
+            .is_err());
+        assert!(grammar::StatementParser::new()
+            .parse("CREATE TABLE t (a I32) RETURNING a")
+            .is_err());
+    }
+
+    #[test]
+    fn test_transaction_command() {
+        let parse = |s: &str| grammar::StatementParser::new().parse(s).unwrap();
+        assert_eq!(
+            parse("begin"),
+            Statement::TransactionCommand(TransactionCommand::Begin)
+        );
+        assert_eq!(
+            parse("COMMIT"),
+            Statement::TransactionCommand(TransactionCommand::Commit)
+        );
+        assert_eq!(
+            parse("ROLLBACK"),
+            Statement::TransactionCommand(TransactionCommand::Rollback)
+        );
+        assert_eq!(
+            parse("SAVEPOINT s1"),
+            Statement::TransactionCommand(TransactionCommand::Savepoint("s1".to_string()))
+        );
+        assert_eq!(
+            parse("ROLLBACK TO s1"),
+            Statement::TransactionCommand(TransactionCommand::RollbackTo("s1".to_string()))
+        );
+        assert_eq!(
+            parse("rollback to savepoint s1"),
+            Statement::TransactionCommand(TransactionCommand::RollbackTo("s1".to_string()))
+        );
+        assert_eq!(
+            parse("RELEASE SAVEPOINT s1"),
+            Statement::TransactionCommand(TransactionCommand::Release("s1".to_string()))
+        );
+        assert!(grammar::StatementParser::new().parse("SAVEPOINT").is_err());
     }
 
     #[test]
@@ -94,8 +346,7 @@ mod tests {
                 .unwrap(),
             Statement::UpdateCommand(UpdateCommand::Modify(
                 "mytable".to_string(),
-                "aaa".to_string(),
-                Expression::I32Constant(333),
+                vec![("aaa".to_string(), Expression::I32Constant(333))],
                 None
             ))
         );
@@ -106,8 +357,7 @@ mod tests {
                 .unwrap(),
             Statement::UpdateCommand(UpdateCommand::Modify(
                 "mytable".to_string(),
-                "aaa".to_string(),
-                Expression::I32Constant(333),
+                vec![("aaa".to_string(), Expression::I32Constant(333))],
                 Some(Predicate::new(vec![Term::Equality(
                     Expression::Field("col".to_string()),
                     Expression::I32Constant(222)
@@ -116,6 +366,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_update() {
+        let field = |name: &str| Expression::Field(name.to_string());
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("update mytable set a = b, b = a + 1 where c = 'x'")
+                .unwrap(),
+            Statement::UpdateCommand(UpdateCommand::Modify(
+                "mytable".to_string(),
+                vec![
+                    ("a".to_string(), field("b")),
+                    (
+                        "b".to_string(),
+                        Expression::Add(Box::new(field("a")), Box::new(Expression::I32Constant(1)))
+                    ),
+                ],
+                Some(Predicate::new(vec![Term::Equality(
+                    field("c"),
+                    Expression::StringConstant("x".to_string())
+                )]))
+            ))
+        );
+        assert!(grammar::StatementParser::new()
+            .parse("UPDATE mytable SET")
+            .is_err());
+    }
+
     #[test]
     fn test_create_index() {
         assert_eq!(
@@ -138,14 +415,14 @@ mod tests {
                 .unwrap(),
             Statement::UpdateCommand(UpdateCommand::Create(CreateCommand::View(
                 "view_name".to_string(),
-                QueryData::new(
+                Box::new(QueryData::new(
                     vec!["aaa".to_string(), "bbb".to_string()],
                     vec!["table_name".to_string()],
                     Some(Predicate::new(vec![Term::Equality(
                         Expression::Field("col".to_string()),
                         Expression::I32Constant(222)
                     )]))
-                )
+                ))
             )))
         );
     }
@@ -185,9 +462,194 @@ mod tests {
             Statement::Query(QueryData::new_all_with_order(
                 vec!["mytable".to_string()],
                 None,
-                Some(vec!["col".to_string()]),
+                Some(vec![OrderByItem::from("col")]),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_select_order_by_direction() {
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT * FROM mytable ORDER BY a DESC NULLS LAST, 2, a + 1 ASC NULLS FIRST")
+                .unwrap(),
+            Statement::Query(QueryData::new_all_with_order(
+                vec!["mytable".to_string()],
+                None,
+                Some(vec![
+                    OrderByItem::new(
+                        Expression::Field("a".to_string()),
+                        SortDirection::Desc,
+                        Some(NullOrdering::Last),
+                    ),
+                    OrderByItem::new(Expression::I32Constant(2), SortDirection::Asc, None),
+                    OrderByItem::new(
+                        Expression::Add(
+                            Box::new(Expression::Field("a".to_string())),
+                            Box::new(Expression::I32Constant(1)),
+                        ),
+                        SortDirection::Asc,
+                        Some(NullOrdering::First),
+                    ),
+                ]),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_select_limit_offset() {
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT * FROM mytable ORDER BY col LIMIT 10 OFFSET 5")
+                .unwrap(),
+            Statement::Query(
+                QueryData::new_all_with_order(
+                    vec!["mytable".to_string()],
+                    None,
+                    Some(vec![OrderByItem::from("col")]),
+                )
+                .with_limit(Some(10), Some(5))
+            )
+        );
+
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT col FROM mytable LIMIT 3")
+                .unwrap(),
+            Statement::Query(
+                QueryData::new(vec!["col".to_string()], vec!["mytable".to_string()], None)
+                    .with_limit(Some(3), None)
+            )
+        );
+
+        assert!(grammar::StatementParser::new()
+            .parse("SELECT col FROM mytable LIMIT -1")
+            .is_err());
+        assert!(grammar::StatementParser::new()
+            .parse("SELECT col FROM mytable OFFSET 1")
+            .is_err());
+    }
+
+    #[test]
+    fn test_select_set_operations() {
+        let select =
+            |table: &str| QueryData::new(vec!["a".to_string()], vec![table.to_string()], None);
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT a FROM t1 UNION SELECT a FROM t2 INTERSECT SELECT a FROM t3 EXCEPT SELECT a FROM t4 ORDER BY a LIMIT 1")
+                .unwrap(),
+            Statement::Query(
+                select("t1")
+                    .with_set_operation(
+                        SetOperator::Union,
+                        select("t2").with_set_operation(SetOperator::Intersect, select("t3")),
+                    )
+                    .with_set_operation(SetOperator::Except, select("t4"))
+                    .with_order_by(Some(vec![OrderByItem::from("a")]))
+                    .with_limit(Some(1), None)
+            )
+        );
+
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT a FROM t1 UNION ALL SELECT a FROM t2")
+                .unwrap(),
+            Statement::Query(select("t1").with_set_operation(SetOperator::UnionAll, select("t2")))
+        );
+
+        assert!(grammar::StatementParser::new()
+            .parse("SELECT a FROM t1 ORDER BY a UNION SELECT a FROM t2")
+            .is_err());
+    }
+
+    #[test]
+    fn test_select_with() {
+        let select = |field: &str, table: &str| {
+            QueryData::new(vec![field.to_string()], vec![table.to_string()], None)
+        };
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("WITH t1 AS (SELECT a FROM t), t2 AS MATERIALIZED (SELECT a FROM t1) SELECT a FROM t2")
+                .unwrap(),
+            Statement::Query(select("a", "t2").with_common_tables(vec![
+                CommonTableExpression::new("t1".to_string(), select("a", "t"), false),
+                CommonTableExpression::new("t2".to_string(), select("a", "t1"), true),
+            ]))
+        );
+
+        let mut cte = CommonTableExpression::new(
+            "r".to_string(),
+            select("a", "t").with_set_operation(SetOperator::UnionAll, select("a", "r")),
+            false,
+        );
+        cte.recursive = true;
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("WITH RECURSIVE r AS (SELECT a FROM t UNION ALL SELECT a FROM r) SELECT a FROM r")
+                .unwrap(),
+            Statement::Query(select("a", "r").with_common_tables(vec![cte]))
+        );
+    }
+
+    #[test]
+    fn test_select_window_functions() {
+        let mut rank = WindowFunction::new(
+            WindowFn::Rank,
+            vec!["a".to_string()],
+            vec![OrderByItem::new(
+                Expression::Field("b".to_string()),
+                SortDirection::Desc,
+                None,
+            )],
+            None,
+        );
+        rank.field_name = "r".to_string();
+        let running = WindowFunction::new(
+            WindowFn::Aggregate(AggregationFn::from(SumFn::new("b"))),
+            vec![],
+            vec![OrderByItem::from("b")],
+            Some(WindowFrame::new(
+                FrameBound::Preceding(2),
+                FrameBound::CurrentRow,
+            )),
+        );
+        let lag = WindowFunction::new(WindowFn::Lag("b".to_string(), 3), vec![], vec![], None);
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT a, RANK() OVER (PARTITION BY a ORDER BY b DESC) AS r, SUM(b) OVER (ORDER BY b ROWS 2 PRECEDING), LAG(b, 3) OVER () FROM t")
+                .unwrap(),
+            Statement::Query(
+                QueryData::new(
+                    vec![
+                        "a".to_string(),
+                        "r".to_string(),
+                        "sum".to_string(),
+                        "lag".to_string()
+                    ],
+                    vec!["t".to_string()],
+                    None
+                )
+                .with_window_functions(vec![rank, running, lag])
+            )
+        );
+
+        let sliding = grammar::StatementParser::new()
+            .parse("SELECT ROW_NUMBER() OVER (ROWS BETWEEN UNBOUNDED PRECEDING AND 1 FOLLOWING) AS n FROM t")
+            .unwrap();
+        let Statement::Query(query) = sliding else {
+            panic!("expected a query");
+        };
+        assert_eq!(
+            query.window_functions[0].frame,
+            Some(WindowFrame::new(
+                FrameBound::UnboundedPreceding,
+                FrameBound::Following(1)
             ))
         );
+
+        assert!(grammar::StatementParser::new()
+            .parse("SELECT LEAD(b, -1) OVER () FROM t")
+            .is_err());
     }
 
     #[test]
@@ -221,6 +683,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_expression_conditional() {
+        let field = |name: &str| Expression::Field(name.to_string());
+        assert_eq!(
+            grammar::TermParser::new()
+                .parse("CASE WHEN a = 1 AND b IS NULL THEN 'x' ELSE c END = 'x'")
+                .unwrap(),
+            Term::Equality(
+                Expression::Case(
+                    vec![(
+                        Predicate::new(vec![
+                            Term::Equality(field("a"), Expression::I32Constant(1)),
+                            Term::IsNull(field("b")),
+                        ]),
+                        Expression::StringConstant("x".to_string()),
+                    )],
+                    Some(Box::new(field("c"))),
+                ),
+                Expression::StringConstant("x".to_string()),
+            )
+        );
+
+        assert_eq!(
+            grammar::TermParser::new()
+                .parse("CASE a WHEN 1 THEN 2 END IS NULL")
+                .unwrap(),
+            Term::IsNull(Expression::Case(
+                vec![(
+                    Predicate::new(vec![Term::Equality(field("a"), Expression::I32Constant(1))]),
+                    Expression::I32Constant(2),
+                )],
+                None,
+            ))
+        );
+
+        assert_eq!(
+            grammar::TermParser::new()
+                .parse("COALESCE(a, NULLIF(b, 0)) = CAST(c AS VARCHAR(5))")
+                .unwrap(),
+            Term::Equality(
+                Expression::Coalesce(vec![
+                    field("a"),
+                    Expression::NullIf(Box::new(field("b")), Box::new(Expression::I32Constant(0))),
+                ]),
+                Expression::Cast(Box::new(field("c")), Spec::VarChar(5)),
+            )
+        );
+    }
+
+    #[test]
+    fn test_expression_function_call() {
+        let field = |name: &str| Expression::Field(name.to_string());
+        assert_eq!(
+            grammar::TermParser::new()
+                .parse("upper(a) || 'x' = Substr(b, 1, 2)")
+                .unwrap(),
+            Term::Equality(
+                Expression::FunctionCall(
+                    ScalarFunction::Concat,
+                    vec![
+                        Expression::FunctionCall(ScalarFunction::Upper, vec![field("a")]),
+                        Expression::StringConstant("x".to_string()),
+                    ]
+                ),
+                Expression::FunctionCall(
+                    ScalarFunction::Substr,
+                    vec![
+                        field("b"),
+                        Expression::I32Constant(1),
+                        Expression::I32Constant(2)
+                    ]
+                ),
+            )
+        );
+        assert!(grammar::TermParser::new().parse("unknown(a) = 1").is_err());
+    }
+
     #[test]
     fn test_select_as_alias() {
         assert_eq!(
@@ -259,6 +798,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_select_expression_without_alias() {
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT a, a + 1 FROM tbl")
+                .unwrap(),
+            Statement::Query(QueryData::new_with_order_and_extend(
+                vec!["a".to_string(), "__column_1".to_string()],
+                vec!["tbl".to_string()],
+                None,
+                None,
+                vec![(
+                    Expression::Add(
+                        Box::new(Expression::Field("a".to_string())),
+                        Box::new(Expression::I32Constant(1)),
+                    ),
+                    "__column_1".to_string(),
+                ),],
+            ))
+        );
+    }
+
     #[test]
     fn test_select_aggregation_as_alias() {
         assert_eq!(
@@ -269,11 +830,41 @@ mod tests {
                 Some(vec!["total".to_string()]),
                 vec!["t".to_string()],
                 None,
-                Some(vec!["val".to_string()]),
+                Some(vec![Expression::Field("val".to_string())]),
                 None,
                 vec![(Expression::Field("val".to_string()), "total".to_string())],
                 vec![AggregationFn::from(SumFn::new("val"))],
             ))
         );
     }
+
+    #[test]
+    fn test_select_aggregation_expressions() {
+        let field = |name: &str| Expression::Field(name.to_string());
+        let price = Expression::Mul(Box::new(field("price")), Box::new(field("qty")));
+        let bucket = Expression::Div(
+            Box::new(field("qty")),
+            Box::new(Expression::I32Constant(10)),
+        );
+        let mut revenue = AggregationFn::from(SumFn::with_argument(price));
+        revenue.set_field_name("revenue");
+        assert_eq!(
+            grammar::StatementParser::new()
+                .parse("SELECT qty / 10 AS b, SUM(price * qty) AS revenue, COUNT(*) FROM t GROUP BY qty / 10")
+                .unwrap(),
+            Statement::Query(QueryData::new_full(
+                Some(vec![
+                    "b".to_string(),
+                    "revenue".to_string(),
+                    "count".to_string()
+                ]),
+                vec!["t".to_string()],
+                None,
+                Some(vec![bucket.clone()]),
+                None,
+                vec![(bucket, "b".to_string())],
+                vec![revenue, AggregationFn::from(CountFn::count_records())],
+            ))
+        );
+    }
 }
@@ -0,0 +1,194 @@
+use lalrpop_util::{lexer::Token, ParseError};
+
+use crate::{errors::QueryError, record::field::Value};
+
+use super::{
+    expression::Expression,
+    grammar::StatementParser,
+    statement::{QueryData, Statement, UpdateCommand},
+};
+
+/// Replaces each `?` outside string literals with `$1`, `$2`, ... in order of
+/// appearance, so that both styles of placeholders parse the same way.
+pub fn number_placeholders(sql: &str) -> Result<String, QueryError> {
+    let mut numbered = String::with_capacity(sql.len());
+    let mut num_placeholders = 0;
+    let mut has_numbered_parameters = false;
+    let mut in_string = false;
+    for c in sql.chars() {
+        match c {
+            '\'' => in_string = !in_string,
+            '?' if !in_string => {
+                num_placeholders += 1;
+                numbered.push_str(&format!("${}", num_placeholders));
+                continue;
+            }
+            '$' if !in_string => has_numbered_parameters = true,
+            _ => {}
+        }
+        numbered.push(c);
+    }
+    if num_placeholders > 0 && has_numbered_parameters {
+        return Err(QueryError::InvalidParameter(
+            "? and $n placeholders cannot be mixed".to_string(),
+        ));
+    }
+    Ok(numbered)
+}
+
+/// A statement parsed once and executed with new parameter values each time,
+/// as by a prepared statement.
+#[derive(Debug)]
+pub struct ParsedStatement {
+    statement: Statement,
+    num_parameters: usize, // Highest parameter number
+}
+
+impl ParsedStatement {
+    pub fn parse(sql: &str) -> Result<Self, QueryError> {
+        let sql = number_placeholders(sql)?;
+        let mut statement = StatementParser::new()
+            .parse(&sql)
+            .map_err(|e: ParseError<usize, Token<'_>, &str>| QueryError::from(e))?;
+        let mut num_parameters = 0;
+        statement.visit_expressions_mut(&mut |expr| {
+            if let Expression::Parameter(index) = expr {
+                num_parameters = num_parameters.max(*index);
+            }
+        });
+        Ok(ParsedStatement {
+            statement,
+            num_parameters,
+        })
+    }
+
+    pub fn num_parameters(&self) -> usize {
+        self.num_parameters
+    }
+
+    /// Returns the statement, which must have no parameters.
+    pub fn statement(&self) -> Result<&Statement, QueryError> {
+        if self.num_parameters > 0 {
+            return Err(QueryError::InvalidParameter(format!(
+                "{} parameters are not bound",
+                self.num_parameters
+            )));
+        }
+        Ok(&self.statement)
+    }
+
+    pub fn query(&self) -> Result<&QueryData, QueryError> {
+        match self.statement()? {
+            Statement::Query(query) => Ok(query),
+            _ => Err(QueryError::InvalidStatement("expected a query".to_string())),
+        }
+    }
+
+    pub fn update_command(&self) -> Result<&UpdateCommand, QueryError> {
+        match self.statement()? {
+            Statement::UpdateCommand(command) => Ok(command),
+            _ => Err(QueryError::InvalidStatement(
+                "expected an update command".to_string(),
+            )),
+        }
+    }
+
+    /// Returns a copy of the statement where parameter `$n` is replaced by
+    /// the constant `parameters[n - 1]`. `None` means the parameter is not
+    /// set yet.
+    pub fn bind(&self, parameters: &[Option<Value>]) -> Result<Statement, QueryError> {
+        if parameters.len() < self.num_parameters {
+            return Err(QueryError::InvalidParameter(format!(
+                "expected {} parameters, got {}",
+                self.num_parameters,
+                parameters.len()
+            )));
+        }
+        let mut statement = self.statement.clone();
+        let mut unset = None;
+        statement.visit_expressions_mut(&mut |expr| {
+            if let Expression::Parameter(index) = expr {
+                *expr = match &parameters[*index - 1] {
+                    Some(Value::I32(value)) => Expression::I32Constant(*value),
+                    Some(Value::String(value)) => Expression::StringConstant(value.clone()),
+                    Some(Value::Null) => Expression::NullConstant,
+                    None => {
+                        unset = Some(*index);
+                        return;
+                    }
+                };
+            }
+        });
+        match unset {
+            Some(index) => Err(QueryError::InvalidParameter(format!(
+                "parameter ${} is not set",
+                index
+            ))),
+            None => Ok(statement),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{
+        predicate::{Predicate, Term},
+        statement::InsertSource,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_number_placeholders() {
+        assert_eq!(
+            number_placeholders("select a from t where a = ? and b = '?'").unwrap(),
+            "select a from t where a = $1 and b = '?'"
+        );
+        assert_eq!(
+            number_placeholders("insert into t (a, b) values (?, ?)").unwrap(),
+            "insert into t (a, b) values ($1, $2)"
+        );
+        assert!(number_placeholders("select a from t where a = ? and b = $1").is_err());
+    }
+
+    #[test]
+    fn test_bind_parameters() -> Result<(), QueryError> {
+        let statement = ParsedStatement::parse("insert into t (a, b) values (?, ?)")?;
+        assert_eq!(statement.num_parameters(), 2);
+        assert!(statement.update_command().is_err());
+        assert!(statement.bind(&[Some(Value::I32(1)), None]).is_err());
+
+        let bound =
+            statement.bind(&[Some(Value::I32(1)), Some(Value::String("it's".to_string()))])?;
+        assert_eq!(
+            bound,
+            Statement::UpdateCommand(UpdateCommand::Insert(
+                "t".to_string(),
+                vec!["a".to_string(), "b".to_string()],
+                InsertSource::Values(vec![vec![
+                    Expression::I32Constant(1),
+                    Expression::StringConstant("it's".to_string())
+                ]]),
+                None
+            ))
+        );
+
+        // A parameter can be used more than once
+        let statement = ParsedStatement::parse("select a from t where a = $1 and b = $1")?;
+        assert_eq!(statement.num_parameters(), 1);
+        let Statement::Query(query) = statement.bind(&[Some(Value::Null)])? else {
+            panic!("expected a query");
+        };
+        assert_eq!(
+            query.predicate,
+            Some(Predicate::new(vec![
+                Term::Equality(Expression::Field("a".to_string()), Expression::NullConstant),
+                Term::Equality(Expression::Field("b".to_string()), Expression::NullConstant),
+            ]))
+        );
+
+        assert!(ParsedStatement::parse("select a from t where a = $0").is_err());
+        assert!(ParsedStatement::parse("select a from t")?.query().is_ok());
+        Ok(())
+    }
+}
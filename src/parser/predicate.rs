@@ -1,16 +1,101 @@
+use std::{mem::discriminant, ops::Bound};
+
 use crate::{
     errors::TransactionError,
+    index::SearchKey,
     plan::{Plan, PlanControl},
-    record::{field::Value, schema::Schema},
+    record::{
+        field::{Spec, Value},
+        schema::Schema,
+    },
     scan::Scan,
 };
 
 use super::expression::Expression;
 
+/// Reduction factor assumed for terms selecting a range of values, such as
+/// BETWEEN and LIKE with a prefix.
+pub(crate) const RANGE_REDUCTION_FACTOR: usize = 4;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Term {
     Equality(Expression, Expression),
     IsNull(Expression),
+    Like(Expression, Expression),                // value, pattern
+    Between(Expression, Expression, Expression), // value, low, high
+    In(Expression, Vec<Expression>),
+}
+
+/// Matches `value` against a LIKE pattern, where `%` stands for any sequence
+/// of characters and `_` for a single character.
+fn matches_like(value: &str, pattern: &str) -> bool {
+    let value: Vec<char> = value.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut v, mut p) = (0, 0);
+    // Positions to resume from when the text after the last % stops matching
+    let mut backtrack = None;
+    while v < value.len() {
+        if p < pattern.len() && pattern[p] == '%' {
+            p += 1;
+            backtrack = Some((p, v));
+        } else if p < pattern.len() && (pattern[p] == '_' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if let Some((backtrack_p, backtrack_v)) = backtrack {
+            p = backtrack_p;
+            v = backtrack_v + 1;
+            backtrack = Some((backtrack_p, v));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '%')
+}
+
+/// Returns the keys of an index holding the values that match a LIKE pattern,
+/// or `None` if the pattern starts with a wildcard.
+fn like_search_key(pattern: &str) -> Option<SearchKey> {
+    let prefix: String = pattern
+        .chars()
+        .take_while(|c| *c != '%' && *c != '_')
+        .collect();
+    if prefix.len() == pattern.len() {
+        return Some(SearchKey::Equal(Value::String(prefix)));
+    }
+    if prefix.is_empty() {
+        return None;
+    }
+
+    // The values starting with the prefix sort before its successor, which is
+    // the prefix with its last character incremented
+    let mut successor: Vec<char> = prefix.chars().collect();
+    let mut high = Bound::Unbounded;
+    while let Some(c) = successor.pop() {
+        if let Some(next) = (c as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+            successor.push(next);
+            high = Bound::Excluded(Value::String(successor.into_iter().collect()));
+            break;
+        }
+    }
+    Some(SearchKey::Range(
+        Bound::Included(Value::String(prefix)),
+        high,
+    ))
+}
+
+/// Returns `true` if no two of the specs are of different types. NULL
+/// constants, given as `None`, fit any type.
+fn have_same_type(specs: &[Option<Spec>]) -> bool {
+    let mut specs = specs.iter().flatten();
+    match specs.next() {
+        Some(first) => specs.all(|spec| {
+            matches!(
+                (first, spec),
+                (Spec::I32, Spec::I32) | (Spec::VarChar(_), Spec::VarChar(_))
+            )
+        }),
+        None => true,
+    }
 }
 
 impl Term {
@@ -29,6 +114,34 @@ impl Term {
                 let value = expr.evaluate(scan)?;
                 Ok(value == Value::Null)
             }
+            Term::Like(expr, pattern) => match (expr.evaluate(scan)?, pattern.evaluate(scan)?) {
+                (Value::String(value), Value::String(pattern)) => {
+                    Ok(matches_like(&value, &pattern))
+                }
+                (Value::Null, _) | (_, Value::Null) => Ok(false),
+                _ => panic!("Type mismatch in LIKE"),
+            },
+            Term::Between(expr, low, high) => {
+                let value = expr.evaluate(scan)?;
+                let low = low.evaluate(scan)?;
+                let high = high.evaluate(scan)?;
+                if [&value, &low, &high].contains(&&Value::Null) {
+                    return Ok(false);
+                }
+                Ok(low <= value && value <= high)
+            }
+            Term::In(expr, list) => {
+                let value = expr.evaluate(scan)?;
+                if value == Value::Null {
+                    return Ok(false);
+                }
+                for item in list {
+                    if item.evaluate(scan)? == value {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
         }
     }
 
@@ -73,6 +186,11 @@ impl Term {
                 }
                 100
             }
+            Term::Like(_, _) | Term::Between(_, _, _) => RANGE_REDUCTION_FACTOR,
+            Term::In(expr, list) => match expr.try_get_field() {
+                Some(field) => std::cmp::max(plan.num_distinct_values(field) / list.len(), 1),
+                None => 100,
+            },
         }
     }
 
@@ -102,31 +220,72 @@ impl Term {
                     }
                 }
             }
+            Term::Like(_, _) | Term::Between(_, _, _) | Term::In(_, _) => {}
         }
         return None;
     }
 
+    /// If this term restricts the given field to constant values or ranges
+    /// that an index can look up, return the keys to search for.
+    fn search_keys(&self, field_name: &str) -> Option<Vec<SearchKey>> {
+        match self {
+            Term::Like(expr, pattern) if expr.try_get_field() == Some(field_name) => {
+                match pattern.try_get_constant()? {
+                    Value::String(pattern) => Some(vec![like_search_key(&pattern)?]),
+                    _ => None,
+                }
+            }
+            Term::Between(expr, low, high) if expr.try_get_field() == Some(field_name) => {
+                match (low.try_get_constant()?, high.try_get_constant()?) {
+                    (Value::Null, _) | (_, Value::Null) => Some(vec![]),
+                    (low, high) => Some(vec![SearchKey::Range(
+                        Bound::Included(low),
+                        Bound::Included(high),
+                    )]),
+                }
+            }
+            Term::In(expr, list) if expr.try_get_field() == Some(field_name) => {
+                let mut values = vec![];
+                for item in list {
+                    match item.try_get_constant()? {
+                        Value::Null => {}
+                        value => values.push(value),
+                    }
+                }
+                // Leave lists mixing types to the type check
+                if values
+                    .windows(2)
+                    .any(|pair| discriminant(&pair[0]) != discriminant(&pair[1]))
+                {
+                    return None;
+                }
+                // Probing a value twice would return its records twice
+                values.sort();
+                values.dedup();
+                Some(values.into_iter().map(SearchKey::Equal).collect())
+            }
+            _ => None,
+        }
+    }
+
     /// If this term equates the given field with another field, return the other
     /// field name.
     fn equates_with_field(&self, field_name: &str) -> Option<String> {
-        match self {
-            Term::Equality(lhs, rhs) => {
-                if let Some(lhs_field) = lhs.try_get_field() {
-                    if lhs_field == field_name {
-                        if let Some(rhs_field) = rhs.try_get_field() {
-                            return Some(rhs_field.to_string());
-                        }
+        if let Term::Equality(lhs, rhs) = self {
+            if let Some(lhs_field) = lhs.try_get_field() {
+                if lhs_field == field_name {
+                    if let Some(rhs_field) = rhs.try_get_field() {
+                        return Some(rhs_field.to_string());
                     }
                 }
-                if let Some(rhs_field) = rhs.try_get_field() {
-                    if rhs_field == field_name {
-                        if let Some(lhs_field) = lhs.try_get_field() {
-                            return Some(lhs_field.to_string());
-                        }
+            }
+            if let Some(rhs_field) = rhs.try_get_field() {
+                if rhs_field == field_name {
+                    if let Some(lhs_field) = lhs.try_get_field() {
+                        return Some(lhs_field.to_string());
                     }
                 }
             }
-            Term::IsNull(_) => {}
         }
         return None;
     }
@@ -137,8 +296,58 @@ impl Term {
         match self {
             Term::Equality(lhs, rhs) => lhs.is_applied_to(schema) && rhs.is_applied_to(schema),
             Term::IsNull(expr) => expr.is_applied_to(schema),
+            Term::Like(expr, pattern) => {
+                expr.is_applied_to(schema) && pattern.is_applied_to(schema)
+            }
+            Term::Between(expr, low, high) => {
+                expr.is_applied_to(schema)
+                    && low.is_applied_to(schema)
+                    && high.is_applied_to(schema)
+            }
+            Term::In(expr, list) => {
+                expr.is_applied_to(schema) && list.iter().all(|item| item.is_applied_to(schema))
+            }
         }
     }
+
+    /// Checks the types of the expressions of the term against the schema.
+    fn check_types(&self, schema: &Schema) -> Result<(), String> {
+        let spec = |expr: &Expression| match expr {
+            Expression::NullConstant => Ok(None),
+            expr => expr.infer_spec(schema).map(Some),
+        };
+        match self {
+            Term::Equality(lhs, rhs) => {
+                spec(lhs)?;
+                spec(rhs)?;
+            }
+            Term::IsNull(expr) => {
+                spec(expr)?;
+            }
+            Term::Like(expr, pattern) => {
+                for operand in [expr, pattern] {
+                    if let Some(Spec::I32) = spec(operand)? {
+                        return Err("LIKE operands must be strings".to_string());
+                    }
+                }
+            }
+            Term::Between(expr, low, high) => {
+                if !have_same_type(&[spec(expr)?, spec(low)?, spec(high)?]) {
+                    return Err("BETWEEN operands must have the same type".to_string());
+                }
+            }
+            Term::In(expr, list) => {
+                let mut specs = vec![spec(expr)?];
+                for item in list {
+                    specs.push(spec(item)?);
+                }
+                if !have_same_type(&specs) {
+                    return Err("IN list values must have the type of the tested value".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -162,6 +371,44 @@ impl Predicate {
         Ok(true)
     }
 
+    /// Returns `true` if all terms can be applied to the provided schema.
+    pub(crate) fn is_applied_to(&self, schema: &Schema) -> bool {
+        self.terms.iter().all(|term| term.is_applied_to(schema))
+    }
+
+    /// Checks the types of the expressions of all terms against the schema.
+    pub(crate) fn check_types(&self, schema: &Schema) -> Result<(), String> {
+        for term in &self.terms {
+            term.check_types(schema)?;
+        }
+        Ok(())
+    }
+
+    /// Calls `f` on every expression of the terms, as with
+    /// [`Expression::visit_mut`].
+    pub(crate) fn visit_expressions_mut(&mut self, f: &mut impl FnMut(&mut Expression)) {
+        for term in &mut self.terms {
+            match term {
+                Term::Equality(lhs, rhs) | Term::Like(lhs, rhs) => {
+                    lhs.visit_mut(f);
+                    rhs.visit_mut(f);
+                }
+                Term::IsNull(expr) => expr.visit_mut(f),
+                Term::Between(expr, low, high) => {
+                    expr.visit_mut(f);
+                    low.visit_mut(f);
+                    high.visit_mut(f);
+                }
+                Term::In(expr, list) => {
+                    expr.visit_mut(f);
+                    for item in list {
+                        item.visit_mut(f);
+                    }
+                }
+            }
+        }
+    }
+
     /// Computes the combined reduction factor of all terms.
     ///
     /// This is the product of the individual reduction factors and is used by
@@ -233,6 +480,14 @@ impl Predicate {
         None
     }
 
+    /// Searches for a term restricting `field_name` to keys an index can look
+    /// up, such as a BETWEEN range, a LIKE prefix or an IN list.
+    pub(crate) fn search_keys(&self, field_name: &str) -> Option<Vec<SearchKey>> {
+        self.terms
+            .iter()
+            .find_map(|term| term.search_keys(field_name))
+    }
+
     /// Searches for a term equating `field_name` with another field.
     pub(crate) fn equates_with_field(&self, field_name: &str) -> Option<String> {
         for term in &self.terms {
@@ -1,29 +1,148 @@
-use crate::record::field::{Spec, Value};
+use crate::record::field::Spec;
 
 use super::{expression::Expression, predicate::Predicate};
-use crate::materialization::aggregation_function::AggregationFn;
+use crate::materialization::{
+    aggregation_function::{AggregationFn, AggregationFnControl},
+    record_comparator::{NullOrdering, SortDirection},
+    window_function::{WindowFn, WindowFrame},
+};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Statement {
     Query(QueryData),
     UpdateCommand(UpdateCommand),
+    TransactionCommand(TransactionCommand),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl Statement {
+    /// Calls `f` on every expression of the statement, as with
+    /// [`Expression::visit_mut`].
+    pub(crate) fn visit_expressions_mut(&mut self, f: &mut impl FnMut(&mut Expression)) {
+        match self {
+            Statement::Query(query) => query.visit_expressions_mut(f),
+            Statement::UpdateCommand(command) => command.visit_expressions_mut(f),
+            Statement::TransactionCommand(_) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SelectField {
     Expression(Expression, Option<String>),
     Aggregation(AggregationFn, Option<String>),
+    Window(WindowFunction, Option<String>),
+}
+
+/// A single `ORDER BY` key.
+///
+/// An integer constant refers to a position in the select list (starting
+/// from 1) rather than a constant value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderByItem {
+    pub expression: Expression,
+    pub direction: SortDirection,
+    pub nulls: Option<NullOrdering>, // None means the default for the direction
+}
+
+impl OrderByItem {
+    pub fn new(
+        expression: Expression,
+        direction: SortDirection,
+        nulls: Option<NullOrdering>,
+    ) -> Self {
+        OrderByItem {
+            expression,
+            direction,
+            nulls,
+        }
+    }
+}
+
+impl From<&str> for OrderByItem {
+    fn from(field_name: &str) -> Self {
+        OrderByItem::new(
+            Expression::Field(field_name.to_string()),
+            SortDirection::Asc,
+            None,
+        )
+    }
+}
+
+/// A window function call from the select list, such as
+/// `RANK() OVER (PARTITION BY a ORDER BY b)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowFunction {
+    pub function: WindowFn,
+    pub partition_by: Vec<String>,
+    pub order_by: Vec<OrderByItem>,
+    pub frame: Option<WindowFrame>, // None means the default frame
+    pub field_name: String,         // Name of the output field
+}
+
+impl WindowFunction {
+    pub fn new(
+        function: WindowFn,
+        partition_by: Vec<String>,
+        order_by: Vec<OrderByItem>,
+        frame: Option<WindowFrame>,
+    ) -> Self {
+        let field_name = function.default_field_name().to_string();
+        WindowFunction {
+            function,
+            partition_by,
+            order_by,
+            frame,
+            field_name,
+        }
+    }
+}
+
+/// Operator combining the results of two SELECT blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    Union,
+    UnionAll,
+    Intersect,
+    Except,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// A named query introduced by `WITH`, usable as a table by the query that
+/// follows it and by later common tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommonTableExpression {
+    pub name: String,
+    pub query: QueryData,
+    pub recursive: bool,    // Declared with WITH RECURSIVE
+    pub materialized: bool, // Declared AS MATERIALIZED instead of being inlined
+}
+
+impl CommonTableExpression {
+    pub fn new(name: String, query: QueryData, materialized: bool) -> Self {
+        CommonTableExpression {
+            name,
+            query,
+            recursive: false,
+            materialized,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct QueryData {
     pub fields: Option<Vec<String>>, // None means all fields
     pub tables: Vec<String>,
     pub predicate: Option<Predicate>,
-    pub group_by: Option<Vec<String>>, // None means no grouping
-    pub order_by: Option<Vec<String>>, // None means no ordering
+    pub group_by: Option<Vec<Expression>>, // None means no grouping
+    pub order_by: Option<Vec<OrderByItem>>, // None means no ordering
     pub extend_fields: Vec<(Expression, String)>,
     pub aggregation_functions: Vec<AggregationFn>,
+    pub window_functions: Vec<WindowFunction>,
+    pub limit: Option<usize>,  // None means no limit
+    pub offset: Option<usize>, // None means no offset
+    // SELECT blocks combined with this one from left to right. When present,
+    // ORDER BY and LIMIT apply to the combined result.
+    pub set_operations: Vec<(SetOperator, QueryData)>,
+    pub common_tables: Vec<CommonTableExpression>,
 }
 
 impl QueryData {
@@ -31,8 +150,8 @@ impl QueryData {
         fields: Option<Vec<String>>,
         tables: Vec<String>,
         predicate: Option<Predicate>,
-        group_by: Option<Vec<String>>,
-        order_by: Option<Vec<String>>,
+        group_by: Option<Vec<Expression>>,
+        order_by: Option<Vec<OrderByItem>>,
         extend_fields: Vec<(Expression, String)>,
         aggregation_functions: Vec<AggregationFn>,
     ) -> Self {
@@ -44,6 +163,93 @@ impl QueryData {
             order_by,
             extend_fields,
             aggregation_functions,
+            window_functions: Vec::new(),
+            limit: None,
+            offset: None,
+            set_operations: Vec::new(),
+            common_tables: Vec::new(),
+        }
+    }
+
+    /// Returns the query with `window_functions` added to its select list.
+    pub fn with_window_functions(mut self, window_functions: Vec<WindowFunction>) -> Self {
+        self.window_functions = window_functions;
+        self
+    }
+
+    /// Returns the query sorted by `order_by`.
+    pub fn with_order_by(mut self, order_by: Option<Vec<OrderByItem>>) -> Self {
+        self.order_by = order_by;
+        self
+    }
+
+    /// Returns the query restricted to `limit` records after skipping `offset`.
+    pub fn with_limit(mut self, limit: Option<usize>, offset: Option<usize>) -> Self {
+        self.limit = limit;
+        self.offset = offset;
+        self
+    }
+
+    /// Returns the query whose result is combined with that of `query`.
+    pub fn with_set_operation(mut self, operator: SetOperator, query: QueryData) -> Self {
+        self.set_operations.push((operator, query));
+        self
+    }
+
+    /// Returns the query preceded by `WITH common_tables`.
+    pub fn with_common_tables(mut self, common_tables: Vec<CommonTableExpression>) -> Self {
+        self.common_tables = common_tables;
+        self
+    }
+
+    /// Returns true if any part of the query reads from the table `table_name`.
+    pub fn references_table(&self, table_name: &str) -> bool {
+        self.tables.iter().any(|t| t == table_name)
+            || self
+                .set_operations
+                .iter()
+                .any(|(_, query)| query.references_table(table_name))
+            || self
+                .common_tables
+                .iter()
+                .any(|cte| cte.query.references_table(table_name))
+    }
+
+    /// Calls `f` on every expression of the query, including those of
+    /// aggregates, window functions, combined queries and common tables.
+    pub(crate) fn visit_expressions_mut(&mut self, f: &mut impl FnMut(&mut Expression)) {
+        if let Some(predicate) = &mut self.predicate {
+            predicate.visit_expressions_mut(f);
+        }
+        for expr in self.group_by.iter_mut().flatten() {
+            expr.visit_mut(f);
+        }
+        for item in self.order_by.iter_mut().flatten() {
+            item.expression.visit_mut(f);
+        }
+        for (expr, _) in &mut self.extend_fields {
+            expr.visit_mut(f);
+        }
+        for aggregation_fn in &mut self.aggregation_functions {
+            if let Some(argument) = aggregation_fn.get_argument_mut() {
+                argument.visit_mut(f);
+            }
+        }
+        for window_function in &mut self.window_functions {
+            if let WindowFn::Aggregate(aggregation_fn) = &mut window_function.function {
+                if let Some(argument) = aggregation_fn.get_argument_mut() {
+                    argument.visit_mut(f);
+                }
+            }
+            for item in &mut window_function.order_by {
+                item.expression.visit_mut(f);
+            }
+        }
+        for (_, query) in &mut self.set_operations {
+            query.visit_expressions_mut(f);
+        }
+        for cte in &mut self.common_tables {
+            cte.query.visit_expressions_mut(f);
         }
     }
 
@@ -59,7 +265,7 @@ impl QueryData {
         fields: Vec<String>,
         tables: Vec<String>,
         predicate: Option<Predicate>,
-        order_by: Option<Vec<String>>,
+        order_by: Option<Vec<OrderByItem>>,
     ) -> Self {
         QueryData::new_full(
             Some(fields),
@@ -75,7 +281,7 @@ impl QueryData {
     pub fn new_all_with_order(
         tables: Vec<String>,
         predicate: Option<Predicate>,
-        order_by: Option<Vec<String>>,
+        order_by: Option<Vec<OrderByItem>>,
     ) -> Self {
         QueryData::new_full(
             None,
@@ -92,7 +298,7 @@ impl QueryData {
         fields: Vec<String>,
         tables: Vec<String>,
         predicate: Option<Predicate>,
-        order_by: Option<Vec<String>>,
+        order_by: Option<Vec<OrderByItem>>,
         extend_fields: Vec<(Expression, String)>,
     ) -> Self {
         QueryData::new_full(
@@ -109,7 +315,7 @@ impl QueryData {
     pub fn new_all_with_order_and_extend(
         tables: Vec<String>,
         predicate: Option<Predicate>,
-        order_by: Option<Vec<String>>,
+        order_by: Option<Vec<OrderByItem>>,
         extend_fields: Vec<(Expression, String)>,
     ) -> Self {
         QueryData::new_full(
@@ -124,22 +330,118 @@ impl QueryData {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Records added by an INSERT statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertSource {
+    // One list of expressions per record, which refer to no fields
+    Values(Vec<Vec<Expression>>),
+    Query(Box<QueryData>),
+}
+
+/// Prefix of the fields through which `ON CONFLICT ... DO UPDATE` refers to
+/// the values of the record that could not be inserted.
+pub const EXCLUDED_PREFIX: &str = "excluded.";
+
+/// `ON CONFLICT (field) DO ...` clause of an INSERT, applied when a record
+/// has the same non-NULL value of `field_name` as an existing record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnConflict {
+    pub field_name: String,
+    pub action: ConflictAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictAction {
+    DoNothing,
+    // Assignments are evaluated over the existing record, and the values of
+    // the new record are read as `excluded.field`
+    DoUpdate(Vec<(String, Expression)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UpdateCommand {
-    Insert(String, Vec<String>, Vec<Value>),
+    // An empty list of fields inserts the output fields of a query into the
+    // fields of the same name
+    Insert(String, Vec<String>, InsertSource, Option<OnConflict>),
     Delete(String, Option<Predicate>),
-    Modify(String, String, Expression, Option<Predicate>),
+    // UPDATE table SET field = expression, ... WHERE predicate
+    Modify(String, Vec<(String, Expression)>, Option<Predicate>),
     Create(CreateCommand),
+    // INSERT, UPDATE or DELETE with `RETURNING expression AS name, ...`,
+    // where no expressions means every field of the table
+    Returning(Box<UpdateCommand>, Vec<(Expression, String)>),
+}
+
+impl UpdateCommand {
+    /// Calls `f` on every expression of the command, as with
+    /// [`Expression::visit_mut`].
+    pub(crate) fn visit_expressions_mut(&mut self, f: &mut impl FnMut(&mut Expression)) {
+        match self {
+            UpdateCommand::Insert(_, _, source, on_conflict) => {
+                match source {
+                    InsertSource::Values(rows) => {
+                        for expr in rows.iter_mut().flatten() {
+                            expr.visit_mut(f);
+                        }
+                    }
+                    InsertSource::Query(query) => query.visit_expressions_mut(f),
+                }
+                if let Some(OnConflict {
+                    action: ConflictAction::DoUpdate(assignments),
+                    ..
+                }) = on_conflict
+                {
+                    for (_, expr) in assignments {
+                        expr.visit_mut(f);
+                    }
+                }
+            }
+            UpdateCommand::Delete(_, predicate) => {
+                if let Some(predicate) = predicate {
+                    predicate.visit_expressions_mut(f);
+                }
+            }
+            UpdateCommand::Modify(_, assignments, predicate) => {
+                for (_, expr) in assignments {
+                    expr.visit_mut(f);
+                }
+                if let Some(predicate) = predicate {
+                    predicate.visit_expressions_mut(f);
+                }
+            }
+            UpdateCommand::Create(CreateCommand::View(_, query)) => query.visit_expressions_mut(f),
+            UpdateCommand::Create(_) => {}
+            UpdateCommand::Returning(command, fields) => {
+                command.visit_expressions_mut(f);
+                for (expr, _) in fields {
+                    expr.visit_mut(f);
+                }
+            }
+        }
+    }
+}
+
+// Statements controlling the transaction of a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionCommand {
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint(String),
+    // ROLLBACK TO [SAVEPOINT] name
+    RollbackTo(String),
+    // RELEASE [SAVEPOINT] name
+    Release(String),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CreateCommand {
     Table(String, Vec<FieldDefinition>),
-    View(String, QueryData),
+    View(String, Box<QueryData>),
     Index(String, String, String),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FieldDefinition {
     pub name: String,
     pub field_type: Spec,
@@ -3,7 +3,7 @@ use std::sync::{Arc, Mutex};
 use crate::{
     errors::TransactionError,
     parser::expression::Expression,
-    record::{field::Spec, schema::Schema},
+    record::schema::Schema,
     scan::{extend_scan::ExtendScan, Scan},
     tx::transaction::Transaction,
 };
@@ -21,7 +21,9 @@ pub struct ExtendPlan {
 impl ExtendPlan {
     pub fn new(plan: Plan, expression: Expression, field_name: &str) -> Self {
         let mut schema = plan.schema().clone();
-        let spec = Self::infer_spec(&expression, plan.schema());
+        let spec = expression
+            .infer_spec(plan.schema())
+            .unwrap_or_else(|e| panic!("Invalid expression {:?}: {}", expression, e));
         schema.add_field(field_name, &spec);
         Self {
             plan: Box::new(plan),
@@ -30,19 +32,6 @@ impl ExtendPlan {
             schema,
         }
     }
-
-    fn infer_spec(expr: &Expression, schema: &Schema) -> Spec {
-        match expr {
-            Expression::Field(f) => schema.get_field_spec(f),
-            Expression::StringConstant(s) => Spec::VarChar(s.len()),
-            Expression::Add(_, _)
-            | Expression::Sub(_, _)
-            | Expression::Mul(_, _)
-            | Expression::Div(_, _)
-            | Expression::I32Constant(_)
-            | Expression::NullConstant => Spec::I32,
-        }
-    }
 }
 
 impl PlanControl for ExtendPlan {
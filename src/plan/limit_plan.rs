@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::TransactionError,
+    record::schema::Schema,
+    scan::{limit_scan::LimitScan, Scan},
+    tx::transaction::Transaction,
+};
+
+use super::{Plan, PlanControl};
+
+#[derive(Clone)]
+pub struct LimitPlan {
+    plan: Box<Plan>,
+    limit: usize,
+    offset: usize,
+}
+
+impl LimitPlan {
+    pub fn new(plan: Plan, limit: usize, offset: usize) -> Self {
+        LimitPlan {
+            plan: Box::new(plan),
+            limit,
+            offset,
+        }
+    }
+}
+
+impl PlanControl for LimitPlan {
+    fn get_num_accessed_blocks(&self) -> usize {
+        self.plan.get_num_accessed_blocks()
+    }
+
+    fn get_num_output_records(&self) -> usize {
+        let num_records = self.plan.get_num_output_records();
+        std::cmp::min(self.limit, num_records.saturating_sub(self.offset))
+    }
+
+    fn num_distinct_values(&self, field_name: &str) -> usize {
+        std::cmp::min(
+            self.plan.num_distinct_values(field_name),
+            self.get_num_output_records(),
+        )
+    }
+
+    fn schema(&self) -> &Schema {
+        self.plan.schema()
+    }
+
+    fn open(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
+        let base_scan = self.plan.open(tx)?;
+        Ok(Scan::from(LimitScan::new(
+            base_scan,
+            self.limit,
+            self.offset,
+        )))
+    }
+}
@@ -2,17 +2,21 @@ use std::sync::{Arc, Mutex};
 
 use enum_dispatch::enum_dispatch;
 use extend_plan::ExtendPlan;
+use limit_plan::LimitPlan;
 use product_plan::ProductPlan;
 use project_plan::ProjectPlan;
 use select_plan::SelectPlan;
 use table_plan::TablePlan;
+use union_all_plan::UnionAllPlan;
 
 use crate::{
     errors::TransactionError,
     index::plan::{index_join_plan::IndexJoinPlan, index_select_plan::IndexSelectPlan},
     materialization::{
         group_by_plan::GroupByPlan, materialize_plan::MaterializePlan,
-        merge_join_plan::MergeJoinPlan, sort_plan::SortPlan,
+        merge_join_plan::MergeJoinPlan, recursive_plan::RecursivePlan,
+        set_operation_plan::SetOperationPlan, sort_plan::SortPlan, top_n_plan::TopNPlan,
+        window_plan::WindowPlan, working_table_plan::WorkingTablePlan,
     },
     multibuffer::multibuffer_product_plan::MultiBufferProductPlan,
     record::schema::Schema,
@@ -21,10 +25,12 @@ use crate::{
 };
 
 pub mod extend_plan;
+pub mod limit_plan;
 pub mod product_plan;
 pub mod project_plan;
 pub mod select_plan;
 pub mod table_plan;
+pub mod union_all_plan;
 
 #[enum_dispatch]
 #[derive(Clone)]
@@ -41,6 +47,13 @@ pub enum Plan {
     GroupByPlan(GroupByPlan),
     MergeJoinPlan(MergeJoinPlan),
     MultiBufferProductPlan(MultiBufferProductPlan),
+    LimitPlan(LimitPlan),
+    TopNPlan(TopNPlan),
+    UnionAllPlan(UnionAllPlan),
+    SetOperationPlan(SetOperationPlan),
+    RecursivePlan(RecursivePlan),
+    WorkingTablePlan(WorkingTablePlan),
+    WindowPlan(WindowPlan),
 }
 
 #[enum_dispatch(Plan)]
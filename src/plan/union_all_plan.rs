@@ -0,0 +1,161 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::TransactionError,
+    record::{field::Spec, schema::Schema},
+    scan::{union_all_scan::UnionAllScan, Scan},
+    tx::transaction::Transaction,
+};
+
+use super::{Plan, PlanControl};
+
+/// Builds the schema of a set operation whose inputs are matched by position.
+///
+/// Fields are named after the first input. Strings get the larger of the two
+/// maximum lengths so that records of either input fit.
+pub(crate) fn set_operation_schema(
+    schema1: &Schema,
+    fields1: &[String],
+    schema2: &Schema,
+    fields2: &[String],
+) -> Schema {
+    assert_eq!(fields1.len(), fields2.len());
+    let mut schema = Schema::new();
+    for (field1, field2) in fields1.iter().zip(fields2) {
+        let spec = match (
+            schema1.get_field_spec(field1),
+            schema2.get_field_spec(field2),
+        ) {
+            (Spec::VarChar(length1), Spec::VarChar(length2)) => {
+                Spec::VarChar(std::cmp::max(length1, length2))
+            }
+            (Spec::I32, Spec::I32) => Spec::I32,
+            _ => panic!("Fields {} and {} have different types", field1, field2),
+        };
+        schema.add_field(field1, &spec);
+    }
+    schema
+}
+
+/// Concatenates the records of two plans, as in `UNION ALL`.
+#[derive(Clone)]
+pub struct UnionAllPlan {
+    p1: Box<Plan>,
+    p2: Box<Plan>,
+    fields1: Vec<String>,
+    fields2: Vec<String>,
+    schema: Schema,
+}
+
+impl UnionAllPlan {
+    /// `fields1` and `fields2` list the output fields of each plan in the
+    /// order they are matched with each other.
+    pub fn new(p1: Plan, p2: Plan, fields1: Vec<String>, fields2: Vec<String>) -> Self {
+        let schema = set_operation_schema(p1.schema(), &fields1, p2.schema(), &fields2);
+        UnionAllPlan {
+            p1: Box::new(p1),
+            p2: Box::new(p2),
+            fields1,
+            fields2,
+            schema,
+        }
+    }
+}
+
+impl PlanControl for UnionAllPlan {
+    fn get_num_accessed_blocks(&self) -> usize {
+        self.p1.get_num_accessed_blocks() + self.p2.get_num_accessed_blocks()
+    }
+
+    fn get_num_output_records(&self) -> usize {
+        self.p1.get_num_output_records() + self.p2.get_num_output_records()
+    }
+
+    fn num_distinct_values(&self, field_name: &str) -> usize {
+        let index = self.fields1.iter().position(|f| f == field_name).unwrap();
+        self.p1.num_distinct_values(field_name) + self.p2.num_distinct_values(&self.fields2[index])
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn open(&mut self, tx: Arc<Mutex<Transaction>>) -> Result<Scan, TransactionError> {
+        let s1 = self.p1.open(tx.clone())?;
+        let s2 = self.p2.open(tx)?;
+        Ok(Scan::from(UnionAllScan::new(
+            s1,
+            s2,
+            self.fields1.clone(),
+            self.fields2.clone(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{db::SimpleDB, plan::table_plan::TablePlan, scan::ScanControl};
+
+    use super::*;
+
+    #[test]
+    fn test_union_all_plan() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+        let db = SimpleDB::new(temp_dir, block_size, 8)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let mut schema1 = Schema::new();
+        schema1.add_i32_field("A");
+        schema1.add_string_field("B", 5);
+        let mut schema2 = Schema::new();
+        schema2.add_i32_field("C");
+        schema2.add_string_field("D", 10);
+        {
+            let mut md = db.metadata_manager.lock().unwrap();
+            md.create_table("table1", &schema1, tx.clone())?;
+            md.create_table("table2", &schema2, tx.clone())?;
+        }
+
+        let mut p1 = TablePlan::new(tx.clone(), "table1", db.metadata_manager.clone())?;
+        let mut p2 = TablePlan::new(tx.clone(), "table2", db.metadata_manager.clone())?;
+        {
+            let mut s1 = p1.open(tx.clone())?;
+            let mut s2 = p2.open(tx.clone())?;
+            s1.before_first()?;
+            s2.before_first()?;
+            for i in 0..3 {
+                s1.insert()?;
+                s1.set_i32("A", i)?;
+                s1.set_string("B", &format!("b{}", i))?;
+                s2.insert()?;
+                s2.set_i32("C", i + 10)?;
+                s2.set_string("D", &format!("d{}", i))?;
+            }
+        }
+
+        let mut plan = UnionAllPlan::new(
+            Plan::from(p1),
+            Plan::from(p2),
+            vec!["A".to_string(), "B".to_string()],
+            vec!["C".to_string(), "D".to_string()],
+        );
+        assert_eq!(plan.schema().get_fields(), vec!["A", "B"]);
+        assert_eq!(plan.schema().get_field_spec("B"), Spec::VarChar(10));
+
+        let mut scan = plan.open(tx.clone())?;
+        scan.before_first()?;
+        for i in 0..3 {
+            assert!(scan.next()?);
+            assert_eq!(scan.get_i32("A")?, Some(i));
+            assert_eq!(scan.get_string("B")?, Some(format!("b{}", i)));
+        }
+        for i in 0..3 {
+            assert!(scan.next()?);
+            assert_eq!(scan.get_i32("A")?, Some(i + 10));
+            assert_eq!(scan.get_string("B")?, Some(format!("d{}", i)));
+        }
+        assert!(!scan.next()?);
+        Ok(())
+    }
+}
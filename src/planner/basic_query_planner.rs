@@ -2,19 +2,20 @@ use std::sync::{Arc, Mutex};
 
 use crate::{
     errors::{ExecutionError, QueryError},
-    materialization::{
-        group_by_plan::GroupByPlan, record_comparator::RecordComparator, sort_plan::SortPlan,
-    },
     metadata::MetadataManager,
     parser::statement::QueryData,
     plan::{
-        extend_plan::ExtendPlan, product_plan::ProductPlan, project_plan::ProjectPlan,
-        select_plan::SelectPlan, table_plan::TablePlan, Plan, PlanControl,
+        product_plan::ProductPlan, project_plan::ProjectPlan, select_plan::SelectPlan,
+        table_plan::TablePlan, Plan, PlanControl,
     },
     tx::transaction::Transaction,
 };
 
-use super::QueryPlanner;
+use super::{
+    add_extend_fields, check_predicate, common_tables::add_common_tables, group_by::add_group_by,
+    order_by::add_order_and_limit, set_operations::add_set_operations,
+    window::add_window_functions, CommonTablePlans, QueryPlanner,
+};
 
 pub struct BasicQueryPlanner {
     metadata_manager: Arc<Mutex<MetadataManager>>,
@@ -27,15 +28,19 @@ impl BasicQueryPlanner {
 }
 
 impl QueryPlanner for BasicQueryPlanner {
-    fn create_plan(
+    fn create_plan_with_common_tables(
         &self,
         query: &QueryData,
+        common_tables: &CommonTablePlans,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<Plan, ExecutionError> {
+        let common_tables = add_common_tables(query, common_tables, tx.clone(), self)?;
         {
             let md = self.metadata_manager.lock().unwrap();
             for table_name in &query.tables {
-                if md.get_layout(table_name, tx.clone())?.is_none() {
+                if !common_tables.contains_key(table_name)
+                    && md.get_layout(table_name, tx.clone())?.is_none()
+                {
                     return Err(QueryError::InvalidTable(table_name.to_string()).into());
                 }
             }
@@ -44,8 +49,14 @@ impl QueryPlanner for BasicQueryPlanner {
         // Step 1
         let mut plans = vec![];
         for table_name in &query.tables {
-            let table_plan = TablePlan::new(tx.clone(), table_name, self.metadata_manager.clone())?;
-            plans.push(Plan::from(table_plan));
+            match common_tables.get(table_name) {
+                Some(plan) => plans.push(plan.clone()),
+                None => {
+                    let table_plan =
+                        TablePlan::new(tx.clone(), table_name, self.metadata_manager.clone())?;
+                    plans.push(Plan::from(table_plan));
+                }
+            }
         }
 
         // Step 2
@@ -69,29 +80,21 @@ impl QueryPlanner for BasicQueryPlanner {
         }
 
         // Step 3
+        check_predicate(query, plan.schema())?;
         if let Some(predicate) = &query.predicate {
             plan = Plan::from(SelectPlan::new(plan, predicate.clone()));
         }
 
         // Step 4
-        if let Some(group_fields) = &query.group_by {
-            plan = Plan::from(GroupByPlan::new(
-                tx.clone(),
-                plan,
-                group_fields.clone(),
-                query.aggregation_functions.clone(),
-            ));
-        }
+        plan = add_group_by(plan, query, tx.clone())?;
 
         // Step 5
-        for (expr, alias) in &query.extend_fields {
-            plan = Plan::from(ExtendPlan::new(plan, expr.clone(), alias));
-        }
+        plan = add_extend_fields(plan, query)?;
+        plan = add_window_functions(plan, query, tx.clone())?;
 
         // Step 6
-        if let Some(order_fields) = &query.order_by {
-            let comparator = Arc::new(RecordComparator::new(order_fields));
-            plan = Plan::from(SortPlan::new(plan, tx.clone(), comparator));
+        if query.set_operations.is_empty() {
+            plan = add_order_and_limit(plan, query, tx.clone())?;
         }
 
         // Step 7
@@ -99,6 +102,11 @@ impl QueryPlanner for BasicQueryPlanner {
             plan = Plan::from(ProjectPlan::new(plan, fields.clone()));
         }
 
+        // Step 8
+        if !query.set_operations.is_empty() {
+            plan = add_set_operations(plan, query, &common_tables, tx.clone(), self)?;
+        }
+
         Ok(plan)
     }
 }
@@ -114,7 +122,10 @@ mod tests {
         predicate::{Predicate, Term},
     };
 
-    use crate::materialization::{aggregation_function::AggregationFn, sum_function::SumFn};
+    use crate::materialization::{
+        aggregation_function::AggregationFn, sum_function::SumFn, top_n_plan::MAX_TOP_N_RECORDS,
+    };
+    use crate::parser::statement::OrderByItem;
     use crate::record::schema::Schema;
     use crate::scan::table_scan::TableScan;
     use crate::scan::ScanControl;
@@ -275,7 +286,7 @@ mod tests {
         let query = QueryData::new_all_with_order(
             vec!["table1".to_string()],
             None,
-            Some(vec!["A".to_string()]),
+            Some(vec![OrderByItem::from("A")]),
         );
 
         let planner = BasicQueryPlanner::new(db.metadata_manager.clone());
@@ -293,6 +304,90 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_limit_basic_query_planner() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+        {
+            let mut metadata_manager = db.metadata_manager.lock().unwrap();
+            let mut schema = Schema::new();
+            schema.add_i32_field("A");
+            metadata_manager.create_table("table1", &schema, tx.clone())?;
+        }
+
+        {
+            let layout = {
+                let md = db.metadata_manager.lock().unwrap();
+                md.get_layout("table1", tx.clone())?.unwrap()
+            };
+            let mut table_scan = TableScan::new(tx.clone(), "table1", Arc::new(layout))?;
+            table_scan.before_first()?;
+            for i in (0..20).rev() {
+                table_scan.insert()?;
+                table_scan.set_i32("A", i)?;
+            }
+            drop(table_scan);
+        }
+
+        let planner = BasicQueryPlanner::new(db.metadata_manager.clone());
+
+        // Without ORDER BY, records are returned in table order
+        let query =
+            QueryData::new_all(vec!["table1".to_string()], None).with_limit(Some(3), Some(2));
+        let mut plan = planner.create_plan(&query, tx.clone())?;
+        assert!(matches!(plan, Plan::LimitPlan(_)));
+        let mut scan = plan.open(tx.clone())?;
+        scan.before_first()?;
+        for i in [17, 16, 15] {
+            assert!(scan.next()?);
+            assert_eq!(scan.get_i32("A")?, Some(i));
+        }
+        assert!(!scan.next()?);
+        drop(scan);
+
+        // With ORDER BY, only the top records are kept
+        let query = QueryData::new_all_with_order(
+            vec!["table1".to_string()],
+            None,
+            Some(vec![OrderByItem::from("A")]),
+        )
+        .with_limit(Some(3), Some(2));
+        let mut plan = planner.create_plan(&query, tx.clone())?;
+        assert!(matches!(plan, Plan::TopNPlan(_)));
+        let mut scan = plan.open(tx.clone())?;
+        scan.before_first()?;
+        for i in 2..5 {
+            assert!(scan.next()?);
+            assert_eq!(scan.get_i32("A")?, Some(i));
+        }
+        assert!(!scan.next()?);
+        drop(scan);
+
+        // Limits too large to keep in memory fall back to a full sort
+        let query = QueryData::new_all_with_order(
+            vec!["table1".to_string()],
+            None,
+            Some(vec![OrderByItem::from("A")]),
+        )
+        .with_limit(Some(MAX_TOP_N_RECORDS), Some(18));
+        let mut plan = planner.create_plan(&query, tx.clone())?;
+        assert!(matches!(plan, Plan::LimitPlan(_)));
+        let mut scan = plan.open(tx.clone())?;
+        scan.before_first()?;
+        for i in 18..20 {
+            assert!(scan.next()?);
+            assert_eq!(scan.get_i32("A")?, Some(i));
+        }
+        assert!(!scan.next()?);
+        drop(scan);
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+
     #[test]
     fn test_extend_basic_query_planner() -> Result<(), ExecutionError> {
         let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
@@ -385,7 +480,7 @@ mod tests {
             Some(vec!["A".to_string(), "B".to_string()]),
             vec!["table1".to_string()],
             None,
-            Some(vec!["A".to_string()]),
+            Some(vec![Expression::Field("A".to_string())]),
             None,
             Vec::new(),
             vec![AggregationFn::from(SumFn::new("B"))],
@@ -441,7 +536,7 @@ mod tests {
             Some(vec!["A".to_string(), "total".to_string()]),
             vec!["table1".to_string()],
             None,
-            Some(vec!["A".to_string()]),
+            Some(vec![Expression::Field("A".to_string())]),
             None,
             vec![(Expression::Field("B".to_string()), "total".to_string())],
             vec![AggregationFn::from(SumFn::new("B"))],
@@ -6,7 +6,7 @@ use crate::{
     parser::{
         expression::Expression,
         predicate::Predicate,
-        statement::{CreateCommand, UpdateCommand},
+        statement::{ConflictAction, CreateCommand, OnConflict},
     },
     plan::{select_plan::SelectPlan, table_plan::TablePlan, Plan, PlanControl},
     record::{field::Value, schema::Schema},
@@ -14,7 +14,11 @@ use crate::{
     tx::transaction::Transaction,
 };
 
-use super::UpdatePlanner;
+use super::{
+    check_assignments,
+    insert::{evaluate_conflict_update, find_record},
+    InsertCount, ReturnedRecords, UpdatePlanner,
+};
 
 pub struct BasicUpdatePlanner {
     metadata_manager: Arc<Mutex<MetadataManager>>,
@@ -25,26 +29,66 @@ impl BasicUpdatePlanner {
         Self { metadata_manager }
     }
 
-    fn insert(
+    /// Inserts the records returned by `next_record` until it returns `None`.
+    /// A record conflicting with an existing one is found by scanning the
+    /// table.
+    fn insert_records(
         &self,
         table_name: &str,
-        fields: &Vec<String>,
-        values: &Vec<Value>,
+        fields: &[String],
+        on_conflict: Option<&OnConflict>,
+        mut returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
-    ) -> Result<usize, TransactionError> {
+        mut next_record: impl FnMut() -> Result<Option<Vec<Value>>, TransactionError>,
+    ) -> Result<InsertCount, TransactionError> {
         let mut table_plan = TablePlan::new(tx.clone(), table_name, self.metadata_manager.clone())?;
+        let table_fields = table_plan.schema().get_fields();
         let mut table_scan = table_plan.open(tx)?;
-        table_scan.insert()?;
-        for (field, value) in fields.iter().zip(values.iter()) {
-            table_scan.set_value(field, value)?;
+        let conflict_index =
+            on_conflict.and_then(|c| fields.iter().position(|f| *f == c.field_name));
+
+        let mut count = InsertCount::default();
+        while let Some(values) = next_record()? {
+            if let (Some(on_conflict), Some(i)) = (on_conflict, conflict_index) {
+                if find_record(&mut table_scan, &on_conflict.field_name, &values[i])? {
+                    if let ConflictAction::DoUpdate(assignments) = &on_conflict.action {
+                        let new_values = evaluate_conflict_update(
+                            &mut table_scan,
+                            &table_fields,
+                            fields,
+                            &values,
+                            assignments,
+                        )?;
+                        for ((field_name, _), new_value) in assignments.iter().zip(&new_values) {
+                            table_scan.set_value(field_name, new_value)?;
+                        }
+                        if let Some(returned) = returned.as_deref_mut() {
+                            returned.add(&mut table_scan)?;
+                        }
+                        count.updated += 1;
+                    }
+                    continue;
+                }
+            }
+            table_scan.insert()?;
+            for (field, value) in fields.iter().zip(&values) {
+                table_scan.set_value(field, value)?;
+            }
+            if let Some(returned) = returned.as_deref_mut() {
+                returned.add(&mut table_scan)?;
+            }
+            count.inserted += 1;
         }
-        Ok(1)
+        Ok(count)
     }
+}
 
+impl UpdatePlanner for BasicUpdatePlanner {
     fn delete(
         &self,
         table_name: &str,
         predicate: &Option<Predicate>,
+        mut returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<usize, TransactionError> {
         let table_plan = Plan::from(TablePlan::new(
@@ -60,6 +104,9 @@ impl BasicUpdatePlanner {
         let mut scan = plan.open(tx.clone())?;
         let mut count = 0;
         while scan.next()? {
+            if let Some(returned) = returned.as_deref_mut() {
+                returned.add(&mut scan)?;
+            }
             scan.delete()?;
             count += 1;
         }
@@ -69,12 +116,13 @@ impl BasicUpdatePlanner {
     fn modify(
         &self,
         table_name: &str,
-        field_name: &str,
-        expression: &Expression,
+        assignments: &[(String, Expression)],
         predicate: &Option<Predicate>,
+        mut returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<usize, TransactionError> {
         let table_plan = TablePlan::new(tx.clone(), table_name, self.metadata_manager.clone())?;
+        check_assignments(table_plan.schema(), assignments)?;
         let table_plan = Plan::from(table_plan);
         let mut plan = if let Some(predicate) = predicate {
             Plan::from(SelectPlan::new(table_plan, predicate.clone()))
@@ -85,8 +133,17 @@ impl BasicUpdatePlanner {
         let mut scan = plan.open(tx.clone())?;
         let mut count = 0;
         while scan.next()? {
-            let new_value = expression.evaluate(&mut scan)?;
-            scan.set_value(field_name, &new_value)?;
+            // Evaluate every expression against the record before changing it
+            let mut new_values = Vec::with_capacity(assignments.len());
+            for (_, expression) in assignments {
+                new_values.push(expression.evaluate(&mut scan)?);
+            }
+            for ((field_name, _), new_value) in assignments.iter().zip(&new_values) {
+                scan.set_value(field_name, new_value)?;
+            }
+            if let Some(returned) = returned.as_deref_mut() {
+                returned.add(&mut scan)?;
+            }
             count += 1;
         }
         Ok(count)
@@ -107,24 +164,45 @@ impl BasicUpdatePlanner {
         }
         Ok(0)
     }
-}
 
-impl UpdatePlanner for BasicUpdatePlanner {
-    fn execute_update(
+    fn insert(
         &self,
-        update_command: &UpdateCommand,
+        table_name: &str,
+        fields: &[String],
+        rows: &[Vec<Expression>],
+        on_conflict: Option<&OnConflict>,
+        returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
-    ) -> Result<usize, TransactionError> {
-        match update_command {
-            UpdateCommand::Insert(table_name, fields, values) => {
-                self.insert(table_name, fields, values, tx)
-            }
-            UpdateCommand::Delete(table_name, predicate) => self.delete(table_name, predicate, tx),
-            UpdateCommand::Modify(table_name, field_name, expression, predicate) => {
-                self.modify(table_name, field_name, expression, predicate, tx)
+    ) -> Result<InsertCount, TransactionError> {
+        let mut rows = rows.iter();
+        self.insert_records(table_name, fields, on_conflict, returned, tx, || {
+            rows.next()
+                .map(|row| row.iter().map(Expression::evaluate_constant).collect())
+                .transpose()
+        })
+    }
+
+    fn insert_from_plan(
+        &self,
+        table_name: &str,
+        fields: &[(String, String)],
+        mut plan: Plan,
+        on_conflict: Option<&OnConflict>,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<InsertCount, TransactionError> {
+        let (fields, source_fields): (Vec<String>, Vec<String>) = fields.iter().cloned().unzip();
+        let mut scan = plan.open(tx.clone())?;
+        self.insert_records(table_name, &fields, on_conflict, returned, tx, || {
+            if !scan.next()? {
+                return Ok(None);
             }
-            UpdateCommand::Create(create_command) => self.create(create_command, tx),
-        }
+            source_fields
+                .iter()
+                .map(|f| scan.get_value(f))
+                .collect::<Result<_, _>>()
+                .map(Some)
+        })
     }
 }
 
@@ -154,37 +232,39 @@ mod tests {
         let tx = Arc::new(Mutex::new(db.new_transaction()?));
 
         let planner = BasicUpdatePlanner::new(db.metadata_manager.clone());
-        let updated = planner.execute_update(
-            &UpdateCommand::Create(CreateCommand::Table(
+        let updated = planner.create(
+            &CreateCommand::Table(
                 "table1".to_string(),
                 vec![
                     FieldDefinition::new("A".to_string(), Spec::I32),
                     FieldDefinition::new("B".to_string(), Spec::VarChar(20)),
                 ],
-            )),
+            ),
             tx.clone(),
         )?;
         assert_eq!(updated, 0);
 
         for i in 0..10 {
-            let updated = planner.execute_update(
-                &UpdateCommand::Insert(
-                    "table1".to_string(),
-                    vec!["A".to_string(), "B".to_string()],
-                    vec![Value::I32(i % 2), Value::String(i.to_string())],
-                ),
+            let count = planner.insert(
+                "table1",
+                &["A".to_string(), "B".to_string()],
+                &[vec![
+                    Expression::I32Constant(i % 2),
+                    Expression::StringConstant(i.to_string()),
+                ]],
+                None,
+                None,
                 tx.clone(),
             )?;
-            assert_eq!(updated, 1);
+            assert_eq!(count.total(), 1);
         }
-        let deleted = planner.execute_update(
-            &UpdateCommand::Delete(
-                "table1".to_string(),
-                Some(Predicate::new(vec![Term::Equality(
-                    Expression::Field("A".to_string()),
-                    Expression::I32Constant(0),
-                )])),
-            ),
+        let deleted = planner.delete(
+            "table1",
+            &Some(Predicate::new(vec![Term::Equality(
+                Expression::Field("A".to_string()),
+                Expression::I32Constant(0),
+            )])),
+            None,
             tx.clone(),
         )?;
         assert_eq!(deleted, 5);
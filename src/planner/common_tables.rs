@@ -0,0 +1,255 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::{ExecutionError, QueryError},
+    materialization::{
+        materialize_plan::MaterializePlan,
+        recursive_plan::RecursivePlan,
+        working_table_plan::{WorkingTable, WorkingTablePlan},
+    },
+    parser::statement::{CommonTableExpression, QueryData, SetOperator},
+    plan::Plan,
+    tx::transaction::Transaction,
+};
+
+use super::{
+    set_operations::{check_compatible_fields, output_fields},
+    CommonTablePlans, QueryPlanner,
+};
+
+/// Plans the common tables declared by the `WITH` clause of `query` and
+/// returns them along with those already visible in `common_tables`.
+///
+/// Common tables are inlined into every query that refers to them unless
+/// declared `AS MATERIALIZED`, in which case their records are written to a
+/// temp table when the plan is opened. Recursive common tables are always
+/// materialized by a [`RecursivePlan`].
+pub(crate) fn add_common_tables(
+    query: &QueryData,
+    common_tables: &CommonTablePlans,
+    tx: Arc<Mutex<Transaction>>,
+    query_planner: &dyn QueryPlanner,
+) -> Result<CommonTablePlans, ExecutionError> {
+    let mut common_tables = common_tables.clone();
+    for cte in &query.common_tables {
+        let plan = if cte.recursive && cte.query.references_table(&cte.name) {
+            plan_recursive_common_table(cte, &common_tables, tx.clone(), query_planner)?
+        } else {
+            let plan = query_planner.create_plan_with_common_tables(
+                &cte.query,
+                &common_tables,
+                tx.clone(),
+            )?;
+            if cte.materialized {
+                Plan::from(MaterializePlan::new(plan, tx.clone()))
+            } else {
+                plan
+            }
+        };
+        common_tables.insert(cte.name.clone(), plan);
+    }
+    Ok(common_tables)
+}
+
+/// Plans a common table of the form `anchor UNION [ALL] recursive`, where only
+/// `recursive` refers to the common table itself.
+fn plan_recursive_common_table(
+    cte: &CommonTableExpression,
+    common_tables: &CommonTablePlans,
+    tx: Arc<Mutex<Transaction>>,
+    query_planner: &dyn QueryPlanner,
+) -> Result<Plan, ExecutionError> {
+    let invalid =
+        |reason: &str| QueryError::InvalidCommonTable(cte.name.clone(), reason.to_string());
+    let query = &cte.query;
+    let (operator, recursive_query) = match query.set_operations.as_slice() {
+        [(operator @ (SetOperator::Union | SetOperator::UnionAll), recursive_query)] => {
+            (*operator, recursive_query)
+        }
+        _ => return Err(invalid("expected SELECT ... UNION [ALL] SELECT ...").into()),
+    };
+    if query.order_by.is_some() || query.limit.is_some() || query.offset.is_some() {
+        return Err(invalid("ORDER BY and LIMIT are not supported in recursive queries").into());
+    }
+    let anchor_query = QueryData {
+        set_operations: Vec::new(),
+        ..query.clone()
+    };
+    if anchor_query.references_table(&cte.name) {
+        return Err(invalid("the first SELECT must not refer to the common table").into());
+    }
+
+    let anchor =
+        query_planner.create_plan_with_common_tables(&anchor_query, common_tables, tx.clone())?;
+    let anchor_fields = output_fields(&anchor_query, &anchor);
+
+    let working_table = WorkingTable::default();
+    let mut recursive_common_tables = common_tables.clone();
+    recursive_common_tables.insert(
+        cte.name.clone(),
+        Plan::from(WorkingTablePlan::new(anchor.clone(), working_table.clone())),
+    );
+    let recursive = query_planner.create_plan_with_common_tables(
+        recursive_query,
+        &recursive_common_tables,
+        tx,
+    )?;
+    let recursive_fields = output_fields(recursive_query, &recursive);
+    check_compatible_fields(&anchor, &anchor_fields, &recursive, &recursive_fields)
+        .map_err(|reason| invalid(&reason))?;
+
+    Ok(Plan::from(RecursivePlan::new(
+        &cte.name,
+        anchor,
+        recursive,
+        anchor_fields,
+        recursive_fields,
+        operator == SetOperator::Union,
+        working_table,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::SimpleDB,
+        errors::TransactionError,
+        materialization::recursive_plan::MAX_RECURSION_ITERATIONS,
+        plan::PlanControl,
+        planner::{
+            basic_query_planner::BasicQueryPlanner, heuristic_query_planner::HeuristicQueryPlanner,
+            index_update_planner::IndexUpdatePlanner, Planner,
+        },
+        record::field::Value,
+        scan::ScanControl,
+    };
+
+    #[test]
+    fn test_common_tables() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = db.planner.lock().unwrap();
+        planner.execute_update(
+            "create table emp (Id I32, Name VARCHAR(10), Manager I32)",
+            tx.clone(),
+        )?;
+        for (id, name, manager) in [(1, "ann", "null"), (2, "bob", "1"), (3, "cid", "1")]
+            .into_iter()
+            .chain([(4, "dan", "2"), (5, "eve", "4")])
+        {
+            planner.execute_update(
+                &format!(
+                    "insert into emp (Id, Name, Manager) values ({}, '{}', {})",
+                    id, name, manager
+                ),
+                tx.clone(),
+            )?;
+        }
+        planner.execute_update("create table edge (Src I32, Dst I32)", tx.clone())?;
+        for (src, dst) in [(1, 2), (2, 3), (3, 1)] {
+            planner.execute_update(
+                &format!("insert into edge (Src, Dst) values ({}, {})", src, dst),
+                tx.clone(),
+            )?;
+        }
+        drop(planner);
+
+        let query_planners: [Box<dyn QueryPlanner>; 2] = [
+            Box::new(BasicQueryPlanner::new(db.metadata_manager.clone())),
+            Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+        ];
+        for query_planner in query_planners {
+            let update_planner = Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone()));
+            let planner = Planner::new(query_planner, update_planner);
+            let collect = |query: &str, field_name: &str| -> Result<Vec<Value>, ExecutionError> {
+                let mut plan = planner.create_query_plan(query, tx.clone())?;
+                let mut scan = plan.open(tx.clone())?;
+                let mut result = vec![];
+                while scan.next()? {
+                    result.push(scan.get_value(field_name)?);
+                }
+                result.sort();
+                Ok(result)
+            };
+            let strings = |values: &[&str]| {
+                values
+                    .iter()
+                    .map(|v| Value::String(v.to_string()))
+                    .collect::<Vec<_>>()
+            };
+            let integers =
+                |values: &[i32]| values.iter().map(|v| Value::I32(*v)).collect::<Vec<_>>();
+
+            for materialized in ["", "materialized"] {
+                assert_eq!(
+                    collect(
+                        &format!(
+                            "with reports as {} (select Id as ReportId from emp where Manager = 1) \
+                             select Name from emp, reports where Id = ReportId",
+                            materialized
+                        ),
+                        "Name"
+                    )?,
+                    strings(&["bob", "cid"])
+                );
+            }
+
+            // Everyone below bob in the org chart
+            assert_eq!(
+                collect(
+                    "with recursive sub as ( \
+                         select Id as SubId from emp where Manager = 2 \
+                         union all \
+                         select Id from emp, sub where Manager = SubId \
+                     ) \
+                     select Name from emp, sub where Id = SubId",
+                    "Name"
+                )?,
+                strings(&["dan", "eve"])
+            );
+
+            // UNION drops records already seen, which ends the recursion on cycles
+            let reachable = "with recursive reach as ( \
+                                 select Dst as Node from edge where Src = 1 \
+                                 union{} \
+                                 select Dst from edge, reach where Src = Node \
+                             ) \
+                             select Node from reach";
+            assert_eq!(
+                collect(&reachable.replace("{}", ""), "Node")?,
+                integers(&[1, 2, 3])
+            );
+            match collect(&reachable.replace("{}", " all"), "Node") {
+                Err(ExecutionError::TransactionError(
+                    TransactionError::RecursionLimitExceeded(name, iterations),
+                )) => {
+                    assert_eq!(name, "reach");
+                    assert_eq!(iterations, MAX_RECURSION_ITERATIONS);
+                }
+                _ => panic!("Expected RecursionLimitExceeded error"),
+            }
+
+            assert!(matches!(
+                planner.create_query_plan(
+                    "with recursive r as ( \
+                         select Id as X from emp, r where Id = X \
+                         union select Id from emp \
+                     ) \
+                     select X from r",
+                    tx.clone()
+                ),
+                Err(ExecutionError::QueryError(QueryError::InvalidCommonTable(
+                    _,
+                    _
+                )))
+            ));
+        }
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::QueryError,
+    materialization::group_by_plan::GroupByPlan,
+    parser::statement::QueryData,
+    plan::{extend_plan::ExtendPlan, Plan, PlanControl},
+    tx::transaction::Transaction,
+};
+
+/// Returns the names of the fields holding the values of the GROUP BY
+/// expressions of the query.
+///
+/// An expression that is not a field is named after the first alias it has in
+/// the select list, if any.
+pub(super) fn group_field_names(query: &QueryData) -> Vec<String> {
+    let group_by = query.group_by.as_deref().unwrap_or_default();
+    group_by
+        .iter()
+        .enumerate()
+        .map(|(i, expr)| match expr.try_get_field() {
+            Some(field_name) => field_name.to_string(),
+            None => query
+                .extend_fields
+                .iter()
+                .find(|(e, _)| e == expr)
+                .map_or_else(|| format!("__group_by_{}", i), |(_, alias)| alias.clone()),
+        })
+        .collect()
+}
+
+/// Groups the records of `plan` by the GROUP BY expressions of the query and
+/// computes its aggregation functions.
+///
+/// GROUP BY expressions that are not fields are computed by an
+/// [`ExtendPlan`] before grouping. A query with aggregation functions but
+/// without GROUP BY aggregates all records into a single record.
+pub(crate) fn add_group_by(
+    mut plan: Plan,
+    query: &QueryData,
+    tx: Arc<Mutex<Transaction>>,
+) -> Result<Plan, QueryError> {
+    if query.group_by.is_none() && query.aggregation_functions.is_empty() {
+        return Ok(plan);
+    }
+
+    let group_fields = group_field_names(query);
+    let group_by = query.group_by.as_deref().unwrap_or_default();
+    for (expr, field_name) in group_by.iter().zip(&group_fields) {
+        expr.infer_spec(plan.schema())
+            .map_err(QueryError::InvalidExpression)?;
+        if expr.try_get_field().is_none() {
+            plan = Plan::from(ExtendPlan::new(plan, expr.clone(), field_name));
+        }
+    }
+    for aggregation_fn in &query.aggregation_functions {
+        aggregation_fn
+            .output_spec(plan.schema())
+            .map_err(QueryError::InvalidExpression)?;
+    }
+    Ok(Plan::from(GroupByPlan::new(
+        tx,
+        plan,
+        group_fields,
+        query.aggregation_functions.clone(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::SimpleDB,
+        errors::ExecutionError,
+        planner::{
+            basic_query_planner::BasicQueryPlanner, heuristic_query_planner::HeuristicQueryPlanner,
+            index_update_planner::IndexUpdatePlanner, Planner, QueryPlanner,
+        },
+        record::field::Value,
+        scan::ScanControl,
+    };
+
+    #[test]
+    fn test_aggregate_expressions() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        {
+            let planner = db.planner.lock().unwrap();
+            planner.execute_update(
+                "create table orders (Item VARCHAR(10), Price I32, Qty I32)",
+                tx.clone(),
+            )?;
+            for (item, price, qty) in [
+                ("'a'", "3", "5"),
+                ("'b'", "10", "12"),
+                ("'c'", "7", "null"),
+                ("'d'", "2", "15"),
+            ] {
+                planner.execute_update(
+                    &format!(
+                        "insert into orders (Item, Price, Qty) values ({}, {}, {})",
+                        item, price, qty
+                    ),
+                    tx.clone(),
+                )?;
+            }
+        }
+
+        let query_planners: [Box<dyn QueryPlanner>; 2] = [
+            Box::new(BasicQueryPlanner::new(db.metadata_manager.clone())),
+            Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+        ];
+        for query_planner in query_planners {
+            let update_planner = Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone()));
+            let planner = Planner::new(query_planner, update_planner);
+            let collect =
+                |query: &str, fields: &[&str]| -> Result<Vec<Vec<Value>>, ExecutionError> {
+                    let mut plan = planner.create_query_plan(query, tx.clone())?;
+                    let mut scan = plan.open(tx.clone())?;
+                    let mut result = vec![];
+                    while scan.next()? {
+                        let mut record = vec![];
+                        for field_name in fields {
+                            record.push(scan.get_value(field_name)?);
+                        }
+                        result.push(record);
+                    }
+                    Ok(result)
+                };
+
+            assert_eq!(
+                collect(
+                    "select sum(Price * Qty) as revenue, count(*) as n, count(Qty) as q from orders",
+                    &["revenue", "n", "q"]
+                )?,
+                vec![vec![Value::I32(165), Value::I32(4), Value::I32(3)]]
+            );
+            assert_eq!(
+                collect("select count(*) from orders", &["count"])?,
+                vec![vec![Value::I32(4)]]
+            );
+            // Without GROUP BY, an empty input still gives one record
+            assert_eq!(
+                collect(
+                    "select count(*) as n, max(Price + 1) as m, sum(Qty) as s from orders where Item = 'x'",
+                    &["n", "m", "s"]
+                )?,
+                vec![vec![Value::I32(0), Value::Null, Value::Null]]
+            );
+            assert_eq!(
+                collect(
+                    "select Qty / 10 as bucket, count(*) as n, avg(Price) as p from orders group by Qty / 10 order by bucket",
+                    &["bucket", "n", "p"]
+                )?,
+                vec![
+                    vec![Value::I32(0), Value::I32(1), Value::I32(3)],
+                    vec![Value::I32(1), Value::I32(2), Value::I32(6)],
+                    vec![Value::Null, Value::I32(1), Value::I32(7)],
+                ]
+            );
+            assert_eq!(
+                collect(
+                    "select max(upper(Item)) as m from orders group by Qty / 10 order by m",
+                    &["m"]
+                )?,
+                [["A"], ["C"], ["D"]].map(|[m]| vec![Value::String(m.to_string())])
+            );
+            // With GROUP BY, an empty input gives no record
+            assert!(collect(
+                "select count(*) as n from orders where Item = 'x' group by Qty",
+                &["n"]
+            )?
+            .is_empty());
+
+            for query in [
+                "select sum(Item) as s from orders",
+                "select avg(Unknown) as s from orders",
+                "select count(*) as n from orders group by Unknown + 1",
+            ] {
+                assert!(matches!(
+                    planner.create_query_plan(query, tx.clone()),
+                    Err(ExecutionError::QueryError(QueryError::InvalidExpression(_)))
+                ));
+            }
+        }
+
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
@@ -1,18 +1,18 @@
 use std::sync::{Arc, Mutex};
 
 use crate::errors::{ExecutionError, QueryError, TransactionError};
-use crate::materialization::{
-    group_by_plan::GroupByPlan, record_comparator::RecordComparator, sort_plan::SortPlan,
-};
 use crate::metadata::MetadataManager;
 use crate::parser::statement::QueryData;
-use crate::plan::extend_plan::ExtendPlan;
 use crate::plan::project_plan::ProjectPlan;
 use crate::plan::{Plan, PlanControl};
 use crate::tx::transaction::Transaction;
 
 use super::table_planner::TablePlanner;
-use super::QueryPlanner;
+use super::{
+    add_extend_fields, check_predicate, common_tables::add_common_tables, group_by::add_group_by,
+    order_by::add_order_and_limit, set_operations::add_set_operations,
+    window::add_window_functions, CommonTablePlans, QueryPlanner,
+};
 
 pub struct HeuristicQueryPlanner {
     metadata_manager: Arc<Mutex<MetadataManager>>,
@@ -98,15 +98,19 @@ impl HeuristicQueryPlanner {
 }
 
 impl QueryPlanner for HeuristicQueryPlanner {
-    fn create_plan(
+    fn create_plan_with_common_tables(
         &self,
         query: &QueryData,
+        common_tables: &CommonTablePlans,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<Plan, ExecutionError> {
+        let common_tables = add_common_tables(query, common_tables, tx.clone(), self)?;
         {
             let md = self.metadata_manager.lock().unwrap();
             for table_name in &query.tables {
-                if md.get_layout(table_name, tx.clone())?.is_none() {
+                if !common_tables.contains_key(table_name)
+                    && md.get_layout(table_name, tx.clone())?.is_none()
+                {
                     return Err(QueryError::InvalidTable(table_name.to_string()).into());
                 }
             }
@@ -115,12 +119,17 @@ impl QueryPlanner for HeuristicQueryPlanner {
         // Step 1, Create a TablePlanner object for each mentioned table
         let mut table_planners = vec![];
         for table_name in &query.tables {
-            let table_planner = TablePlanner::new(
-                table_name,
-                &query.predicate,
-                tx.clone(),
-                self.metadata_manager.clone(),
-            )?;
+            let table_planner = match common_tables.get(table_name) {
+                Some(plan) => {
+                    TablePlanner::new_common_table(plan.clone(), &query.predicate, tx.clone())
+                }
+                None => TablePlanner::new(
+                    table_name,
+                    &query.predicate,
+                    tx.clone(),
+                    self.metadata_manager.clone(),
+                )?,
+            };
             table_planners.push(table_planner);
         }
 
@@ -143,25 +152,19 @@ impl QueryPlanner for HeuristicQueryPlanner {
             }
         }
 
-        // Step 4, Group by if specified
-        if let Some(group_fields) = &query.group_by {
-            current_plan = Plan::from(GroupByPlan::new(
-                tx.clone(),
-                current_plan,
-                group_fields.clone(),
-                query.aggregation_functions.clone(),
-            ));
-        }
+        check_predicate(query, current_plan.schema())?;
 
-        // Step 5, Extend fields using expressions
-        for (expr, alias) in &query.extend_fields {
-            current_plan = Plan::from(ExtendPlan::new(current_plan, expr.clone(), alias));
-        }
+        // Step 4, Group by and aggregate if specified
+        current_plan = add_group_by(current_plan, query, tx.clone())?;
+
+        // Step 5, Extend fields using expressions and window functions
+        current_plan = add_extend_fields(current_plan, query)?;
+        current_plan = add_window_functions(current_plan, query, tx.clone())?;
 
-        // Step 6, apply ordering if specified
-        if let Some(order_fields) = &query.order_by {
-            let comparator = Arc::new(RecordComparator::new(order_fields));
-            current_plan = Plan::from(SortPlan::new(current_plan, tx.clone(), comparator));
+        // Step 6, apply ordering and limit if specified. Compound queries are
+        // ordered after combining all of their SELECT blocks.
+        if query.set_operations.is_empty() {
+            current_plan = add_order_and_limit(current_plan, query, tx.clone())?;
         }
 
         // Step 7, Project on the field names
@@ -169,6 +172,12 @@ impl QueryPlanner for HeuristicQueryPlanner {
             current_plan = Plan::from(ProjectPlan::new(current_plan, fields.clone()));
         }
 
+        // Step 8, Combine with the other SELECT blocks of a compound query
+        if !query.set_operations.is_empty() {
+            current_plan =
+                add_set_operations(current_plan, query, &common_tables, tx.clone(), self)?;
+        }
+
         Ok(current_plan)
     }
 }
@@ -346,7 +355,7 @@ mod tests {
             Some(vec!["A".to_string(), "B".to_string()]),
             vec![table.to_string()],
             None,
-            Some(vec!["A".to_string()]),
+            Some(vec![Expression::Field("A".to_string())]),
             None,
             Vec::new(),
             vec![AggregationFn::from(SumFn::new("B"))],
@@ -398,7 +407,7 @@ mod tests {
             Some(vec!["A".to_string(), "total".to_string()]),
             vec![table.to_string()],
             None,
-            Some(vec!["A".to_string()]),
+            Some(vec![Expression::Field("A".to_string())]),
             None,
             vec![(Expression::Field("B".to_string()), "total".to_string())],
             vec![AggregationFn::from(SumFn::new("B"))],
@@ -2,17 +2,21 @@ use std::sync::{Arc, Mutex};
 
 use crate::{
     errors::TransactionError,
-    index::IndexControl,
+    index::{Index, IndexControl},
     metadata::MetadataManager,
     parser::{
         expression::Expression,
         predicate::Predicate,
-        statement::{CreateCommand, UpdateCommand},
+        statement::{ConflictAction, CreateCommand, OnConflict},
     },
     plan::{select_plan::SelectPlan, table_plan::TablePlan, Plan, PlanControl},
-    planner::UpdatePlanner,
+    planner::{
+        check_assignments,
+        insert::{evaluate_conflict_update, find_record},
+        InsertCount, ReturnedRecords, UpdatePlanner,
+    },
     record::{field::Value, schema::Schema},
-    scan::{RecordId, Scan, ScanControl},
+    scan::{RecordId, RecordPointer, Scan, ScanControl},
     tx::transaction::Transaction,
 };
 
@@ -25,46 +29,185 @@ impl IndexUpdatePlanner {
         Self { metadata_manager }
     }
 
-    fn insert(
+    /// Inserts the records returned by `next_record` until it returns `None`.
+    ///
+    /// With an `ON CONFLICT` clause, each record is first looked up in the
+    /// index on the conflict field, or in the whole table if the field has no
+    /// index, and an existing record is updated the way [`Self::modify`]
+    /// updates records.
+    fn insert_records(
         &self,
         table_name: &str,
-        fields: &Vec<String>,
-        values: &Vec<Value>,
+        fields: &[String],
+        on_conflict: Option<&OnConflict>,
+        mut returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
-    ) -> Result<usize, TransactionError> {
+        mut next_record: impl FnMut() -> Result<Option<Vec<Value>>, TransactionError>,
+    ) -> Result<InsertCount, TransactionError> {
         let mut table_plan = TablePlan::new(tx.clone(), table_name, self.metadata_manager.clone())?;
+        let table_fields = table_plan.schema().get_fields();
+        let mut table_scan = table_plan.open(tx.clone())?;
+        let mut indexes = self.open_indexes(table_name, fields, tx.clone())?;
 
-        // First, insert a new record into the table
-        let table_scan = table_plan.open(tx.clone())?;
-        let mut table_scan = match table_scan {
-            Scan::TableScan(table_scan) => table_scan,
-            _ => panic!("Expected TableScan"),
+        let conflict_position =
+            on_conflict.and_then(|c| fields.iter().position(|f| *f == c.field_name));
+        let mut conflict_index = match (on_conflict, conflict_position) {
+            (Some(on_conflict), Some(_)) => self
+                .open_indexes(
+                    table_name,
+                    std::slice::from_ref(&on_conflict.field_name),
+                    tx.clone(),
+                )?
+                .pop()
+                .unwrap(),
+            _ => None,
+        };
+        let (assignments, mut assignment_indexes) = match on_conflict.map(|c| &c.action) {
+            Some(ConflictAction::DoUpdate(assignments)) => {
+                let field_names: Vec<String> = assignments.iter().map(|(f, _)| f.clone()).collect();
+                let indexes = self.open_indexes(table_name, &field_names, tx)?;
+                (assignments.as_slice(), indexes)
+            }
+            _ => (&[][..], vec![]),
         };
 
-        table_scan.insert()?;
-        let record_pointer = table_scan.get_record_pointer();
+        let mut count = InsertCount::default();
+        while let Some(values) = next_record()? {
+            if let (Some(on_conflict), Some(i)) = (on_conflict, conflict_position) {
+                let conflict = Self::find_conflict(
+                    &mut table_scan,
+                    &mut conflict_index,
+                    &on_conflict.field_name,
+                    &values[i],
+                )?;
+                if conflict {
+                    if let ConflictAction::DoUpdate(_) = on_conflict.action {
+                        let new_values = evaluate_conflict_update(
+                            &mut table_scan,
+                            &table_fields,
+                            fields,
+                            &values,
+                            assignments,
+                        )?;
+                        Self::update_record(
+                            &mut table_scan,
+                            assignments,
+                            &new_values,
+                            &mut assignment_indexes,
+                        )?;
+                        if let Some(returned) = returned.as_deref_mut() {
+                            returned.add(&mut table_scan)?;
+                        }
+                        count.updated += 1;
+                    }
+                    continue;
+                }
+            }
+            Self::insert_record(&mut table_scan, fields, &values, &mut indexes)?;
+            if let Some(returned) = returned.as_deref_mut() {
+                returned.add(&mut table_scan)?;
+            }
+            count.inserted += 1;
+        }
+        Ok(count)
+    }
+
+    /// Moves `table_scan` to a record whose `field_name` equals `value`,
+    /// probing the index on the field if there is one.
+    fn find_conflict(
+        table_scan: &mut Scan,
+        index: &mut Option<Index>,
+        field_name: &str,
+        value: &Value,
+    ) -> Result<bool, TransactionError> {
+        let Some(index) = index else {
+            return find_record(table_scan, field_name, value);
+        };
+        if *value == Value::Null {
+            return Ok(false);
+        }
+        index.before_first(value)?;
+        if !index.next()? {
+            return Ok(false);
+        }
+        table_scan.move_to_record_pointer(&RecordPointer::from(index.get()?))?;
+        Ok(true)
+    }
 
+    /// Opens the index on each of `fields`, if any.
+    fn open_indexes(
+        &self,
+        table_name: &str,
+        fields: &[String],
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<Vec<Option<Index>>, TransactionError> {
         let index_info_map = self
             .metadata_manager
             .lock()
             .unwrap()
-            .get_index_info(table_name, tx.clone())?;
+            .get_index_info(table_name, tx)?;
+        let mut indexes = Vec::with_capacity(fields.len());
+        for field_name in fields {
+            let index = match index_info_map.get(field_name) {
+                Some(index_info) => Some(index_info.open()?),
+                None => None,
+            };
+            indexes.push(index);
+        }
+        Ok(indexes)
+    }
 
-        for (field, value) in fields.iter().zip(values.iter()) {
-            table_scan.set_value(field, value)?;
+    /// Inserts a record into the table and its ID into the index of each field.
+    fn insert_record(
+        table_scan: &mut Scan,
+        fields: &[String],
+        values: &[Value],
+        indexes: &mut [Option<Index>],
+    ) -> Result<(), TransactionError> {
+        // First, insert a new record into the table
+        table_scan.insert()?;
+        let record_id = RecordId::from(table_scan.get_record_pointer());
+        for ((field_name, value), index) in fields.iter().zip(values).zip(indexes) {
+            table_scan.set_value(field_name, value)?;
 
-            if let Some(index_info) = index_info_map.get(field) {
-                let mut index = index_info.open()?;
-                index.insert(value, &RecordId::from(record_pointer))?;
+            if let Some(index) = index {
+                index.insert(value, &record_id)?;
             }
         }
-        Ok(1)
+        Ok(())
     }
 
+    /// Writes `new_values` into the fields of `assignments` in the current
+    /// record of `scan`, moving the record ID between the keys of each index.
+    fn update_record(
+        scan: &mut Scan,
+        assignments: &[(String, Expression)],
+        new_values: &[Value],
+        indexes: &mut [Option<Index>],
+    ) -> Result<(), TransactionError> {
+        let record_id = RecordId::from(scan.get_record_pointer());
+        for (((field_name, _), new_value), index) in assignments.iter().zip(new_values).zip(indexes)
+        {
+            let old_value = scan.get_value(field_name)?;
+
+            // First, update the record in the table
+            scan.set_value(field_name, new_value)?;
+
+            if let Some(index) = index {
+                index.delete(&old_value, &record_id)?;
+                index.insert(new_value, &record_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl UpdatePlanner for IndexUpdatePlanner {
     fn delete(
         &self,
         table_name: &str,
         predicate: &Option<Predicate>,
+        mut returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<usize, TransactionError> {
         let table_plan = TablePlan::new(tx.clone(), table_name, self.metadata_manager.clone())?;
@@ -83,6 +226,10 @@ impl IndexUpdatePlanner {
         let mut scan = plan.open(tx.clone())?;
         let mut count = 0;
         while scan.next()? {
+            if let Some(returned) = returned.as_deref_mut() {
+                returned.add(&mut scan)?;
+            }
+
             // First, delete the record's ID from each index
             let record_pointer = scan.get_record_pointer();
             for (field_name, index_info) in index_info_map.iter() {
@@ -98,15 +245,19 @@ impl IndexUpdatePlanner {
         Ok(count)
     }
 
+    /// Sets the fields of `assignments` in the records satisfying the
+    /// predicate. The expressions of a record are all evaluated before any of
+    /// its fields is written, so they see the record as it was.
     fn modify(
         &self,
         table_name: &str,
-        field_name: &str,
-        expression: &Expression,
+        assignments: &[(String, Expression)],
         predicate: &Option<Predicate>,
+        mut returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<usize, TransactionError> {
         let table_plan = TablePlan::new(tx.clone(), table_name, self.metadata_manager.clone())?;
+        check_assignments(table_plan.schema(), assignments)?;
         let table_plan = Plan::from(table_plan);
         let mut plan = if let Some(predicate) = predicate {
             Plan::from(SelectPlan::new(table_plan, predicate.clone()))
@@ -114,30 +265,20 @@ impl IndexUpdatePlanner {
             table_plan
         };
 
-        let index_info_map = self
-            .metadata_manager
-            .lock()
-            .unwrap()
-            .get_index_info(table_name, tx.clone())?;
-        let index = index_info_map.get(field_name).map(|info| info.open());
-        let mut index = match index {
-            Some(index_info) => Some(index_info?),
-            _ => None,
-        };
+        let field_names: Vec<String> = assignments.iter().map(|(f, _)| f.clone()).collect();
+        let mut indexes = self.open_indexes(table_name, &field_names, tx.clone())?;
 
         let mut scan = plan.open(tx.clone())?;
         let mut count = 0;
         while scan.next()? {
-            let new_value = expression.evaluate(&mut scan)?;
-            let old_value = scan.get_value(field_name)?;
-
-            // First, update the record in the table
-            scan.set_value(field_name, &new_value)?;
+            let mut new_values = Vec::with_capacity(assignments.len());
+            for (_, expression) in assignments {
+                new_values.push(expression.evaluate(&mut scan)?);
+            }
 
-            if let Some(index) = &mut index {
-                let record_id = RecordId::from(scan.get_record_pointer());
-                index.delete(&old_value, &record_id)?;
-                index.insert(&new_value, &record_id)?;
+            Self::update_record(&mut scan, assignments, &new_values, &mut indexes)?;
+            if let Some(returned) = returned.as_deref_mut() {
+                returned.add(&mut scan)?;
             }
             count += 1;
         }
@@ -163,23 +304,145 @@ impl IndexUpdatePlanner {
         }
         Ok(0)
     }
-}
 
-impl UpdatePlanner for IndexUpdatePlanner {
-    fn execute_update(
+    fn insert(
+        &self,
+        table_name: &str,
+        fields: &[String],
+        rows: &[Vec<Expression>],
+        on_conflict: Option<&OnConflict>,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<InsertCount, TransactionError> {
+        let mut rows = rows.iter();
+        self.insert_records(table_name, fields, on_conflict, returned, tx, || {
+            rows.next()
+                .map(|row| row.iter().map(Expression::evaluate_constant).collect())
+                .transpose()
+        })
+    }
+
+    fn insert_from_plan(
         &self,
-        update_command: &UpdateCommand,
+        table_name: &str,
+        fields: &[(String, String)],
+        mut plan: Plan,
+        on_conflict: Option<&OnConflict>,
+        returned: Option<&mut ReturnedRecords>,
         tx: Arc<Mutex<Transaction>>,
-    ) -> Result<usize, TransactionError> {
-        match update_command {
-            UpdateCommand::Insert(table_name, fields, values) => {
-                self.insert(table_name, fields, values, tx)
+    ) -> Result<InsertCount, TransactionError> {
+        let (fields, source_fields): (Vec<String>, Vec<String>) = fields.iter().cloned().unzip();
+        let mut scan = plan.open(tx.clone())?;
+        self.insert_records(table_name, &fields, on_conflict, returned, tx, || {
+            if !scan.next()? {
+                return Ok(None);
             }
-            UpdateCommand::Delete(table_name, predicate) => self.delete(table_name, predicate, tx),
-            UpdateCommand::Modify(table_name, field_name, expression, predicate) => {
-                self.modify(table_name, field_name, expression, predicate, tx)
+            source_fields
+                .iter()
+                .map(|f| scan.get_value(f))
+                .collect::<Result<_, _>>()
+                .map(Some)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::SimpleDB,
+        errors::ExecutionError,
+        planner::{
+            basic_update_planner::BasicUpdatePlanner,
+            heuristic_query_planner::HeuristicQueryPlanner, Planner,
+        },
+    };
+
+    #[test]
+    fn test_update_multiple_fields() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = Planner::new(
+            Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+            Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone())),
+        );
+        planner.execute_update(
+            "create table table1 (A I32, B I32, C VARCHAR(5))",
+            tx.clone(),
+        )?;
+        planner.execute_update("create index IA on table1 (A)", tx.clone())?;
+        planner.execute_update("create index IB on table1 (B)", tx.clone())?;
+        for i in 0..10 {
+            planner.execute_update(
+                &format!(
+                    "insert into table1 (A, B, C) values ({}, {}, 'x')",
+                    i,
+                    i + 100
+                ),
+                tx.clone(),
+            )?;
+        }
+
+        // Both right-hand sides see the old values, so A and B are swapped
+        let updated = planner.execute_update(
+            "update table1 set A = B, B = A, C = 'y' where A in (3, 4)",
+            tx.clone(),
+        )?;
+        assert_eq!(updated, 2);
+        let updated =
+            planner.execute_update("MODIFY table1 SET C = 'z' WHERE A = 5", tx.clone())?;
+        assert_eq!(updated, 1);
+
+        // The indexes on A and B find the records by their new values only
+        let collect = |predicate: &str| -> Result<Vec<(i32, i32, String)>, ExecutionError> {
+            let query = format!("select A, B, C from table1 where {}", predicate);
+            let mut plan = planner.create_query_plan(&query, tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut result = vec![];
+            while scan.next()? {
+                result.push((
+                    scan.get_i32("A")?.unwrap(),
+                    scan.get_i32("B")?.unwrap(),
+                    scan.get_string("C")?.unwrap(),
+                ));
+            }
+            result.sort();
+            Ok(result)
+        };
+        assert_eq!(collect("A = 103")?, vec![(103, 3, "y".to_string())]);
+        assert_eq!(collect("B = 4")?, vec![(104, 4, "y".to_string())]);
+        assert_eq!(collect("A = 3")?, vec![]);
+        assert_eq!(collect("B = 103")?, vec![]);
+        assert_eq!(collect("A = 5")?, vec![(5, 105, "z".to_string())]);
+        assert_eq!(collect("C = 'x'")?.len(), 7);
+
+        let update_planners: [Box<dyn UpdatePlanner>; 2] = [
+            Box::new(BasicUpdatePlanner::new(db.metadata_manager.clone())),
+            Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone())),
+        ];
+        for update_planner in update_planners {
+            let planner = Planner::new(
+                Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+                update_planner,
+            );
+            for statement in [
+                "update table1 set D = 1 where A = 1",
+                "update table1 set A = 1, A = 2",
+                "update table1 set A = D + 1",
+            ] {
+                match planner.execute_update(statement, tx.clone()) {
+                    Err(ExecutionError::TransactionError(TransactionError::InvalidUpdate(_))) => {}
+                    _ => panic!("Expected InvalidUpdate error for {}", statement),
+                }
             }
-            UpdateCommand::Create(create_command) => self.create(create_command, tx),
         }
+        assert_eq!(collect("A = 1")?, vec![(1, 101, "x".to_string())]);
+
+        tx.lock().unwrap().commit()?;
+        Ok(())
     }
 }
@@ -0,0 +1,350 @@
+use crate::{
+    errors::TransactionError,
+    materialization::top_n_scan::TopNScan,
+    parser::{
+        expression::Expression,
+        statement::{ConflictAction, OnConflict, EXCLUDED_PREFIX},
+    },
+    record::{field::Value, schema::Schema},
+    scan::{Scan, ScanControl},
+};
+
+/// Numbers of records inserted and, with `ON CONFLICT ... DO UPDATE`,
+/// updated by an INSERT.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InsertCount {
+    pub inserted: usize,
+    pub updated: usize,
+}
+
+impl InsertCount {
+    pub fn total(&self) -> usize {
+        self.inserted + self.updated
+    }
+}
+
+/// Moves `scan` to the first record whose `field_name` equals `value`,
+/// returning whether there is one. NULL equals no value.
+pub(crate) fn find_record(
+    scan: &mut Scan,
+    field_name: &str,
+    value: &Value,
+) -> Result<bool, TransactionError> {
+    if *value == Value::Null {
+        return Ok(false);
+    }
+    scan.before_first()?;
+    while scan.next()? {
+        if scan.get_value(field_name)? == *value {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Evaluates the assignments of `ON CONFLICT ... DO UPDATE` over the record
+/// of `scan`. The values of the record that could not be inserted are read
+/// as `excluded.field`.
+pub(crate) fn evaluate_conflict_update(
+    scan: &mut Scan,
+    table_fields: &[String],
+    fields: &[String],
+    values: &[Value],
+    assignments: &[(String, Expression)],
+) -> Result<Vec<Value>, TransactionError> {
+    let mut record_fields = table_fields.to_vec();
+    let mut record = Vec::with_capacity(table_fields.len() + values.len());
+    for field_name in table_fields {
+        record.push(scan.get_value(field_name)?);
+    }
+    record_fields.extend(fields.iter().map(|f| format!("{}{}", EXCLUDED_PREFIX, f)));
+    record.extend_from_slice(values);
+
+    let mut record_scan = Scan::from(TopNScan::new(record_fields, vec![record]));
+    record_scan.next()?;
+    let mut new_values = Vec::with_capacity(assignments.len());
+    for (_, expression) in assignments {
+        new_values.push(expression.evaluate(&mut record_scan)?);
+    }
+    Ok(new_values)
+}
+
+/// Checks that the fields of an INSERT and of its `ON CONFLICT` clause are
+/// fields of the table.
+pub(super) fn check_insert_fields(
+    table_schema: &Schema,
+    fields: &[String],
+    on_conflict: Option<&OnConflict>,
+) -> Result<(), String> {
+    if let Some(field_name) = fields.iter().find(|f| !table_schema.has_field(f)) {
+        return Err(format!("unknown field {}", field_name));
+    }
+    let Some(on_conflict) = on_conflict else {
+        return Ok(());
+    };
+    if !fields.contains(&on_conflict.field_name) {
+        return Err(format!(
+            "conflict field {} is not inserted",
+            on_conflict.field_name
+        ));
+    }
+    if let ConflictAction::DoUpdate(assignments) = &on_conflict.action {
+        let mut schema = table_schema.clone();
+        for field_name in fields {
+            let spec = table_schema.get_field_spec(field_name);
+            schema.add_field(&format!("{}{}", EXCLUDED_PREFIX, field_name), &spec);
+        }
+        for (field_name, expression) in assignments {
+            if !table_schema.has_field(field_name) {
+                return Err(format!("unknown field {}", field_name));
+            }
+            if !expression.is_applied_to(&schema) {
+                return Err(format!("unknown field in {:?}", expression));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        db::SimpleDB,
+        errors::{ExecutionError, QueryError},
+        plan::PlanControl,
+        planner::{
+            basic_update_planner::BasicUpdatePlanner,
+            heuristic_query_planner::HeuristicQueryPlanner,
+            index_update_planner::IndexUpdatePlanner, Planner, UpdatePlanner,
+        },
+        scan::ScanControl,
+    };
+
+    #[test]
+    fn test_insert_rows_and_query_results() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = Planner::new(
+            Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+            Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone())),
+        );
+        planner.execute_update("create table src (A I32, B VARCHAR(5))", tx.clone())?;
+        planner.execute_update("create table dst (X I32, Y VARCHAR(5))", tx.clone())?;
+        planner.execute_update("create index IX on dst (X)", tx.clone())?;
+
+        let inserted = planner.execute_update(
+            "insert into src (A, B) values (1, 'a'), (2 * 3, UPPER('b')), (NULL, 'c')",
+            tx.clone(),
+        )?;
+        assert_eq!(inserted, 3);
+
+        let inserted = planner.execute_update(
+            "insert into dst (Y, X) select B, A + 100 as S from src where A between 1 and 10",
+            tx.clone(),
+        )?;
+        assert_eq!(inserted, 2);
+        // The index on X finds the inserted records
+        let collect = |query: &str| -> Result<Vec<(Option<i32>, String)>, ExecutionError> {
+            let mut plan = planner.create_query_plan(query, tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut result = vec![];
+            while scan.next()? {
+                result.push((scan.get_i32("X")?, scan.get_string("Y")?.unwrap()));
+            }
+            result.sort();
+            Ok(result)
+        };
+        assert_eq!(
+            collect("select X, Y from dst where X = 106")?,
+            vec![(Some(106), "B".to_string())]
+        );
+
+        // Without a field list, output fields go into the fields of the same name
+        let inserted = planner.execute_update(
+            "insert into dst select A as X, B as Y from src where A is null",
+            tx.clone(),
+        )?;
+        assert_eq!(inserted, 1);
+
+        // Records inserted into the queried table are not read again
+        let inserted =
+            planner.execute_update("insert into dst select X, Y from dst", tx.clone())?;
+        assert_eq!(inserted, 3);
+        assert_eq!(
+            collect("select X, Y from dst")?,
+            vec![
+                (None, "c".to_string()),
+                (None, "c".to_string()),
+                (Some(101), "a".to_string()),
+                (Some(101), "a".to_string()),
+                (Some(106), "B".to_string()),
+                (Some(106), "B".to_string()),
+            ]
+        );
+
+        // Expressions need no alias when the fields are matched by position
+        let inserted = planner.execute_update(
+            "insert into dst (X, Y) select A + 10, upper(B) from src where A = 1",
+            tx.clone(),
+        )?;
+        assert_eq!(inserted, 1);
+        assert_eq!(
+            collect("select X, Y from dst where X = 11")?,
+            vec![(Some(11), "A".to_string())]
+        );
+
+        for statement in [
+            "insert into dst (X) select A, B from src",
+            "insert into dst (Z) select A from src",
+            "insert into dst (X, Y) select B, A from src",
+        ] {
+            match planner.execute_update(statement, tx.clone()) {
+                Err(ExecutionError::QueryError(QueryError::InvalidInsert(_))) => {}
+                _ => panic!("Expected InvalidInsert error for {}", statement),
+            }
+        }
+
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_on_conflict() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let update_planners: [Box<dyn UpdatePlanner>; 2] = [
+            Box::new(BasicUpdatePlanner::new(db.metadata_manager.clone())),
+            Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone())),
+        ];
+        for (i, update_planner) in update_planners.into_iter().enumerate() {
+            let planner = Planner::new(
+                Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+                update_planner,
+            );
+            // Conflicts on K are found through its index if there is one, and on N
+            // by a scan
+            let table = format!("kv{}", i);
+            planner.execute_update(
+                &format!("create table {} (K I32, N I32, V VARCHAR(5))", table),
+                tx.clone(),
+            )?;
+            // Only the index planner creates and maintains indexes
+            if i == 1 {
+                planner.execute_update(&format!("create index IK on {} (K)", table), tx.clone())?;
+                planner.execute_update(&format!("create index IV on {} (V)", table), tx.clone())?;
+            }
+            planner.execute_update(
+                &format!(
+                    "insert into {} (K, N, V) values (1, 10, 'a'), (2, 20, 'b')",
+                    table
+                ),
+                tx.clone(),
+            )?;
+
+            let count = planner.execute_insert(
+                &format!(
+                    "insert into {} (K, N, V) values (2, 0, 'x'), (3, 30, 'c') on conflict (K) do nothing",
+                    table
+                ),
+                tx.clone(),
+            )?;
+            assert_eq!(
+                count,
+                InsertCount {
+                    inserted: 1,
+                    updated: 0
+                }
+            );
+
+            let count = planner.execute_insert(
+                &format!(
+                    "insert into {} (K, N, V) values (1, 5, 'y'), (4, 40, 'd'), (NULL, 0, 'z') \
+                     on conflict (K) do update set N = N + excluded.N, V = excluded.V",
+                    table
+                ),
+                tx.clone(),
+            )?;
+            assert_eq!(
+                count,
+                InsertCount {
+                    inserted: 2,
+                    updated: 1
+                }
+            );
+
+            let count = planner.execute_insert(
+                &format!(
+                    "insert into {} (N, V) select N, V from {} where K = 3 \
+                     on conflict (N) do update set V = 'w'",
+                    table, table
+                ),
+                tx.clone(),
+            )?;
+            assert_eq!(
+                count,
+                InsertCount {
+                    inserted: 0,
+                    updated: 1
+                }
+            );
+
+            let mut plan =
+                planner.create_query_plan(&format!("select K, N, V from {}", table), tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut records = vec![];
+            while scan.next()? {
+                records.push((
+                    scan.get_i32("K")?,
+                    scan.get_i32("N")?.unwrap(),
+                    scan.get_string("V")?.unwrap(),
+                ));
+            }
+            records.sort();
+            let expected = [
+                (None, 0, "z"),
+                (Some(1), 15, "y"),
+                (Some(2), 20, "b"),
+                (Some(3), 30, "w"),
+                (Some(4), 40, "d"),
+            ]
+            .map(|(k, n, v)| (k, n, v.to_string()));
+            assert_eq!(records, expected);
+            drop(scan);
+
+            // The index on V only finds records by their new values
+            let mut plan = planner.create_query_plan(
+                &format!("select K from {} where V = 'a'", table),
+                tx.clone(),
+            )?;
+            let mut scan = plan.open(tx.clone())?;
+            assert!(!scan.next()?);
+            drop(scan);
+
+            for statement in [
+                "insert into {} (N) values (1) on conflict (K) do nothing",
+                "insert into {} (K) values (1) on conflict (K) do update set X = 1",
+                "insert into {} (K) values (1) on conflict (K) do update set N = excluded.N",
+            ] {
+                let statement = statement.replace("{}", &table);
+                match planner.execute_update(&statement, tx.clone()) {
+                    Err(ExecutionError::QueryError(QueryError::InvalidInsert(_))) => {}
+                    _ => panic!("Expected InvalidInsert error for {}", statement),
+                }
+            }
+        }
+
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
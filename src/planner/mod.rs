@@ -1,42 +1,188 @@
-use std::sync::{Arc, Mutex};
-
-use lalrpop_util::{lexer::Token, ParseError};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
     errors::{ExecutionError, QueryError, TransactionError},
+    materialization::materialize_plan::MaterializePlan,
     parser::{
-        grammar::{QueryParser, UpdateCommandParser},
-        statement::{QueryData, UpdateCommand},
+        expression::Expression,
+        parameter::ParsedStatement,
+        predicate::Predicate,
+        statement::{CreateCommand, InsertSource, OnConflict, QueryData, UpdateCommand},
     },
-    plan::Plan,
+    plan::{extend_plan::ExtendPlan, Plan, PlanControl},
+    record::schema::Schema,
     tx::transaction::Transaction,
 };
 
 pub mod basic_query_planner;
 pub mod basic_update_planner;
+mod common_tables;
+mod group_by;
 pub mod heuristic_query_planner;
 pub mod index_update_planner;
+mod insert;
+mod order_by;
+mod returning;
+mod set_operations;
+pub mod statement_cache;
 mod table_planner;
+mod window;
+
+pub use insert::InsertCount;
+pub use returning::ReturnedRecords;
+
+use group_by::group_field_names;
+use insert::check_insert_fields;
+use set_operations::{check_compatible_fields, output_fields};
+use statement_cache::{StatementCache, STATEMENT_CACHE_SIZE};
+
+/// Plans of the common tables visible to a query, by name.
+pub type CommonTablePlans = HashMap<String, Plan>;
 
 pub trait QueryPlanner: Send + Sync {
     fn create_plan(
         &self,
         query: &QueryData,
         tx: Arc<Mutex<Transaction>>,
+    ) -> Result<Plan, ExecutionError> {
+        self.create_plan_with_common_tables(query, &CommonTablePlans::new(), tx)
+    }
+
+    // Create a plan in which the names of `common_tables` refer to their plans
+    // rather than to stored tables
+    fn create_plan_with_common_tables(
+        &self,
+        query: &QueryData,
+        common_tables: &CommonTablePlans,
+        tx: Arc<Mutex<Transaction>>,
     ) -> Result<Plan, ExecutionError>;
 }
 
+// The `Planner` plans the query of `INSERT ... SELECT` and the RETURNING clause,
+// and passes the rest of each update command to the method for its kind
 pub trait UpdatePlanner: Send + Sync {
-    fn execute_update(
+    // Delete the records satisfying `predicate`, adding them to `returned`
+    fn delete(
         &self,
-        update_command: &UpdateCommand,
+        table_name: &str,
+        predicate: &Option<Predicate>,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<usize, TransactionError>;
+
+    // Apply `assignments` to the records satisfying `predicate`, adding them
+    // to `returned` once changed
+    fn modify(
+        &self,
+        table_name: &str,
+        assignments: &[(String, Expression)],
+        predicate: &Option<Predicate>,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<usize, TransactionError>;
+
+    fn create(
+        &self,
+        create_command: &CreateCommand,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<usize, TransactionError>;
+
+    // Insert one record per row of expressions, which refer to no fields
+    fn insert(
+        &self,
+        table_name: &str,
+        fields: &[String],
+        rows: &[Vec<Expression>],
+        on_conflict: Option<&OnConflict>,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<InsertCount, TransactionError>;
+
+    // Insert the records of `plan` into `table_name`. Each pair of `fields`
+    // holds a field of the table and the field of `plan` whose value it takes
+    fn insert_from_plan(
+        &self,
+        table_name: &str,
+        fields: &[(String, String)],
+        plan: Plan,
+        on_conflict: Option<&OnConflict>,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<InsertCount, TransactionError>;
+}
+
+/// Checks the types of the expressions in the WHERE clause of the query
+/// against the schema of the records it filters.
+pub(crate) fn check_predicate(query: &QueryData, schema: &Schema) -> Result<(), QueryError> {
+    match &query.predicate {
+        Some(predicate) => predicate
+            .check_types(schema)
+            .map_err(QueryError::InvalidExpression),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the assignments of an UPDATE set distinct fields of the table
+/// to expressions over its fields.
+pub(crate) fn check_assignments(
+    table_schema: &Schema,
+    assignments: &[(String, Expression)],
+) -> Result<(), TransactionError> {
+    for (i, (field_name, expression)) in assignments.iter().enumerate() {
+        if !table_schema.has_field(field_name) {
+            return Err(TransactionError::InvalidUpdate(format!(
+                "unknown field {}",
+                field_name
+            )));
+        }
+        if assignments[..i].iter().any(|(f, _)| f == field_name) {
+            return Err(TransactionError::InvalidUpdate(format!(
+                "field {} is assigned twice",
+                field_name
+            )));
+        }
+        if !expression.is_applied_to(table_schema) {
+            return Err(TransactionError::InvalidUpdate(format!(
+                "unknown field in {:?}",
+                expression
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Adds the fields computed by expressions in the select list of the query on
+/// top of `plan`, checking the types of the expressions first.
+///
+/// Expressions that also appear in GROUP BY were computed before grouping and
+/// are read from the grouped records.
+pub(crate) fn add_extend_fields(mut plan: Plan, query: &QueryData) -> Result<Plan, QueryError> {
+    let group_by = query.group_by.as_deref().unwrap_or_default();
+    let group_fields = group_field_names(query);
+    for (expr, alias) in &query.extend_fields {
+        let expr = match group_by.iter().position(|e| e == expr) {
+            Some(i) if expr.try_get_field().is_none() => {
+                if group_fields[i] == *alias {
+                    continue;
+                }
+                Expression::Field(group_fields[i].clone())
+            }
+            _ => expr.clone(),
+        };
+        expr.infer_spec(plan.schema())
+            .map_err(QueryError::InvalidExpression)?;
+        plan = Plan::from(ExtendPlan::new(plan, expr, alias));
+    }
+    Ok(plan)
 }
 
 pub struct Planner {
     query_planner: Box<dyn QueryPlanner>,
     update_planner: Box<dyn UpdatePlanner>,
+    statement_cache: Mutex<StatementCache>,
 }
 
 impl Planner {
@@ -47,19 +193,31 @@ impl Planner {
         Self {
             query_planner,
             update_planner,
+            statement_cache: Mutex::new(StatementCache::new(STATEMENT_CACHE_SIZE)),
         }
     }
 
+    /// Parses a statement, which may have parameters, or returns the statement
+    /// parsed for the same text before.
+    pub fn prepare(&self, sql: &str) -> Result<Arc<ParsedStatement>, ExecutionError> {
+        Ok(self.statement_cache.lock().unwrap().get_or_parse(sql)?)
+    }
+
     pub fn create_query_plan(
         &self,
         query: &str,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<Plan, ExecutionError> {
-        // Discard the parse error because it requires static lifetime for the query
-        let query_data = QueryParser::new()
-            .parse(query)
-            .map_err(|e: ParseError<usize, Token<'_>, &str>| QueryError::from(e))?;
-        Ok(self.query_planner.create_plan(&query_data, tx)?)
+        let statement = self.prepare(query)?;
+        self.create_plan(statement.query()?, tx)
+    }
+
+    pub fn create_plan(
+        &self,
+        query_data: &QueryData,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<Plan, ExecutionError> {
+        self.query_planner.create_plan(query_data, tx)
     }
 
     pub fn execute_update(
@@ -67,18 +225,181 @@ impl Planner {
         update_command: &str,
         tx: Arc<Mutex<Transaction>>,
     ) -> Result<usize, ExecutionError> {
-        // Discard the parse error because it requires static lifetime for the update_command
-        let update_command = UpdateCommandParser::new()
-            .parse(&update_command)
-            .map_err(QueryError::from)?;
-        Ok(self.update_planner.execute_update(&update_command, tx)?)
+        let statement = self.prepare(update_command)?;
+        self.execute_update_command(statement.update_command()?, tx)
+    }
+
+    pub fn execute_update_command(
+        &self,
+        update_command: &UpdateCommand,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<usize, ExecutionError> {
+        self.execute_command(update_command, None, tx)
+    }
+
+    /// Executes an INSERT, UPDATE or DELETE with a RETURNING clause,
+    /// returning the records it affected.
+    pub fn execute_update_returning(
+        &self,
+        update_command: &str,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<ReturnedRecords, ExecutionError> {
+        let statement = self.prepare(update_command)?;
+        let UpdateCommand::Returning(command, fields) = statement.update_command()? else {
+            return Err(QueryError::InvalidReturning("no RETURNING clause".to_string()).into());
+        };
+        let mut returned = self.returned_records(command, fields, tx.clone())?;
+        self.execute_command(command, Some(&mut returned), tx)?;
+        Ok(returned)
+    }
+
+    /// Executes an INSERT, returning the numbers of records that it inserted
+    /// and that its `ON CONFLICT ... DO UPDATE` clause updated.
+    pub fn execute_insert(
+        &self,
+        insert_command: &str,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<InsertCount, ExecutionError> {
+        let statement = self.prepare(insert_command)?;
+        match statement.update_command()? {
+            UpdateCommand::Returning(command, fields) => {
+                let mut returned = self.returned_records(command, fields, tx.clone())?;
+                self.insert(command, Some(&mut returned), tx)
+            }
+            update_command => self.insert(update_command, None, tx),
+        }
+    }
+
+    fn execute_command(
+        &self,
+        update_command: &UpdateCommand,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<usize, ExecutionError> {
+        let update_planner = &self.update_planner;
+        match update_command {
+            UpdateCommand::Insert(..) => Ok(self.insert(update_command, returned, tx)?.total()),
+            UpdateCommand::Delete(table_name, predicate) => {
+                Ok(update_planner.delete(table_name, predicate, returned, tx)?)
+            }
+            UpdateCommand::Modify(table_name, assignments, predicate) => {
+                Ok(update_planner.modify(table_name, assignments, predicate, returned, tx)?)
+            }
+            UpdateCommand::Create(create_command) => Ok(update_planner.create(create_command, tx)?),
+            // The returned records are discarded
+            UpdateCommand::Returning(command, fields) => {
+                let mut returned = self.returned_records(command, fields, tx.clone())?;
+                self.execute_command(command, Some(&mut returned), tx)
+            }
+        }
+    }
+
+    /// Checks the RETURNING clause of `update_command` against its table.
+    fn returned_records(
+        &self,
+        update_command: &UpdateCommand,
+        fields: &[(Expression, String)],
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<ReturnedRecords, ExecutionError> {
+        let table_name = match update_command {
+            UpdateCommand::Insert(table_name, ..)
+            | UpdateCommand::Delete(table_name, _)
+            | UpdateCommand::Modify(table_name, ..) => table_name,
+            UpdateCommand::Create(_) | UpdateCommand::Returning(..) => {
+                return Err(QueryError::InvalidReturning(
+                    "only INSERT, UPDATE and DELETE return records".to_string(),
+                )
+                .into())
+            }
+        };
+        let table_plan = self.create_table_plan(table_name, tx)?;
+        Ok(ReturnedRecords::new(fields, table_plan.schema())
+            .map_err(QueryError::InvalidReturning)?)
+    }
+
+    /// Plans a scan of every field of a stored table.
+    fn create_table_plan(
+        &self,
+        table_name: &str,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<Plan, ExecutionError> {
+        let table_query = QueryData::new_full(
+            None,
+            vec![table_name.to_string()],
+            None,
+            None,
+            None,
+            Vec::new(),
+            Vec::new(),
+        );
+        self.query_planner.create_plan(&table_query, tx)
+    }
+
+    fn insert(
+        &self,
+        update_command: &UpdateCommand,
+        returned: Option<&mut ReturnedRecords>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Result<InsertCount, ExecutionError> {
+        let UpdateCommand::Insert(table_name, fields, source, on_conflict) = update_command else {
+            return Err(QueryError::InvalidInsert("not an INSERT statement".to_string()).into());
+        };
+        let table_plan = self.create_table_plan(table_name, tx.clone())?;
+
+        let query = match source {
+            InsertSource::Values(rows) => {
+                check_insert_fields(table_plan.schema(), fields, on_conflict.as_ref())
+                    .map_err(QueryError::InvalidInsert)?;
+                return Ok(self.update_planner.insert(
+                    table_name,
+                    fields,
+                    rows,
+                    on_conflict.as_ref(),
+                    returned,
+                    tx,
+                )?);
+            }
+            InsertSource::Query(query) => query,
+        };
+
+        // Plan the query of `INSERT ... SELECT`
+        let mut plan = self.query_planner.create_plan(query, tx.clone())?;
+        let source_fields = output_fields(query, &plan);
+        let fields = if fields.is_empty() {
+            source_fields.clone()
+        } else {
+            fields.to_vec()
+        };
+        check_insert_fields(table_plan.schema(), &fields, on_conflict.as_ref())
+            .map_err(QueryError::InvalidInsert)?;
+        check_compatible_fields(&table_plan, &fields, &plan, &source_fields)
+            .map_err(QueryError::InvalidInsert)?;
+
+        // Records inserted into a table the query reads would be read again
+        if query.references_table(table_name) {
+            plan = Plan::from(MaterializePlan::new(plan, tx.clone()));
+        }
+        let fields: Vec<(String, String)> = fields.into_iter().zip(source_fields).collect();
+        Ok(self.update_planner.insert_from_plan(
+            table_name,
+            &fields,
+            plan,
+            on_conflict.as_ref(),
+            returned,
+            tx,
+        )?)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{db::SimpleDB, plan::PlanControl, scan::ScanControl};
+    use crate::{
+        db::SimpleDB,
+        plan::PlanControl,
+        record::field::{Spec, Value},
+        scan::ScanControl,
+    };
 
     #[test]
     fn test_planner() -> Result<(), ExecutionError> {
@@ -127,6 +448,183 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_conditional_expressions() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = db.planner.lock().unwrap();
+        planner.execute_update("create table table1 (A I32, B VARCHAR(10))", tx.clone())?;
+        for (a, b) in [("1", "'x'"), ("2", "'12'"), ("null", "'y'"), ("3", "null")] {
+            planner.execute_update(
+                &format!("insert into table1 (A, B) values ({}, {})", a, b),
+                tx.clone(),
+            )?;
+        }
+
+        let collect = |query: &str| -> Result<Vec<Value>, ExecutionError> {
+            let mut plan = planner.create_query_plan(query, tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut result = vec![];
+            while scan.next()? {
+                result.push(scan.get_value("C")?);
+            }
+            Ok(result)
+        };
+        let string = |s: &str| Value::String(s.to_string());
+
+        assert_eq!(
+            collect(
+                "select case when A = 1 then 'one' when A is null then 'none' else B end as C \
+                 from table1"
+            )?,
+            vec![string("one"), string("12"), string("none"), Value::Null]
+        );
+        assert_eq!(
+            collect("select case A when 2 then A * 10 end as C from table1")?,
+            vec![Value::Null, Value::I32(20), Value::Null, Value::Null]
+        );
+        assert_eq!(
+            collect("select coalesce(B, 'z') as C from table1")?,
+            vec![string("x"), string("12"), string("y"), string("z")]
+        );
+        assert_eq!(
+            collect("select nullif(A, 2) as C from table1")?,
+            vec![Value::I32(1), Value::Null, Value::Null, Value::I32(3)]
+        );
+        assert_eq!(
+            collect("select cast(A as varchar(3)) as C from table1 where coalesce(A, 0) = 3")?,
+            vec![string("3")]
+        );
+        assert_eq!(
+            collect("select B as C from table1 where cast(A as varchar(10)) = '2'")?,
+            vec![string("12")]
+        );
+        assert_eq!(
+            collect("select B as C from table1 order by coalesce(A, 0) desc")?,
+            vec![Value::Null, string("12"), string("x"), string("y")]
+        );
+        for query in [
+            "select case when A = 1 then A else B end as C from table1",
+            "select case A when 1 then 'one' else 0 end as C from table1",
+            "select coalesce(A, B) as C from table1",
+            "select B as C from table1 where coalesce(B, 0) = 'x'",
+        ] {
+            assert!(matches!(
+                planner.create_query_plan(query, tx.clone()),
+                Err(ExecutionError::QueryError(QueryError::InvalidExpression(_)))
+            ));
+        }
+        assert!(matches!(
+            collect("select cast(B as I32) as C from table1"),
+            Err(ExecutionError::TransactionError(
+                TransactionError::InvalidCast(_)
+            ))
+        ));
+
+        planner.execute_update(
+            "modify table1 set B = case when B is null then cast(A as varchar(10)) else B end",
+            tx.clone(),
+        )?;
+        assert_eq!(
+            collect("select B as C from table1")?,
+            vec![string("x"), string("12"), string("y"), string("3")]
+        );
+        drop(planner);
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_scalar_functions() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = db.planner.lock().unwrap();
+        planner.execute_update("create table table1 (A I32, B VARCHAR(10))", tx.clone())?;
+        for (a, b) in [("-15", "' Ab '"), ("7", "'cde'"), ("null", "'f'")] {
+            planner.execute_update(
+                &format!("insert into table1 (A, B) values ({}, {})", a, b),
+                tx.clone(),
+            )?;
+        }
+
+        let collect = |query: &str| -> Result<Vec<Value>, ExecutionError> {
+            let mut plan = planner.create_query_plan(query, tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut result = vec![];
+            while scan.next()? {
+                result.push(scan.get_value("C")?);
+            }
+            Ok(result)
+        };
+        let strings = |values: &[&str]| {
+            values
+                .iter()
+                .map(|v| Value::String(v.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            collect("select upper(trim(B)) as C from table1")?,
+            strings(&["AB", "CDE", "F"])
+        );
+        assert_eq!(
+            collect("select lower(B) || A as C from table1")?,
+            vec![
+                Value::String(" ab -15".to_string()),
+                Value::String("cde7".to_string()),
+                Value::Null
+            ]
+        );
+        assert_eq!(
+            collect("select concat(substr(B, 2), '!') as C from table1")?,
+            strings(&["Ab !", "de!", "!"])
+        );
+        assert_eq!(
+            collect("select abs(A) + length(B) as C from table1")?,
+            vec![Value::I32(19), Value::I32(10), Value::Null]
+        );
+        assert_eq!(
+            collect("select round(A, -1) as C from table1 where mod(A, 2) = 1")?,
+            vec![Value::I32(10)]
+        );
+        assert_eq!(
+            collect("select B as C from table1 order by length(B) desc")?,
+            strings(&[" Ab ", "cde", "f"])
+        );
+
+        let plan = planner.create_query_plan(
+            "select upper(B) as U, length(B) as L, B || A as S from table1",
+            tx.clone(),
+        )?;
+        assert_eq!(plan.schema().get_field_spec("U"), Spec::VarChar(10));
+        assert_eq!(plan.schema().get_field_spec("L"), Spec::I32);
+        assert_eq!(plan.schema().get_field_spec("S"), Spec::VarChar(21));
+
+        for query in [
+            "select upper(A) as C from table1",
+            "select mod(A) as C from table1",
+            "select B as C from table1 where abs(B) = 1",
+            "select B as C from table1 order by length(A)",
+            "select B + 1 as C from table1",
+        ] {
+            assert!(matches!(
+                planner.create_query_plan(query, tx.clone()),
+                Err(ExecutionError::QueryError(QueryError::InvalidExpression(_)))
+            ));
+        }
+        drop(planner);
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+
     #[test]
     fn test_invalid_table_error() -> Result<(), ExecutionError> {
         let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
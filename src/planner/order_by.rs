@@ -0,0 +1,170 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::QueryError,
+    materialization::{
+        record_comparator::{RecordComparator, SortKey},
+        sort_plan::SortPlan,
+        top_n_plan::{TopNPlan, MAX_TOP_N_RECORDS},
+    },
+    parser::{expression::Expression, statement::QueryData},
+    plan::{
+        extend_plan::ExtendPlan, limit_plan::LimitPlan, project_plan::ProjectPlan, Plan,
+        PlanControl,
+    },
+    tx::transaction::Transaction,
+};
+
+/// Applies the ORDER BY and LIMIT clauses of the query on top of `plan`.
+///
+/// ORDER BY keys that are not fields of `plan` are computed by an
+/// [`ExtendPlan`] before sorting and projected away afterwards. Integer
+/// constants refer to positions in the select list. When a small enough LIMIT
+/// accompanies the ORDER BY, a [`TopNPlan`] replaces the full sort.
+pub(crate) fn add_order_and_limit(
+    mut plan: Plan,
+    query: &QueryData,
+    tx: Arc<Mutex<Transaction>>,
+) -> Result<Plan, QueryError> {
+    let fields = plan.schema().get_fields();
+    let mut keys = vec![];
+    let mut has_computed_keys = false;
+    for (i, item) in query.order_by.iter().flatten().enumerate() {
+        let field_name = match &item.expression {
+            Expression::I32Constant(position) => {
+                let output_fields = query.fields.as_ref().unwrap_or(&fields);
+                let index = usize::try_from(*position)
+                    .ok()
+                    .filter(|index| (1..=output_fields.len()).contains(index))
+                    .ok_or_else(|| {
+                        QueryError::InvalidOrderBy(format!("position {} is out of range", position))
+                    })?;
+                output_fields[index - 1].clone()
+            }
+            Expression::Field(field_name) => {
+                if !plan.schema().has_field(field_name) {
+                    return Err(QueryError::InvalidOrderBy(format!(
+                        "unknown field {}",
+                        field_name
+                    )));
+                }
+                field_name.clone()
+            }
+            expression => {
+                if !expression.is_applied_to(plan.schema()) {
+                    return Err(QueryError::InvalidOrderBy(
+                        "expression refers to an unknown field".to_string(),
+                    ));
+                }
+                expression
+                    .infer_spec(plan.schema())
+                    .map_err(QueryError::InvalidExpression)?;
+                let field_name = format!("__order_by_{}", i);
+                plan = Plan::from(ExtendPlan::new(plan, expression.clone(), &field_name));
+                has_computed_keys = true;
+                field_name
+            }
+        };
+        keys.push(SortKey::new(&field_name, item.direction, item.nulls));
+    }
+
+    let offset = query.offset.unwrap_or(0);
+    let mut limit = query.limit;
+    if !keys.is_empty() {
+        let comparator = Arc::new(RecordComparator::new_with_keys(keys));
+        plan = match limit {
+            Some(n) if n.saturating_add(offset) <= MAX_TOP_N_RECORDS => {
+                // The top-N plan applies the limit itself
+                limit = None;
+                Plan::from(TopNPlan::new(plan, comparator, n, offset))
+            }
+            _ => Plan::from(SortPlan::new(plan, tx, comparator)),
+        };
+    }
+    if let Some(limit) = limit {
+        plan = Plan::from(LimitPlan::new(plan, limit, offset));
+    }
+    if has_computed_keys {
+        plan = Plan::from(ProjectPlan::new(plan, fields));
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::SimpleDB, errors::ExecutionError, scan::ScanControl};
+
+    #[test]
+    fn test_order_by() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = db.planner.lock().unwrap();
+        planner.execute_update("create table table1 (A I32, B VARCHAR(20))", tx.clone())?;
+        for (a, b) in [(1, "x"), (3, "y"), (2, "z")] {
+            planner.execute_update(
+                &format!("insert into table1 (A, B) values ({}, '{}')", a, b),
+                tx.clone(),
+            )?;
+        }
+        planner.execute_update("insert into table1 (A, B) values (null, 'n')", tx.clone())?;
+
+        let collect = |query: &str| -> Result<Vec<Option<String>>, ExecutionError> {
+            let mut plan = planner.create_query_plan(query, tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut result = vec![];
+            while scan.next()? {
+                result.push(scan.get_string("B")?);
+            }
+            Ok(result)
+        };
+        let strings = |values: &[&str]| {
+            values
+                .iter()
+                .map(|v| Some(v.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            collect("select A, B from table1 order by A")?,
+            strings(&["x", "z", "y", "n"])
+        );
+        assert_eq!(
+            collect("select A, B from table1 order by A desc")?,
+            strings(&["n", "y", "z", "x"])
+        );
+        assert_eq!(
+            collect("select A, B from table1 order by A desc nulls last")?,
+            strings(&["y", "z", "x", "n"])
+        );
+        assert_eq!(
+            collect("select A, B from table1 order by 2 desc")?,
+            strings(&["z", "y", "x", "n"])
+        );
+        assert_eq!(
+            collect("select B from table1 order by 0 - A")?,
+            strings(&["y", "z", "x", "n"])
+        );
+        assert_eq!(
+            collect("select A, B from table1 order by A nulls first limit 2")?,
+            strings(&["n", "x"])
+        );
+
+        for query in [
+            "select A, B from table1 order by C",
+            "select A, B from table1 order by 3",
+        ] {
+            assert!(matches!(
+                planner.create_query_plan(query, tx.clone()),
+                Err(ExecutionError::QueryError(QueryError::InvalidOrderBy(_)))
+            ));
+        }
+        drop(planner);
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    errors::TransactionError,
+    materialization::top_n_scan::TopNScan,
+    parser::expression::Expression,
+    record::{field::Value, schema::Schema},
+    scan::Scan,
+};
+
+/// Records affected by an update command with a RETURNING clause. Holds
+/// the values of the clause's expressions over each inserted or updated
+/// record after the change, and over each deleted record before it.
+pub struct ReturnedRecords {
+    expressions: Vec<Expression>,
+    fields: Vec<String>,
+    schema: Schema,
+    records: Vec<Vec<Value>>,
+}
+
+impl ReturnedRecords {
+    /// Returns the records of `fields` over the records of a table, or of
+    /// every field of the table if `fields` is empty.
+    pub fn new(fields: &[(Expression, String)], table_schema: &Schema) -> Result<Self, String> {
+        let fields = if fields.is_empty() {
+            table_schema
+                .get_fields()
+                .into_iter()
+                .map(|f| (Expression::Field(f.clone()), f))
+                .collect()
+        } else {
+            fields.to_vec()
+        };
+        let mut schema = Schema::new();
+        for (expression, field_name) in &fields {
+            if schema.has_field(field_name) {
+                return Err(format!("duplicate field {}", field_name));
+            }
+            schema.add_field(field_name, &expression.infer_spec(table_schema)?);
+        }
+        let (expressions, fields) = fields.into_iter().unzip();
+        Ok(ReturnedRecords {
+            expressions,
+            fields,
+            schema,
+            records: Vec::new(),
+        })
+    }
+
+    /// Adds the values of the expressions over the current record of `scan`.
+    pub(crate) fn add(&mut self, scan: &mut Scan) -> Result<(), TransactionError> {
+        let mut record = Vec::with_capacity(self.expressions.len());
+        for expression in &self.expressions {
+            record.push(expression.evaluate(scan)?);
+        }
+        self.records.push(record);
+        Ok(())
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns a scan over the records in the order they were affected.
+    pub fn into_scan(self) -> Scan {
+        Scan::from(TopNScan::new(self.fields, self.records))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        db::SimpleDB,
+        errors::{ExecutionError, QueryError},
+        planner::{
+            basic_update_planner::BasicUpdatePlanner,
+            heuristic_query_planner::HeuristicQueryPlanner,
+            index_update_planner::IndexUpdatePlanner, Planner, UpdatePlanner,
+        },
+        record::field::Value,
+        scan::ScanControl,
+    };
+
+    #[test]
+    fn test_update_returning() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let update_planners: [Box<dyn UpdatePlanner>; 2] = [
+            Box::new(BasicUpdatePlanner::new(db.metadata_manager.clone())),
+            Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone())),
+        ];
+        for (i, update_planner) in update_planners.into_iter().enumerate() {
+            let planner = Planner::new(
+                Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+                update_planner,
+            );
+            let table = format!("r{}", i);
+            planner.execute_update(
+                &format!("create table {} (K I32, V VARCHAR(5))", table),
+                tx.clone(),
+            )?;
+            let execute = |command: &str| -> Result<Vec<Vec<Value>>, ExecutionError> {
+                let returned =
+                    planner.execute_update_returning(&command.replace("{}", &table), tx.clone())?;
+                let fields = returned.schema().get_fields();
+                let mut scan = returned.into_scan();
+                let mut records = vec![];
+                while scan.next()? {
+                    let mut record = vec![];
+                    for field_name in &fields {
+                        record.push(scan.get_value(field_name)?);
+                    }
+                    records.push(record);
+                }
+                Ok(records)
+            };
+            let string = |s: &str| Value::String(s.to_string());
+
+            assert_eq!(
+                execute("insert into {} (K, V) values (1, 'a'), (2, 'b') returning V, K * 2 as D")?,
+                vec![
+                    vec![Value::I32(2), string("a")],
+                    vec![Value::I32(4), string("b")]
+                ]
+            );
+            // Updated records are returned with their new values
+            assert_eq!(
+                execute("update {} set V = 'c' where K = 2 returning K, V")?,
+                vec![vec![Value::I32(2), string("c")]]
+            );
+            assert_eq!(
+                execute(
+                    "insert into {} (K, V) values (1, 'x'), (3, 'y') \
+                     on conflict (K) do update set V = excluded.V returning *"
+                )?,
+                vec![
+                    vec![Value::I32(1), string("x")],
+                    vec![Value::I32(3), string("y")]
+                ]
+            );
+            assert_eq!(
+                execute(
+                    "insert into {} (K, V) select K + 10 as N, V from {} where K = 3 returning K"
+                )?,
+                vec![vec![Value::I32(13)]]
+            );
+            // Deleted records are returned with the values they had
+            let mut deleted = execute("delete from {} returning V")?;
+            deleted.sort();
+            assert_eq!(
+                deleted,
+                vec![
+                    vec![string("c")],
+                    vec![string("x")],
+                    vec![string("y")],
+                    vec![string("y")]
+                ]
+            );
+
+            for command in [
+                "delete from {}",
+                "delete from {} returning Z",
+                "delete from {} returning K, V as K",
+            ] {
+                match execute(command) {
+                    Err(ExecutionError::QueryError(QueryError::InvalidReturning(_))) => {}
+                    _ => panic!("Expected InvalidReturning error for {}", command),
+                }
+            }
+            // The records of a plain update are not returned
+            let updated = planner.execute_update(
+                &format!("insert into {} (K) values (5) returning K", table),
+                tx.clone(),
+            )?;
+            assert_eq!(updated, 1);
+        }
+
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::{ExecutionError, QueryError},
+    materialization::set_operation_plan::SetOperationPlan,
+    parser::statement::{QueryData, SetOperator},
+    plan::{union_all_plan::UnionAllPlan, Plan, PlanControl},
+    record::field::Spec,
+    tx::transaction::Transaction,
+};
+
+use super::{order_by::add_order_and_limit, CommonTablePlans, QueryPlanner};
+
+/// Returns the output fields of `query` in the order of its select list.
+pub(super) fn output_fields(query: &QueryData, plan: &Plan) -> Vec<String> {
+    query
+        .fields
+        .clone()
+        .unwrap_or_else(|| plan.schema().get_fields())
+}
+
+/// Checks that two lists of fields, matched by position, agree in number and
+/// type.
+pub(super) fn check_compatible_fields(
+    plan1: &Plan,
+    fields1: &[String],
+    plan2: &Plan,
+    fields2: &[String],
+) -> Result<(), String> {
+    if fields1.len() != fields2.len() {
+        return Err(format!(
+            "expected {} fields but got {}",
+            fields1.len(),
+            fields2.len()
+        ));
+    }
+    for (field1, field2) in fields1.iter().zip(fields2) {
+        let specs = (
+            plan1.schema().get_field_spec(field1),
+            plan2.schema().get_field_spec(field2),
+        );
+        if !matches!(
+            specs,
+            (Spec::I32, Spec::I32) | (Spec::VarChar(_), Spec::VarChar(_))
+        ) {
+            return Err(format!(
+                "fields {} and {} have different types",
+                field1, field2
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Combines `plan`, the plan of the first SELECT block of a compound query,
+/// with the plans of the blocks in `query.set_operations`.
+///
+/// Blocks are matched by the position of their output fields, which must
+/// agree in number and type. The combined plan is then ordered and limited as
+/// requested by the query.
+pub(crate) fn add_set_operations(
+    mut plan: Plan,
+    query: &QueryData,
+    common_tables: &CommonTablePlans,
+    tx: Arc<Mutex<Transaction>>,
+    query_planner: &dyn QueryPlanner,
+) -> Result<Plan, ExecutionError> {
+    let fields = output_fields(query, &plan);
+    for (operator, branch) in &query.set_operations {
+        let branch_plan =
+            query_planner.create_plan_with_common_tables(branch, common_tables, tx.clone())?;
+        let branch_fields = output_fields(branch, &branch_plan);
+        check_compatible_fields(&plan, &fields, &branch_plan, &branch_fields)
+            .map_err(QueryError::IncompatibleSetOperation)?;
+
+        plan = match operator {
+            SetOperator::UnionAll => Plan::from(UnionAllPlan::new(
+                plan,
+                branch_plan,
+                fields.clone(),
+                branch_fields,
+            )),
+            _ => Plan::from(SetOperationPlan::new(
+                tx.clone(),
+                *operator,
+                plan,
+                branch_plan,
+                fields.clone(),
+                branch_fields,
+            )),
+        };
+    }
+    Ok(add_order_and_limit(plan, query, tx)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::SimpleDB, scan::ScanControl};
+
+    #[test]
+    fn test_set_operations() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = db.planner.lock().unwrap();
+        planner.execute_update("create table table1 (A I32, B VARCHAR(5))", tx.clone())?;
+        planner.execute_update("create table table2 (C I32, D VARCHAR(10))", tx.clone())?;
+        for (a, b) in [(1, "x"), (2, "y"), (2, "y"), (3, "z")] {
+            planner.execute_update(
+                &format!("insert into table1 (A, B) values ({}, '{}')", a, b),
+                tx.clone(),
+            )?;
+        }
+        for (c, d) in [(2, "y"), (4, "long value")] {
+            planner.execute_update(
+                &format!("insert into table2 (C, D) values ({}, '{}')", c, d),
+                tx.clone(),
+            )?;
+        }
+
+        let collect = |query: &str| -> Result<Vec<(i32, String)>, ExecutionError> {
+            let mut plan = planner.create_query_plan(query, tx.clone())?;
+            let mut scan = plan.open(tx.clone())?;
+            let mut result = vec![];
+            while scan.next()? {
+                result.push((scan.get_i32("A")?.unwrap(), scan.get_string("B")?.unwrap()));
+            }
+            Ok(result)
+        };
+        let records = |values: &[(i32, &str)]| {
+            values
+                .iter()
+                .map(|(i, s)| (*i, s.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            collect("select A, B from table1 union all select C, D from table2")?,
+            records(&[
+                (1, "x"),
+                (2, "y"),
+                (2, "y"),
+                (3, "z"),
+                (2, "y"),
+                (4, "long value")
+            ])
+        );
+        assert_eq!(
+            collect("select A, B from table1 union select C, D from table2")?,
+            records(&[(1, "x"), (2, "y"), (3, "z"), (4, "long value")])
+        );
+        assert_eq!(
+            collect("select A, B from table1 intersect select C, D from table2")?,
+            records(&[(2, "y")])
+        );
+        assert_eq!(
+            collect("select A, B from table1 except select C, D from table2")?,
+            records(&[(1, "x"), (3, "z")])
+        );
+        assert_eq!(
+            collect(
+                "select A, B from table1 except select C, D from table2 \
+                 union select C, D from table2 order by 1 desc limit 2"
+            )?,
+            records(&[(4, "long value"), (3, "z")])
+        );
+
+        for query in [
+            "select A, B from table1 union select C from table2",
+            "select A, B from table1 union select D, C from table2",
+        ] {
+            assert!(matches!(
+                planner.create_query_plan(query, tx.clone()),
+                Err(ExecutionError::QueryError(
+                    QueryError::IncompatibleSetOperation(_)
+                ))
+            ));
+        }
+        drop(planner);
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{errors::QueryError, parser::parameter::ParsedStatement};
+
+/// Maximum number of parsed statements kept by a [`Planner`](super::Planner).
+pub const STATEMENT_CACHE_SIZE: usize = 128;
+
+/// Parsed statements by their SQL text, so that statements executed again
+/// are not parsed again. The least recently used statement is dropped when
+/// the cache is full.
+pub struct StatementCache {
+    capacity: usize,
+    // Statement and the time it was last used
+    statements: HashMap<String, (Arc<ParsedStatement>, u64)>,
+    clock: u64,
+}
+
+impl StatementCache {
+    pub fn new(capacity: usize) -> Self {
+        StatementCache {
+            capacity,
+            statements: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Returns the cached statement for `sql`, parsing it if needed. Statements
+    /// that fail to parse are not cached.
+    pub fn get_or_parse(&mut self, sql: &str) -> Result<Arc<ParsedStatement>, QueryError> {
+        self.clock += 1;
+        if let Some((statement, last_used)) = self.statements.get_mut(sql) {
+            *last_used = self.clock;
+            return Ok(statement.clone());
+        }
+
+        let statement = Arc::new(ParsedStatement::parse(sql)?);
+        if self.statements.len() >= self.capacity {
+            let least_recently_used = self
+                .statements
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(sql, _)| sql.clone());
+            if let Some(sql) = least_recently_used {
+                self.statements.remove(&sql);
+            }
+        }
+        self.statements
+            .insert(sql.to_string(), (statement.clone(), self.clock));
+        Ok(statement)
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_cache() -> Result<(), QueryError> {
+        let mut cache = StatementCache::new(2);
+        let a = cache.get_or_parse("select a from t")?;
+        assert!(Arc::ptr_eq(&a, &cache.get_or_parse("select a from t")?));
+        cache.get_or_parse("select b from t")?;
+        assert!(cache.get_or_parse("select from").is_err());
+        assert_eq!(cache.len(), 2);
+
+        // "select b from t" is the least recently used statement
+        cache.get_or_parse("select a from t")?;
+        cache.get_or_parse("select c from t")?;
+        assert_eq!(cache.len(), 2);
+        assert!(Arc::ptr_eq(&a, &cache.get_or_parse("select a from t")?));
+        Ok(())
+    }
+}
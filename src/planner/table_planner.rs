@@ -5,7 +5,10 @@ use std::{
 
 use crate::{
     errors::TransactionError,
-    index::plan::{index_join_plan::IndexJoinPlan, index_select_plan::IndexSelectPlan},
+    index::{
+        plan::{index_join_plan::IndexJoinPlan, index_select_plan::IndexSelectPlan},
+        SearchKey,
+    },
     metadata::{index_manager::IndexInfo, MetadataManager},
     multibuffer::multibuffer_product_plan::MultiBufferProductPlan,
     parser::predicate::Predicate,
@@ -15,7 +18,8 @@ use crate::{
 };
 
 pub struct TablePlanner {
-    table_plan: TablePlan,
+    // A TablePlan, or the plan of a common table, which has no indexes
+    table_plan: Plan,
     predicate: Option<Predicate>,
     tx: Arc<Mutex<Transaction>>,
     index_info_map: HashMap<String, IndexInfo>,
@@ -35,34 +39,55 @@ impl TablePlanner {
             .get_index_info(table_name, tx.clone())?;
 
         Ok(Self {
-            table_plan,
+            table_plan: Plan::from(table_plan),
             predicate: predicate.clone(),
             tx,
             index_info_map,
         })
     }
 
+    pub(crate) fn new_common_table(
+        plan: Plan,
+        predicate: &Option<Predicate>,
+        tx: Arc<Mutex<Transaction>>,
+    ) -> Self {
+        Self {
+            table_plan: plan,
+            predicate: predicate.clone(),
+            tx,
+            index_info_map: HashMap::new(),
+        }
+    }
+
     pub(crate) fn make_select_plan(&self) -> Result<Plan, TransactionError> {
         if let Some(predicate) = &self.predicate {
-            for field_name in self.index_info_map.keys() {
-                match predicate.equates_with_constant(field_name) {
-                    Some(value) => {
-                        let index_info = self.index_info_map.get(field_name).unwrap();
-                        let index_select_plan =
-                            IndexSelectPlan::new(self.table_plan.clone(), index_info, &value);
-                        return Ok(self.add_select_predicates(
-                            Plan::from(index_select_plan),
-                            predicate.clone(),
-                        ));
-                    }
-                    None => {}
-                }
+            // Equalities select fewer records than ranges and IN lists
+            let search_keys = self
+                .index_info_map
+                .keys()
+                .find_map(|field_name| {
+                    let value = predicate.equates_with_constant(field_name)?;
+                    Some((field_name, vec![SearchKey::Equal(value)]))
+                })
+                .or_else(|| {
+                    self.index_info_map.keys().find_map(|field_name| {
+                        Some((field_name, predicate.search_keys(field_name)?))
+                    })
+                });
+            if let Some((field_name, search_keys)) = search_keys {
+                let index_info = self.index_info_map.get(field_name).unwrap();
+                let Plan::TablePlan(table_plan) = &self.table_plan else {
+                    unreachable!("Only stored tables have indexes");
+                };
+                let index_select_plan =
+                    IndexSelectPlan::new_with_keys(table_plan.clone(), index_info, search_keys);
+                return Ok(
+                    self.add_select_predicates(Plan::from(index_select_plan), predicate.clone())
+                );
             }
-            return Ok(
-                self.add_select_predicates(Plan::from(self.table_plan.clone()), predicate.clone())
-            );
+            return Ok(self.add_select_predicates(self.table_plan.clone(), predicate.clone()));
         }
-        Ok(Plan::from(self.table_plan.clone()))
+        Ok(self.table_plan.clone())
     }
 
     pub(crate) fn make_join_plan(
@@ -86,7 +111,7 @@ impl TablePlanner {
     }
 
     pub(crate) fn make_product_plan(&self, current_plan: Plan) -> Result<Plan, TransactionError> {
-        let table_plan = Plan::from(self.table_plan.clone());
+        let table_plan = self.table_plan.clone();
         let plan = if let Some(predicate) = self.predicate.clone() {
             self.add_select_predicates(table_plan, predicate)
         } else {
@@ -113,7 +138,7 @@ impl TablePlanner {
                 let index_info = self.index_info_map.get(field_name).unwrap();
                 let plan = IndexJoinPlan::new(
                     current_plan.clone(),
-                    Plan::from(self.table_plan.clone()),
+                    self.table_plan.clone(),
                     index_info.clone(),
                     outer_field_name,
                 );
@@ -154,3 +179,96 @@ impl TablePlanner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::SimpleDB,
+        errors::{ExecutionError, QueryError},
+        planner::{
+            basic_query_planner::BasicQueryPlanner, heuristic_query_planner::HeuristicQueryPlanner,
+            index_update_planner::IndexUpdatePlanner, Planner, QueryPlanner,
+        },
+        scan::ScanControl,
+    };
+
+    #[test]
+    fn test_like_between_in() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        {
+            let planner = db.planner.lock().unwrap();
+            planner.execute_update("create table table1 (A I32, B VARCHAR(10))", tx.clone())?;
+            planner.execute_update("create index IA on table1 (A)", tx.clone())?;
+            planner.execute_update("create index IB on table1 (B)", tx.clone())?;
+            for i in 0..40 {
+                let b = format!("{}{}", ["ab", "ac", "b"][i % 3], i);
+                planner.execute_update(
+                    &format!("insert into table1 (A, B) values ({}, '{}')", i, b),
+                    tx.clone(),
+                )?;
+            }
+            planner.execute_update("insert into table1 (A, B) values (null, null)", tx.clone())?;
+        }
+
+        // The heuristic planner looks up LIKE prefixes, BETWEEN ranges and IN
+        // lists in the indexes, while the basic planner scans the table
+        let query_planners: [Box<dyn QueryPlanner>; 2] = [
+            Box::new(BasicQueryPlanner::new(db.metadata_manager.clone())),
+            Box::new(HeuristicQueryPlanner::new(db.metadata_manager.clone())),
+        ];
+        for query_planner in query_planners {
+            let update_planner = Box::new(IndexUpdatePlanner::new(db.metadata_manager.clone()));
+            let planner = Planner::new(query_planner, update_planner);
+            let collect = |predicate: &str| -> Result<Vec<i32>, ExecutionError> {
+                let query = format!("select A from table1 where {}", predicate);
+                let mut plan = planner.create_query_plan(&query, tx.clone())?;
+                let mut scan = plan.open(tx.clone())?;
+                let mut result = vec![];
+                while scan.next()? {
+                    result.push(scan.get_i32("A")?.unwrap());
+                }
+                result.sort();
+                Ok(result)
+            };
+
+            assert_eq!(
+                collect("B like 'ab%'")?,
+                (0..40).step_by(3).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                collect("B like 'a_1%'")?,
+                vec![1, 10, 12, 13, 15, 16, 18, 19]
+            );
+            assert_eq!(collect("B like '%9'")?, vec![9, 19, 29, 39]);
+            assert_eq!(collect("B like 'b2'")?, vec![2]);
+            assert_eq!(
+                collect("A between 10 and 15")?,
+                vec![10, 11, 12, 13, 14, 15]
+            );
+            assert_eq!(collect("A between 15 and 10")?, vec![]);
+            assert_eq!(collect("A between 38 and null")?, vec![]);
+            assert_eq!(collect("A in (30, 3, 30, null, 99)")?, vec![3, 30]);
+            assert_eq!(collect("B in ('b5', 'ab3') and A in (3, 4)")?, vec![3]);
+            assert_eq!(
+                collect("A between 1 and 20 and B like 'b%'")?,
+                vec![2, 5, 8, 11, 14, 17, 20]
+            );
+
+            for predicate in ["A like 'x%'", "A between 1 and 'x'", "A in (1, 'x')"] {
+                assert!(matches!(
+                    collect(predicate),
+                    Err(ExecutionError::QueryError(QueryError::InvalidExpression(_)))
+                ));
+            }
+        }
+
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
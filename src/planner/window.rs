@@ -0,0 +1,167 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    errors::QueryError,
+    materialization::{
+        record_comparator::SortKey,
+        window_function::{WindowColumn, WindowFn},
+        window_plan::WindowPlan,
+    },
+    parser::statement::QueryData,
+    plan::{Plan, PlanControl},
+    tx::transaction::Transaction,
+};
+
+/// Evaluates the window functions of the query on top of `plan`.
+///
+/// Consecutive functions sharing the same PARTITION BY and ORDER BY clauses
+/// are computed by a single [`WindowPlan`], so the records are only sorted
+/// once for them.
+pub(crate) fn add_window_functions(
+    mut plan: Plan,
+    query: &QueryData,
+    tx: Arc<Mutex<Transaction>>,
+) -> Result<Plan, QueryError> {
+    let mut window_functions = query.window_functions.iter().peekable();
+    while let Some(first) = window_functions.next() {
+        let mut columns = vec![first];
+        while let Some(next) = window_functions
+            .next_if(|w| w.partition_by == first.partition_by && w.order_by == first.order_by)
+        {
+            columns.push(next);
+        }
+
+        let check_field = |field_name: &str| {
+            if plan.schema().has_field(field_name) {
+                Ok(())
+            } else {
+                Err(QueryError::InvalidWindow(format!(
+                    "unknown field {}",
+                    field_name
+                )))
+            }
+        };
+        for field_name in &first.partition_by {
+            check_field(field_name)?;
+        }
+        let mut order_by = vec![];
+        for item in &first.order_by {
+            let Some(field_name) = item.expression.try_get_field() else {
+                return Err(QueryError::InvalidWindow(
+                    "ORDER BY keys must be fields".to_string(),
+                ));
+            };
+            check_field(field_name)?;
+            order_by.push(SortKey::new(field_name, item.direction, item.nulls));
+        }
+        for column in &columns {
+            match &column.function {
+                WindowFn::Aggregate(aggregation_fn) => {
+                    aggregation_fn
+                        .output_spec(plan.schema())
+                        .map_err(QueryError::InvalidWindow)?;
+                }
+                function => {
+                    if let Some(field_name) = function.get_source_field() {
+                        check_field(field_name)?;
+                    }
+                }
+            }
+        }
+
+        let columns = columns
+            .into_iter()
+            .map(|w| WindowColumn::new(w.function.clone(), w.frame, &w.field_name))
+            .collect();
+        plan = Plan::from(WindowPlan::new(
+            tx.clone(),
+            plan,
+            first.partition_by.clone(),
+            order_by,
+            columns,
+        ));
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::SimpleDB, errors::ExecutionError, record::field::Value, scan::ScanControl};
+
+    #[test]
+    fn test_window_functions() -> Result<(), ExecutionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 1024;
+        let num_buffers = 100;
+        let db = SimpleDB::new(temp_dir, block_size, num_buffers)?;
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+
+        let planner = db.planner.lock().unwrap();
+        planner.execute_update("create table emp (Dept VARCHAR(5), Salary I32)", tx.clone())?;
+        for (dept, salary) in [
+            ("a", 10),
+            ("b", 7),
+            ("a", 30),
+            ("a", 20),
+            ("b", 7),
+            ("c", 1),
+        ] {
+            planner.execute_update(
+                &format!(
+                    "insert into emp (Dept, Salary) values ('{}', {})",
+                    dept, salary
+                ),
+                tx.clone(),
+            )?;
+        }
+
+        let mut plan = planner.create_query_plan(
+            "select Dept, Salary, \
+             row_number() over (partition by Dept order by Salary desc) as N, \
+             dense_rank() over (partition by Dept order by Salary desc) as R, \
+             lead(Salary) over (partition by Dept order by Salary desc) as Next, \
+             sum(Salary) over (order by Salary, N rows between 1 preceding and current row) as Pair \
+             from emp order by Dept, N",
+            tx.clone(),
+        )?;
+        let mut scan = plan.open(tx.clone())?;
+        let mut records = vec![];
+        while scan.next()? {
+            records.push((
+                scan.get_string("Dept")?.unwrap(),
+                scan.get_i32("Salary")?.unwrap(),
+                scan.get_i32("N")?.unwrap(),
+                scan.get_i32("R")?.unwrap(),
+                scan.get_value("Next")?,
+                scan.get_i32("Pair")?.unwrap(),
+            ));
+        }
+        drop(scan);
+        // Ordered by salary, the records are 1, 7, 7, 10, 20, 30
+        let expected = [
+            ("a", 30, 1, 1, Value::I32(20), 50),
+            ("a", 20, 2, 2, Value::I32(10), 30),
+            ("a", 10, 3, 3, Value::Null, 17),
+            ("b", 7, 1, 1, Value::I32(7), 8),
+            ("b", 7, 2, 1, Value::Null, 14),
+            ("c", 1, 1, 1, Value::Null, 1),
+        ]
+        .map(|(d, s, n, r, next, pair)| (d.to_string(), s, n, r, next, pair));
+        assert_eq!(records, expected);
+
+        for query in [
+            "select rank() over (partition by Name) as R from emp",
+            "select rank() over (order by Salary + 1) as R from emp",
+            "select lag(Name) over () as L from emp",
+        ] {
+            assert!(matches!(
+                planner.create_query_plan(query, tx.clone()),
+                Err(ExecutionError::QueryError(QueryError::InvalidWindow(_)))
+            ));
+        }
+        drop(planner);
+        tx.lock().unwrap().commit()?;
+        Ok(())
+    }
+}
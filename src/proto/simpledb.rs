@@ -157,6 +157,76 @@ pub struct StatementExecuteUpdateResponse {
     #[prost(uint64, tag = "1")]
     pub num_updated_rows: u64,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatementExecuteUpdateReturningRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub command: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StatementExecuteUpdateReturningResponse {
+    #[prost(uint64, tag = "1")]
+    pub result_set_id: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementSetI32Request {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(uint64, tag = "2")]
+    pub index: u64,
+    #[prost(int32, tag = "3")]
+    pub value: i32,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementSetI32Response {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PreparedStatementSetStringRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(uint64, tag = "2")]
+    pub index: u64,
+    #[prost(string, tag = "3")]
+    pub value: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementSetStringResponse {}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementSetNullRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(uint64, tag = "2")]
+    pub index: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementSetNullResponse {}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementExecuteQueryRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementExecuteQueryResponse {
+    #[prost(uint64, tag = "1")]
+    pub result_set_id: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementExecuteUpdateRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementExecuteUpdateResponse {
+    #[prost(uint64, tag = "1")]
+    pub num_updated_rows: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementCloseRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PreparedStatementCloseResponse {}
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct ConnectionCreateStatementRequest {
     #[prost(uint64, tag = "1")]
@@ -167,6 +237,18 @@ pub struct ConnectionCreateStatementResponse {
     #[prost(uint64, tag = "1")]
     pub statement_id: u64,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConnectionPrepareStatementRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub sql: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ConnectionPrepareStatementResponse {
+    #[prost(uint64, tag = "1")]
+    pub prepared_statement_id: u64,
+}
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct ConnectionCloseRequest {
     #[prost(uint64, tag = "1")]
@@ -188,6 +270,25 @@ pub struct ConnectionRollbackRequest {
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct ConnectionRollbackResponse {}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ConnectionSetAutocommitRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(bool, tag = "2")]
+    pub autocommit: bool,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ConnectionSetAutocommitResponse {}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ConnectionGetAutocommitRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ConnectionGetAutocommitResponse {
+    #[prost(bool, tag = "1")]
+    pub autocommit: bool,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DriverCreateConnectionRequest {
     #[prost(string, tag = "1")]
@@ -718,10 +819,31 @@ pub mod statement_service_client {
             ));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn execute_update_returning(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StatementExecuteUpdateReturningRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StatementExecuteUpdateReturningResponse>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/simpledb.StatementService/ExecuteUpdateReturning",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.StatementService",
+                "ExecuteUpdateReturning",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated client implementations.
-pub mod connection_service_client {
+pub mod prepared_statement_service_client {
     #![allow(
         unused_variables,
         dead_code,
@@ -732,10 +854,10 @@ pub mod connection_service_client {
     use tonic::codegen::http::Uri;
     use tonic::codegen::*;
     #[derive(Debug, Clone)]
-    pub struct ConnectionServiceClient<T> {
+    pub struct PreparedStatementServiceClient<T> {
         inner: tonic::client::Grpc<T>,
     }
-    impl ConnectionServiceClient<tonic::transport::Channel> {
+    impl PreparedStatementServiceClient<tonic::transport::Channel> {
         /// Attempt to create a new client by connecting to a given endpoint.
         pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
         where
@@ -746,7 +868,7 @@ pub mod connection_service_client {
             Ok(Self::new(conn))
         }
     }
-    impl<T> ConnectionServiceClient<T>
+    impl<T> PreparedStatementServiceClient<T>
     where
         T: tonic::client::GrpcService<tonic::body::BoxBody>,
         T::Error: Into<StdError>,
@@ -764,7 +886,7 @@ pub mod connection_service_client {
         pub fn with_interceptor<F>(
             inner: T,
             interceptor: F,
-        ) -> ConnectionServiceClient<InterceptedService<T, F>>
+        ) -> PreparedStatementServiceClient<InterceptedService<T, F>>
         where
             F: tonic::service::Interceptor,
             T::ResponseBody: Default,
@@ -777,7 +899,7 @@ pub mod connection_service_client {
             <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error:
                 Into<StdError> + std::marker::Send + std::marker::Sync,
         {
-            ConnectionServiceClient::new(InterceptedService::new(inner, interceptor))
+            PreparedStatementServiceClient::new(InterceptedService::new(inner, interceptor))
         }
         /// Compress requests with the given encoding.
         ///
@@ -810,11 +932,11 @@ pub mod connection_service_client {
             self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
-        pub async fn create_statement(
+        pub async fn set_i32(
             &mut self,
-            request: impl tonic::IntoRequest<super::ConnectionCreateStatementRequest>,
+            request: impl tonic::IntoRequest<super::PreparedStatementSetI32Request>,
         ) -> std::result::Result<
-            tonic::Response<super::ConnectionCreateStatementResponse>,
+            tonic::Response<super::PreparedStatementSetI32Response>,
             tonic::Status,
         > {
             self.inner.ready().await.map_err(|e| {
@@ -822,63 +944,121 @@ pub mod connection_service_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/CreateStatement");
+                http::uri::PathAndQuery::from_static("/simpledb.PreparedStatementService/SetI32");
             let mut req = request.into_request();
             req.extensions_mut().insert(GrpcMethod::new(
-                "simpledb.ConnectionService",
-                "CreateStatement",
+                "simpledb.PreparedStatementService",
+                "SetI32",
             ));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn close(
+        pub async fn set_string(
             &mut self,
-            request: impl tonic::IntoRequest<super::ConnectionCloseRequest>,
-        ) -> std::result::Result<tonic::Response<super::ConnectionCloseResponse>, tonic::Status>
-        {
+            request: impl tonic::IntoRequest<super::PreparedStatementSetStringRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementSetStringResponse>,
+            tonic::Status,
+        > {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/Close");
+            let path = http::uri::PathAndQuery::from_static(
+                "/simpledb.PreparedStatementService/SetString",
+            );
             let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(GrpcMethod::new("simpledb.ConnectionService", "Close"));
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.PreparedStatementService",
+                "SetString",
+            ));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn commit(
+        pub async fn set_null(
             &mut self,
-            request: impl tonic::IntoRequest<super::ConnectionCommitRequest>,
-        ) -> std::result::Result<tonic::Response<super::ConnectionCommitResponse>, tonic::Status>
-        {
+            request: impl tonic::IntoRequest<super::PreparedStatementSetNullRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementSetNullResponse>,
+            tonic::Status,
+        > {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/Commit");
+            let path =
+                http::uri::PathAndQuery::from_static("/simpledb.PreparedStatementService/SetNull");
             let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(GrpcMethod::new("simpledb.ConnectionService", "Commit"));
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.PreparedStatementService",
+                "SetNull",
+            ));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn rollback(
+        pub async fn execute_query(
             &mut self,
-            request: impl tonic::IntoRequest<super::ConnectionRollbackRequest>,
-        ) -> std::result::Result<tonic::Response<super::ConnectionRollbackResponse>, tonic::Status>
-        {
+            request: impl tonic::IntoRequest<super::PreparedStatementExecuteQueryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementExecuteQueryResponse>,
+            tonic::Status,
+        > {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/Rollback");
+            let path = http::uri::PathAndQuery::from_static(
+                "/simpledb.PreparedStatementService/ExecuteQuery",
+            );
             let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(GrpcMethod::new("simpledb.ConnectionService", "Rollback"));
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.PreparedStatementService",
+                "ExecuteQuery",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn execute_update(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PreparedStatementExecuteUpdateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementExecuteUpdateResponse>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/simpledb.PreparedStatementService/ExecuteUpdate",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.PreparedStatementService",
+                "ExecuteUpdate",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn close(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PreparedStatementCloseRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementCloseResponse>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/simpledb.PreparedStatementService/Close");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.PreparedStatementService",
+                "Close",
+            ));
             self.inner.unary(req, path, codec).await
         }
     }
 }
 /// Generated client implementations.
-pub mod driver_service_client {
+pub mod connection_service_client {
     #![allow(
         unused_variables,
         dead_code,
@@ -889,10 +1069,10 @@ pub mod driver_service_client {
     use tonic::codegen::http::Uri;
     use tonic::codegen::*;
     #[derive(Debug, Clone)]
-    pub struct DriverServiceClient<T> {
+    pub struct ConnectionServiceClient<T> {
         inner: tonic::client::Grpc<T>,
     }
-    impl DriverServiceClient<tonic::transport::Channel> {
+    impl ConnectionServiceClient<tonic::transport::Channel> {
         /// Attempt to create a new client by connecting to a given endpoint.
         pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
         where
@@ -903,7 +1083,7 @@ pub mod driver_service_client {
             Ok(Self::new(conn))
         }
     }
-    impl<T> DriverServiceClient<T>
+    impl<T> ConnectionServiceClient<T>
     where
         T: tonic::client::GrpcService<tonic::body::BoxBody>,
         T::Error: Into<StdError>,
@@ -921,7 +1101,7 @@ pub mod driver_service_client {
         pub fn with_interceptor<F>(
             inner: T,
             interceptor: F,
-        ) -> DriverServiceClient<InterceptedService<T, F>>
+        ) -> ConnectionServiceClient<InterceptedService<T, F>>
         where
             F: tonic::service::Interceptor,
             T::ResponseBody: Default,
@@ -934,7 +1114,7 @@ pub mod driver_service_client {
             <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error:
                 Into<StdError> + std::marker::Send + std::marker::Sync,
         {
-            DriverServiceClient::new(InterceptedService::new(inner, interceptor))
+            ConnectionServiceClient::new(InterceptedService::new(inner, interceptor))
         }
         /// Compress requests with the given encoding.
         ///
@@ -967,11 +1147,11 @@ pub mod driver_service_client {
             self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
-        pub async fn create_connection(
+        pub async fn create_statement(
             &mut self,
-            request: impl tonic::IntoRequest<super::DriverCreateConnectionRequest>,
+            request: impl tonic::IntoRequest<super::ConnectionCreateStatementRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::DriverCreateConnectionResponse>,
+            tonic::Response<super::ConnectionCreateStatementResponse>,
             tonic::Status,
         > {
             self.inner.ready().await.map_err(|e| {
@@ -979,70 +1159,288 @@ pub mod driver_service_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/simpledb.DriverService/CreateConnection");
+                http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/CreateStatement");
             let mut req = request.into_request();
             req.extensions_mut().insert(GrpcMethod::new(
-                "simpledb.DriverService",
-                "CreateConnection",
+                "simpledb.ConnectionService",
+                "CreateStatement",
             ));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod metadata_service_server {
-    #![allow(
-        unused_variables,
-        dead_code,
-        missing_docs,
-        clippy::wildcard_imports,
-        clippy::let_unit_value
-    )]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with MetadataServiceServer.
-    #[async_trait]
-    pub trait MetadataService: std::marker::Send + std::marker::Sync + 'static {
-        async fn get_column_count(
-            &self,
-            request: tonic::Request<super::MetadataGetColumnCountRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::MetadataGetColumnCountResponse>,
-            tonic::Status,
-        >;
-        async fn get_column_name(
-            &self,
-            request: tonic::Request<super::MetadataGetColumnNameRequest>,
-        ) -> std::result::Result<tonic::Response<super::MetadataGetColumnNameResponse>, tonic::Status>;
-        async fn get_column_display_size(
-            &self,
-            request: tonic::Request<super::MetadataGetColumnDisplaySizeRequest>,
+        pub async fn prepare_statement(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConnectionPrepareStatementRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::MetadataGetColumnDisplaySizeResponse>,
+            tonic::Response<super::ConnectionPrepareStatementResponse>,
             tonic::Status,
-        >;
-        async fn get_column_type(
-            &self,
-            request: tonic::Request<super::MetadataGetColumnTypeRequest>,
-        ) -> std::result::Result<tonic::Response<super::MetadataGetColumnTypeResponse>, tonic::Status>;
-    }
-    #[derive(Debug)]
-    pub struct MetadataServiceServer<T> {
-        inner: Arc<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    impl<T> MetadataServiceServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/simpledb.ConnectionService/PrepareStatement",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.ConnectionService",
+                "PrepareStatement",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn close(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConnectionCloseRequest>,
+        ) -> std::result::Result<tonic::Response<super::ConnectionCloseResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/Close");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("simpledb.ConnectionService", "Close"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn commit(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConnectionCommitRequest>,
+        ) -> std::result::Result<tonic::Response<super::ConnectionCommitResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/Commit");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("simpledb.ConnectionService", "Commit"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn rollback(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConnectionRollbackRequest>,
+        ) -> std::result::Result<tonic::Response<super::ConnectionRollbackResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/Rollback");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("simpledb.ConnectionService", "Rollback"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn set_autocommit(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConnectionSetAutocommitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConnectionSetAutocommitResponse>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/SetAutocommit");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.ConnectionService",
+                "SetAutocommit",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_autocommit(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConnectionGetAutocommitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConnectionGetAutocommitResponse>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/simpledb.ConnectionService/GetAutocommit");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.ConnectionService",
+                "GetAutocommit",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated client implementations.
+pub mod driver_service_client {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value
+    )]
+    use tonic::codegen::http::Uri;
+    use tonic::codegen::*;
+    #[derive(Debug, Clone)]
+    pub struct DriverServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl DriverServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> DriverServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + std::marker::Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + std::marker::Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> DriverServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error:
+                Into<StdError> + std::marker::Send + std::marker::Sync,
+        {
+            DriverServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn create_connection(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DriverCreateConnectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DriverCreateConnectionResponse>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::unknown(format!("Service was not ready: {}", e.into()))
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/simpledb.DriverService/CreateConnection");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "simpledb.DriverService",
+                "CreateConnection",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod metadata_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with MetadataServiceServer.
+    #[async_trait]
+    pub trait MetadataService: std::marker::Send + std::marker::Sync + 'static {
+        async fn get_column_count(
+            &self,
+            request: tonic::Request<super::MetadataGetColumnCountRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MetadataGetColumnCountResponse>,
+            tonic::Status,
+        >;
+        async fn get_column_name(
+            &self,
+            request: tonic::Request<super::MetadataGetColumnNameRequest>,
+        ) -> std::result::Result<tonic::Response<super::MetadataGetColumnNameResponse>, tonic::Status>;
+        async fn get_column_display_size(
+            &self,
+            request: tonic::Request<super::MetadataGetColumnDisplaySizeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MetadataGetColumnDisplaySizeResponse>,
+            tonic::Status,
+        >;
+        async fn get_column_type(
+            &self,
+            request: tonic::Request<super::MetadataGetColumnTypeRequest>,
+        ) -> std::result::Result<tonic::Response<super::MetadataGetColumnTypeResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct MetadataServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> MetadataServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
                 max_encoding_message_size: None,
             }
         }
@@ -1857,6 +2255,13 @@ pub mod statement_service_server {
             tonic::Response<super::StatementExecuteUpdateResponse>,
             tonic::Status,
         >;
+        async fn execute_update_returning(
+            &self,
+            request: tonic::Request<super::StatementExecuteUpdateReturningRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StatementExecuteUpdateReturningResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct StatementServiceServer<T> {
@@ -2015,6 +2420,49 @@ pub mod statement_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/simpledb.StatementService/ExecuteUpdateReturning" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExecuteUpdateReturningSvc<T: StatementService>(pub Arc<T>);
+                    impl<T: StatementService>
+                        tonic::server::UnaryService<super::StatementExecuteUpdateReturningRequest>
+                        for ExecuteUpdateReturningSvc<T>
+                    {
+                        type Response = super::StatementExecuteUpdateReturningResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StatementExecuteUpdateReturningRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as StatementService>::execute_update_returning(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ExecuteUpdateReturningSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     let mut response = http::Response::new(empty_body());
                     let headers = response.headers_mut();
@@ -2050,6 +2498,423 @@ pub mod statement_service_server {
     }
 }
 /// Generated server implementations.
+pub mod prepared_statement_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with PreparedStatementServiceServer.
+    #[async_trait]
+    pub trait PreparedStatementService: std::marker::Send + std::marker::Sync + 'static {
+        async fn set_i32(
+            &self,
+            request: tonic::Request<super::PreparedStatementSetI32Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementSetI32Response>,
+            tonic::Status,
+        >;
+        async fn set_string(
+            &self,
+            request: tonic::Request<super::PreparedStatementSetStringRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementSetStringResponse>,
+            tonic::Status,
+        >;
+        async fn set_null(
+            &self,
+            request: tonic::Request<super::PreparedStatementSetNullRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementSetNullResponse>,
+            tonic::Status,
+        >;
+        async fn execute_query(
+            &self,
+            request: tonic::Request<super::PreparedStatementExecuteQueryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementExecuteQueryResponse>,
+            tonic::Status,
+        >;
+        async fn execute_update(
+            &self,
+            request: tonic::Request<super::PreparedStatementExecuteUpdateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementExecuteUpdateResponse>,
+            tonic::Status,
+        >;
+        async fn close(
+            &self,
+            request: tonic::Request<super::PreparedStatementCloseRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PreparedStatementCloseResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct PreparedStatementServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> PreparedStatementServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for PreparedStatementServiceServer<T>
+    where
+        T: PreparedStatementService,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/simpledb.PreparedStatementService/SetI32" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetI32Svc<T: PreparedStatementService>(pub Arc<T>);
+                    impl<T: PreparedStatementService>
+                        tonic::server::UnaryService<super::PreparedStatementSetI32Request>
+                        for SetI32Svc<T>
+                    {
+                        type Response = super::PreparedStatementSetI32Response;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PreparedStatementSetI32Request>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PreparedStatementService>::set_i32(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetI32Svc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/simpledb.PreparedStatementService/SetString" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetStringSvc<T: PreparedStatementService>(pub Arc<T>);
+                    impl<T: PreparedStatementService>
+                        tonic::server::UnaryService<super::PreparedStatementSetStringRequest>
+                        for SetStringSvc<T>
+                    {
+                        type Response = super::PreparedStatementSetStringResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PreparedStatementSetStringRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PreparedStatementService>::set_string(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetStringSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/simpledb.PreparedStatementService/SetNull" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetNullSvc<T: PreparedStatementService>(pub Arc<T>);
+                    impl<T: PreparedStatementService>
+                        tonic::server::UnaryService<super::PreparedStatementSetNullRequest>
+                        for SetNullSvc<T>
+                    {
+                        type Response = super::PreparedStatementSetNullResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PreparedStatementSetNullRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PreparedStatementService>::set_null(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetNullSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/simpledb.PreparedStatementService/ExecuteQuery" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExecuteQuerySvc<T: PreparedStatementService>(pub Arc<T>);
+                    impl<T: PreparedStatementService>
+                        tonic::server::UnaryService<super::PreparedStatementExecuteQueryRequest>
+                        for ExecuteQuerySvc<T>
+                    {
+                        type Response = super::PreparedStatementExecuteQueryResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PreparedStatementExecuteQueryRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PreparedStatementService>::execute_query(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ExecuteQuerySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/simpledb.PreparedStatementService/ExecuteUpdate" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExecuteUpdateSvc<T: PreparedStatementService>(pub Arc<T>);
+                    impl<T: PreparedStatementService>
+                        tonic::server::UnaryService<super::PreparedStatementExecuteUpdateRequest>
+                        for ExecuteUpdateSvc<T>
+                    {
+                        type Response = super::PreparedStatementExecuteUpdateResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PreparedStatementExecuteUpdateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PreparedStatementService>::execute_update(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ExecuteUpdateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/simpledb.PreparedStatementService/Close" => {
+                    #[allow(non_camel_case_types)]
+                    struct CloseSvc<T: PreparedStatementService>(pub Arc<T>);
+                    impl<T: PreparedStatementService>
+                        tonic::server::UnaryService<super::PreparedStatementCloseRequest>
+                        for CloseSvc<T>
+                    {
+                        type Response = super::PreparedStatementCloseResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PreparedStatementCloseRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as PreparedStatementService>::close(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = CloseSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    let mut response = http::Response::new(empty_body());
+                    let headers = response.headers_mut();
+                    headers.insert(
+                        tonic::Status::GRPC_STATUS,
+                        (tonic::Code::Unimplemented as i32).into(),
+                    );
+                    headers.insert(
+                        http::header::CONTENT_TYPE,
+                        tonic::metadata::GRPC_CONTENT_TYPE,
+                    );
+                    Ok(response)
+                }),
+            }
+        }
+    }
+    impl<T> Clone for PreparedStatementServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "simpledb.PreparedStatementService";
+    impl<T> tonic::server::NamedService for PreparedStatementServiceServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
+/// Generated server implementations.
 pub mod connection_service_server {
     #![allow(
         unused_variables,
@@ -2069,6 +2934,13 @@ pub mod connection_service_server {
             tonic::Response<super::ConnectionCreateStatementResponse>,
             tonic::Status,
         >;
+        async fn prepare_statement(
+            &self,
+            request: tonic::Request<super::ConnectionPrepareStatementRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConnectionPrepareStatementResponse>,
+            tonic::Status,
+        >;
         async fn close(
             &self,
             request: tonic::Request<super::ConnectionCloseRequest>,
@@ -2081,6 +2953,20 @@ pub mod connection_service_server {
             &self,
             request: tonic::Request<super::ConnectionRollbackRequest>,
         ) -> std::result::Result<tonic::Response<super::ConnectionRollbackResponse>, tonic::Status>;
+        async fn set_autocommit(
+            &self,
+            request: tonic::Request<super::ConnectionSetAutocommitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConnectionSetAutocommitResponse>,
+            tonic::Status,
+        >;
+        async fn get_autocommit(
+            &self,
+            request: tonic::Request<super::ConnectionGetAutocommitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConnectionGetAutocommitResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct ConnectionServiceServer<T> {
@@ -2197,6 +3083,48 @@ pub mod connection_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/simpledb.ConnectionService/PrepareStatement" => {
+                    #[allow(non_camel_case_types)]
+                    struct PrepareStatementSvc<T: ConnectionService>(pub Arc<T>);
+                    impl<T: ConnectionService>
+                        tonic::server::UnaryService<super::ConnectionPrepareStatementRequest>
+                        for PrepareStatementSvc<T>
+                    {
+                        type Response = super::ConnectionPrepareStatementResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ConnectionPrepareStatementRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ConnectionService>::prepare_statement(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = PrepareStatementSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/simpledb.ConnectionService/Close" => {
                     #[allow(non_camel_case_types)]
                     struct CloseSvc<T: ConnectionService>(pub Arc<T>);
@@ -2322,6 +3250,90 @@ pub mod connection_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/simpledb.ConnectionService/SetAutocommit" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetAutocommitSvc<T: ConnectionService>(pub Arc<T>);
+                    impl<T: ConnectionService>
+                        tonic::server::UnaryService<super::ConnectionSetAutocommitRequest>
+                        for SetAutocommitSvc<T>
+                    {
+                        type Response = super::ConnectionSetAutocommitResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ConnectionSetAutocommitRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ConnectionService>::set_autocommit(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetAutocommitSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/simpledb.ConnectionService/GetAutocommit" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAutocommitSvc<T: ConnectionService>(pub Arc<T>);
+                    impl<T: ConnectionService>
+                        tonic::server::UnaryService<super::ConnectionGetAutocommitRequest>
+                        for GetAutocommitSvc<T>
+                    {
+                        type Response = super::ConnectionGetAutocommitResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ConnectionGetAutocommitRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ConnectionService>::get_autocommit(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetAutocommitSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     let mut response = http::Response::new(empty_body());
                     let headers = response.headers_mut();
@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 
 use anyhow::{anyhow, Error};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Spec {
     I32,
     VarChar(usize),
@@ -28,6 +28,8 @@ pub struct RecordPage {
     pub block: BlockId,
     pub layout: Arc<Layout>,
     num_slots: usize,
+    // Whether the block is pinned, which it is not if pinning it failed
+    is_pinned: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -65,22 +67,31 @@ impl Slot {
 }
 
 impl RecordPage {
-    pub fn new(tx: Arc<Mutex<Transaction>>, block: BlockId, layout: Arc<Layout>) -> Self {
-        tx.lock().unwrap().pin(&block).unwrap();
+    pub fn new(
+        tx: Arc<Mutex<Transaction>>,
+        block: BlockId,
+        layout: Arc<Layout>,
+    ) -> Result<Self, TransactionError> {
+        tx.lock().unwrap().pin(&block)?;
         let block_size = tx.lock().unwrap().get_block_size();
         let num_slots = block_size / layout.slot_size;
-        RecordPage {
+        Ok(RecordPage {
             tx,
             block,
             layout,
             num_slots,
-        }
+            is_pinned: true,
+        })
     }
 
     pub fn reset_block(&mut self, block: BlockId) -> Result<(), TransactionError> {
-        self.tx.lock().unwrap().unpin(&self.block);
-        self.tx.lock().unwrap().pin(&block)?;
+        if self.is_pinned {
+            self.tx.lock().unwrap().unpin(&self.block);
+        }
         self.block = block;
+        self.is_pinned = false;
+        self.tx.lock().unwrap().pin(&self.block)?;
+        self.is_pinned = true;
         let block_size = self.tx.lock().unwrap().get_block_size();
         self.num_slots = block_size / self.layout.slot_size;
         Ok(())
@@ -93,7 +104,7 @@ impl RecordPage {
         let mut lock = self.tx.lock().unwrap();
         if lock.is_last_block(&self.block)? {
             if append_if_neccessary {
-                let new_block = lock.append_block(&self.block.file_name)?;
+                let new_block = lock.append_block(&self.block.file_name())?;
                 Ok(Some(new_block))
             } else {
                 Ok(None)
@@ -366,7 +377,9 @@ impl RecordPage {
 
 impl Drop for RecordPage {
     fn drop(&mut self) {
-        self.tx.lock().unwrap().unpin(&self.block);
+        if self.is_pinned {
+            self.tx.lock().unwrap().unpin(&self.block);
+        }
     }
 }
 
@@ -396,7 +409,7 @@ mod tests {
 
         let tx = Arc::new(Mutex::new(db.new_transaction()?));
         let block = db.file_manager.lock().unwrap().append_block("testfile")?;
-        let mut record_page = RecordPage::new(tx.clone(), block.clone(), layout.clone());
+        let mut record_page = RecordPage::new(tx.clone(), block.clone(), layout.clone())?;
         record_page.format()?;
 
         let mut slot = Slot::Start;
@@ -441,7 +454,7 @@ mod tests {
 
         let tx = Arc::new(Mutex::new(db.new_transaction()?));
         let block = db.file_manager.lock().unwrap().append_block("testfile2")?;
-        let mut record_page = RecordPage::new(tx.clone(), block.clone(), layout.clone());
+        let mut record_page = RecordPage::new(tx.clone(), block.clone(), layout.clone())?;
         record_page.format()?;
 
         let mut slot = Slot::Start;
@@ -474,7 +487,7 @@ mod tests {
 
         let tx = Arc::new(Mutex::new(db.new_transaction()?));
         let block = db.file_manager.lock().unwrap().append_block("testfile3")?;
-        let mut record_page = RecordPage::new(tx.clone(), block.clone(), layout.clone());
+        let mut record_page = RecordPage::new(tx.clone(), block.clone(), layout.clone())?;
         record_page.format()?;
 
         let slot = record_page.insert_after(Slot::Start)?;
@@ -0,0 +1,119 @@
+use crate::{errors::TransactionError, record::field::Value};
+
+use super::{Scan, ScanControl};
+
+pub struct LimitScan {
+    base_scan: Box<Scan>,
+    limit: usize,
+    offset: usize,
+    num_returned: usize,
+}
+
+impl LimitScan {
+    pub fn new(base_scan: Scan, limit: usize, offset: usize) -> Self {
+        LimitScan {
+            base_scan: Box::new(base_scan),
+            limit,
+            offset,
+            num_returned: 0,
+        }
+    }
+}
+
+impl ScanControl for LimitScan {
+    fn before_first(&mut self) -> Result<(), TransactionError> {
+        self.num_returned = 0;
+        self.base_scan.before_first()
+    }
+
+    fn next(&mut self) -> Result<bool, TransactionError> {
+        // Stop pulling from the base scan once the limit is reached.
+        if self.num_returned >= self.limit {
+            return Ok(false);
+        }
+        if self.num_returned == 0 {
+            for _ in 0..self.offset {
+                if !self.base_scan.next()? {
+                    self.num_returned = self.limit;
+                    return Ok(false);
+                }
+            }
+        }
+        if !self.base_scan.next()? {
+            self.num_returned = self.limit;
+            return Ok(false);
+        }
+        self.num_returned += 1;
+        Ok(true)
+    }
+
+    fn get_i32(&mut self, field_name: &str) -> Result<Option<i32>, TransactionError> {
+        self.base_scan.get_i32(field_name)
+    }
+
+    fn get_string(&mut self, field_name: &str) -> Result<Option<String>, TransactionError> {
+        self.base_scan.get_string(field_name)
+    }
+
+    fn get_value(&mut self, field_name: &str) -> Result<Value, TransactionError> {
+        self.base_scan.get_value(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.base_scan.has_field(field_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        db::SimpleDB,
+        record::{layout::Layout, schema::Schema},
+        scan::table_scan::TableScan,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_limit_scan() -> Result<(), TransactionError> {
+        let mut schema = Schema::new();
+        schema.add_i32_field("A");
+        let layout = Arc::new(Layout::new(schema));
+
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block_size = 256;
+        let db = SimpleDB::new(temp_dir, block_size, 3)?;
+
+        let tx = Arc::new(Mutex::new(db.new_transaction()?));
+        let mut table_scan = TableScan::new(tx.clone(), "t", layout.clone())?;
+        table_scan.before_first()?;
+        for i in 0..10 {
+            table_scan.insert()?;
+            table_scan.set_i32("A", i)?;
+        }
+
+        let mut scan = LimitScan::new(Scan::from(table_scan), 3, 4);
+        // The scan must be restartable
+        for _ in 0..2 {
+            scan.before_first()?;
+            for i in 4..7 {
+                assert!(scan.next()?);
+                assert_eq!(scan.get_i32("A")?, Some(i));
+            }
+            assert!(!scan.next()?);
+            assert!(!scan.next()?);
+        }
+
+        let table_scan = TableScan::new(tx.clone(), "t", layout.clone())?;
+        let mut scan = LimitScan::new(Scan::from(table_scan), 5, 8);
+        scan.before_first()?;
+        for i in 8..10 {
+            assert!(scan.next()?);
+            assert_eq!(scan.get_i32("A")?, Some(i));
+        }
+        assert!(!scan.next()?);
+        Ok(())
+    }
+}
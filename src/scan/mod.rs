@@ -1,15 +1,19 @@
 use enum_dispatch::enum_dispatch;
 use extend_scan::ExtendScan;
+use limit_scan::LimitScan;
 use product_scan::ProductScan;
 use project_scan::ProjectScan;
 use select_scan::SelectScan;
 use table_scan::TableScan;
+use union_all_scan::UnionAllScan;
 
 use crate::{
     errors::TransactionError,
     index::scan::{index_join_scan::IndexJoinScan, index_select_scan::IndexSelectScan},
     materialization::{
-        group_by_scan::GroupByScan, merge_join_scan::MergeJoinScan, sort_scan::SortScan,
+        group_by_scan::GroupByScan, merge_join_scan::MergeJoinScan,
+        set_operation_scan::SetOperationScan, sort_scan::SortScan, top_n_scan::TopNScan,
+        window_scan::WindowScan,
     },
     multibuffer::{chunk_scan::ChunkScan, multibuffer_product_scan::MultiBufferProductScan},
     record::{field::Value, record_page::Slot},
@@ -47,6 +51,11 @@ pub enum Scan {
     MergeJoinScan(MergeJoinScan),
     ChunkScan(ChunkScan),
     MultiBufferProductScan(MultiBufferProductScan),
+    LimitScan(LimitScan),
+    TopNScan(TopNScan),
+    UnionAllScan(UnionAllScan),
+    SetOperationScan(SetOperationScan),
+    WindowScan(WindowScan),
 }
 
 #[enum_dispatch(Scan)]
@@ -107,7 +116,9 @@ pub trait ScanControl {
 }
 
 pub mod extend_scan;
+pub mod limit_scan;
 pub mod product_scan;
 pub mod project_scan;
 pub mod select_scan;
 pub mod table_scan;
+pub mod union_all_scan;
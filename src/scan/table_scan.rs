@@ -35,7 +35,7 @@ impl TableScan {
             } else {
                 (BlockId::get_first_block(&file_name), false)
             };
-            let mut rp = RecordPage::new(tx.clone(), block, layout);
+            let mut rp = RecordPage::new(tx.clone(), block, layout)?;
             if is_new {
                 rp.format()?;
             }
@@ -74,7 +74,8 @@ impl TableScan {
 impl ScanControl for TableScan {
     fn before_first(&mut self) -> Result<(), TransactionError> {
         let block = BlockId::get_first_block(&self.file_name);
-        self.record_page.reset_block(block)?;
+        self.record_page.reset_block(block.clone())?;
+        self.tx.lock().unwrap().read_ahead(&block);
         self.current_slot = Slot::Start;
         Ok(())
     }
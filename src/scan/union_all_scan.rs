@@ -0,0 +1,84 @@
+use crate::{errors::TransactionError, record::field::Value};
+
+use super::{Scan, ScanControl};
+
+/// Returns all records of the first scan followed by all records of the
+/// second one.
+///
+/// Fields are matched by position: the record of the second scan is read
+/// through `fields2` but exposed under the names in `fields1`.
+pub struct UnionAllScan {
+    scan1: Box<Scan>,
+    scan2: Box<Scan>,
+    fields1: Vec<String>,
+    fields2: Vec<String>,
+    on_second: bool,
+}
+
+impl UnionAllScan {
+    pub fn new(scan1: Scan, scan2: Scan, fields1: Vec<String>, fields2: Vec<String>) -> Self {
+        UnionAllScan {
+            scan1: Box::new(scan1),
+            scan2: Box::new(scan2),
+            fields1,
+            fields2,
+            on_second: false,
+        }
+    }
+
+    fn second_field_name(&self, field_name: &str) -> &str {
+        match self.fields1.iter().position(|f| f == field_name) {
+            Some(index) => &self.fields2[index],
+            None => panic!("Field {} not found", field_name),
+        }
+    }
+}
+
+impl ScanControl for UnionAllScan {
+    fn before_first(&mut self) -> Result<(), TransactionError> {
+        self.on_second = false;
+        self.scan1.before_first()
+    }
+
+    fn next(&mut self) -> Result<bool, TransactionError> {
+        if !self.on_second {
+            if self.scan1.next()? {
+                return Ok(true);
+            }
+            self.on_second = true;
+            self.scan2.before_first()?;
+        }
+        self.scan2.next()
+    }
+
+    fn get_i32(&mut self, field_name: &str) -> Result<Option<i32>, TransactionError> {
+        if self.on_second {
+            let field_name = self.second_field_name(field_name).to_string();
+            self.scan2.get_i32(&field_name)
+        } else {
+            self.scan1.get_i32(field_name)
+        }
+    }
+
+    fn get_string(&mut self, field_name: &str) -> Result<Option<String>, TransactionError> {
+        if self.on_second {
+            let field_name = self.second_field_name(field_name).to_string();
+            self.scan2.get_string(&field_name)
+        } else {
+            self.scan1.get_string(field_name)
+        }
+    }
+
+    fn get_value(&mut self, field_name: &str) -> Result<Value, TransactionError> {
+        if self.on_second {
+            let field_name = self.second_field_name(field_name).to_string();
+            self.scan2.get_value(&field_name)
+        } else {
+            self.scan1.get_value(field_name)
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.fields1.iter().any(|f| f == field_name)
+    }
+}
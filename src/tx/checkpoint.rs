@@ -0,0 +1,135 @@
+use std::sync::Mutex;
+
+use crate::buffer::BufferManager;
+use crate::errors::TransactionError;
+use crate::log::{manager::LogManager, record::LogRecord};
+
+/// Writes a non-quiescent checkpoint, which neither waits for transactions
+/// nor writes buffers. It records the active transactions and the dirty page
+/// table, so recovery reads the log back only to the oldest record that
+/// either of them needs, and the log segments before it are truncated.
+///
+/// The latest LSN is taken before the dirty page table, as a block modified
+/// after it is in the table or has its first update logged after that LSN.
+pub fn write_nonquiescent_checkpoint(
+    log_manager: &Mutex<LogManager>,
+    buffer_manager: &BufferManager,
+    checkpoint_transaction_id: usize,
+) -> Result<(), TransactionError> {
+    let start_log_sequence_number = log_manager.lock().unwrap().get_latest_log_sequence_number();
+    let dirty_page_table = buffer_manager.get_dirty_page_table();
+    // The blocks written before are not in the table, so they must be on the
+    // disk before the log they need is truncated
+    buffer_manager.sync_written_blocks()?;
+
+    let mut log_manager = log_manager.lock().unwrap();
+    let max_record_size = log_manager.get_max_record_size();
+    let new_record = || {
+        LogRecord::NonQuiescentCheckpoint(
+            checkpoint_transaction_id,
+            start_log_sequence_number,
+            vec![],
+            vec![],
+        )
+    };
+    let active_transactions = log_manager.get_active_transactions();
+    let oldest_log_sequence_number = active_transactions
+        .iter()
+        .map(|&(_, log_sequence_number)| log_sequence_number)
+        .chain(dirty_page_table.iter().map(|&(_, lsn)| lsn))
+        .fold(start_log_sequence_number + 1, usize::min);
+    let mut records = vec![new_record()];
+    for active_transaction in active_transactions {
+        push_entry(&mut records, max_record_size, new_record, |record| {
+            if let LogRecord::NonQuiescentCheckpoint(_, _, active_transactions, _) = record {
+                active_transactions.push(active_transaction);
+            }
+        });
+    }
+    for dirty_page in dirty_page_table {
+        push_entry(&mut records, max_record_size, new_record, |record| {
+            if let LogRecord::NonQuiescentCheckpoint(_, _, _, dirty_blocks) = record {
+                dirty_blocks.push(dirty_page.clone());
+            }
+        });
+    }
+
+    let mut log_sequence_number = 0;
+    for record in records.iter() {
+        log_sequence_number = log_manager.append_record(record)?;
+    }
+    log_manager.flush(log_sequence_number)?;
+    log_manager.truncate(oldest_log_sequence_number)?;
+    Ok(())
+}
+
+// Adds an entry to the last record, or to a new record if the last one would
+// not fit in a log block.
+fn push_entry(
+    records: &mut Vec<LogRecord>,
+    max_record_size: usize,
+    new_record: impl Fn() -> LogRecord,
+    push: impl Fn(&mut LogRecord),
+) {
+    let last_record = records.last_mut().unwrap();
+    let mut record = last_record.clone();
+    push(&mut record);
+    if record.to_bytes().len() <= max_record_size {
+        *last_record = record;
+    } else {
+        let mut record = new_record();
+        push(&mut record);
+        records.push(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::SimpleDB;
+
+    use super::*;
+
+    #[test]
+    fn test_nonquiescent_checkpoint() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let db = SimpleDB::new(temp_dir, 256, 10)?;
+        let log_manager = db.log_manager.clone();
+        let mut tx = db.new_transaction()?;
+        let mut blocks = vec![];
+        for _ in 0..8 {
+            let block = db.file_manager.lock().unwrap().append_block("testfile")?;
+            tx.pin(&block)?;
+            tx.set_i32(&block, 0, 1, true)?;
+            blocks.push(block);
+        }
+        let first_log_sequence_number = log_manager
+            .lock()
+            .unwrap()
+            .get_first_log_sequence_number(tx.id)
+            .unwrap();
+
+        write_nonquiescent_checkpoint(&log_manager, &db.buffer_manager, 100)?;
+        let mut log_manager = log_manager.lock().unwrap();
+        let mut active_transactions = vec![];
+        let mut dirty_blocks = vec![];
+        let mut num_records = 0;
+        for log_record in log_manager.get_backward_iter()? {
+            match log_record? {
+                LogRecord::NonQuiescentCheckpoint(100, _, transactions, blocks) => {
+                    active_transactions.extend(transactions);
+                    dirty_blocks.extend(blocks.into_iter().map(|(block, _)| block));
+                    num_records += 1;
+                }
+                _ => break,
+            }
+        }
+        // The dirty page table does not fit in one log block
+        assert!(num_records > 1);
+        assert_eq!(
+            active_transactions,
+            vec![(tx.id, first_log_sequence_number)]
+        );
+        assert!(blocks.iter().all(|block| dirty_blocks.contains(block)));
+        Ok(())
+    }
+}
@@ -1,2 +1,3 @@
+pub mod checkpoint;
 pub mod concurrency;
 pub mod transaction;
@@ -1,111 +1,172 @@
 use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
 use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex};
 
-use crate::buffer::BufferManager;
+use crate::buffer::{Buffer, BufferManager, PAGE_HEADER_SIZE};
 use crate::errors::TransactionError;
-use crate::file::{BlockId, FileManager};
+use crate::file::{BlockId, FileManager, BLOCK_CHECKSUM_SIZE};
+use crate::log::group_commit::GroupCommit;
 use crate::log::manager::LogManager;
 use crate::log::record::LogRecord;
+use crate::page::Page;
 
 use super::concurrency::{ConcurrencyManager, LockTable};
 
 pub struct Transaction {
     file_manager: Arc<Mutex<FileManager>>,
-    buffer_manager: Arc<Mutex<BufferManager>>,
+    buffer_manager: Arc<BufferManager>,
     concurrency_manager: ConcurrencyManager,
     log_manager: Arc<Mutex<LogManager>>,
+    group_commit: Arc<GroupCommit>,
     pub id: usize,
     block_to_buffer_map: HashMap<BlockId, usize>,
     pinned_blocks: Vec<BlockId>,
     block_size: usize,
+    // Names of the active savepoints, oldest first
+    savepoints: Vec<String>,
 }
 
 static TRANSACTION_ID: AtomicUsize = AtomicUsize::new(0);
 
+pub(crate) fn next_transaction_id() -> usize {
+    TRANSACTION_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+}
+
 impl Transaction {
     pub fn new(
         file_manager: Arc<Mutex<FileManager>>,
         log_manager: Arc<Mutex<LogManager>>,
-        buffer_manager: Arc<Mutex<BufferManager>>,
+        group_commit: Arc<GroupCommit>,
+        buffer_manager: Arc<BufferManager>,
         lock_table: Arc<Mutex<LockTable>>,
     ) -> Result<Self, TransactionError> {
         let concurrency_manager = ConcurrencyManager::new(lock_table.clone());
-        let id = TRANSACTION_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        let block_size = file_manager.lock().unwrap().block_size;
+        let id = next_transaction_id();
+        // The page header and the checksum are hidden from the users of the
+        // transaction
+        let block_size =
+            file_manager.lock().unwrap().block_size - PAGE_HEADER_SIZE - BLOCK_CHECKSUM_SIZE;
 
         Ok(Transaction {
             file_manager,
             buffer_manager,
             concurrency_manager,
             log_manager,
+            group_commit,
             block_to_buffer_map: HashMap::new(),
             pinned_blocks: Vec::new(),
             id,
             block_size,
+            savepoints: Vec::new(),
         })
     }
 
+    // Only the log is forced; the modified pages are written later, as they
+    // can be redone from the log. The log is forced together with other
+    // committing transactions.
     pub fn commit(&mut self) -> Result<(), TransactionError> {
-        {
-            let buffer_manager = self.buffer_manager.lock().unwrap();
-            buffer_manager.flush_all(self.id)?;
-        }
-
-        {
-            let mut log_manager = self.log_manager.lock().unwrap();
-            let log_sequence_number = log_manager.append_record(&LogRecord::Commit(self.id))?;
-            log_manager.flush(log_sequence_number)?;
-        }
+        let log_sequence_number = self
+            .log_manager
+            .lock()
+            .unwrap()
+            .append_record(&LogRecord::Commit(self.id))?;
+        self.group_commit.flush(log_sequence_number)?;
 
         self.concurrency_manager.release();
         self.unpin_all();
+        self.savepoints.clear();
         Ok(())
     }
 
     pub fn rollback(&mut self) -> Result<(), TransactionError> {
         self.do_rollback()?;
 
-        {
-            let buffer_manager = self.buffer_manager.lock().unwrap();
-            buffer_manager.flush_all(self.id)?;
-        }
-
-        {
-            let mut log_manager = self.log_manager.lock().unwrap();
-            let log_sequence_number = log_manager.append_record(&LogRecord::Rollback(self.id))?;
-            log_manager.flush(log_sequence_number)?;
-        }
+        let log_sequence_number = self
+            .log_manager
+            .lock()
+            .unwrap()
+            .append_record(&LogRecord::Rollback(self.id))?;
+        self.group_commit.flush(log_sequence_number)?;
         // self.recovery_manager.rollback(self.id)?;
         self.concurrency_manager.release();
         self.unpin_all();
+        self.savepoints.clear();
         Ok(())
     }
 
-    // "Quiescent Checkpointing" is currently implemented
-    // This method is not thread-safe.
-    // TODO: understand why we need to flush all buffers twice.
-    pub fn recover(&mut self) -> Result<(), TransactionError> {
-        {
-            let buffer_manager = self.buffer_manager.lock().unwrap();
-            buffer_manager.flush_all(self.id)?;
+    // A savepoint with the name of an active one replaces it.
+    pub fn savepoint(&mut self, name: &str) -> Result<(), TransactionError> {
+        let mut log_manager = self.log_manager.lock().unwrap();
+        log_manager.append_record(&LogRecord::Savepoint(self.id, name.to_string()))?;
+        self.savepoints.retain(|savepoint| savepoint != name);
+        self.savepoints.push(name.to_string());
+        Ok(())
+    }
+
+    // Undoes the updates made since the savepoint, which stays active while
+    // the savepoints created after it are released.
+    pub fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), TransactionError> {
+        let position = self.find_savepoint(name)?;
+        self.savepoints.truncate(position + 1);
+
+        let mut log_manager = self.log_manager.lock().unwrap();
+        let log_iter = log_manager.get_backward_iter()?;
+        let mut log_records = vec![];
+        for log_record in log_iter {
+            let log_record = log_record?;
+            if log_record.get_transaction_id() != self.id {
+                continue;
+            }
+            if let LogRecord::Savepoint(_, savepoint) = &log_record {
+                if savepoint == name {
+                    break;
+                }
+            }
+            log_records.push(log_record);
         }
+        drop(log_manager);
+        for log_record in log_records.iter() {
+            self.undo_update(log_record, true)?;
+        }
+        Ok(())
+    }
+
+    // Releases the savepoint and the savepoints created after it, keeping
+    // their updates.
+    pub fn release_savepoint(&mut self, name: &str) -> Result<(), TransactionError> {
+        let position = self.find_savepoint(name)?;
+        self.savepoints.truncate(position);
+        Ok(())
+    }
 
+    fn find_savepoint(&self, name: &str) -> Result<usize, TransactionError> {
+        self.savepoints
+            .iter()
+            .position(|savepoint| savepoint == name)
+            .ok_or_else(|| TransactionError::UnknownSavepoint(name.to_string()))
+    }
+
+    // Repeats the history logged since the last checkpoint and then undoes
+    // the transactions that did not finish. A non-quiescent checkpoint tells
+    // how far back the log is read. The pages are written before the
+    // quiescent checkpoint that ends the recovery, before which the log is
+    // truncated.
+    // This method is not thread-safe.
+    pub fn recover(&mut self) -> Result<(), TransactionError> {
         self.do_recover()?;
-        {
-            let buffer_manager = self.buffer_manager.lock().unwrap();
-            buffer_manager.flush_all(self.id)?;
-        }
+        self.buffer_manager.flush_all(self.id)?;
+        self.file_manager.lock().unwrap().sync_written_files()?;
 
         let mut log_manager = self.log_manager.lock().unwrap();
         let log_sequence_number = log_manager.append_record(&LogRecord::Checkpoint(self.id))?;
         log_manager.flush(log_sequence_number)?;
+        log_manager.truncate(log_sequence_number)?;
         Ok(())
     }
 
     pub fn pin(&mut self, block: &BlockId) -> Result<(), TransactionError> {
-        let mut buffer_manager = self.buffer_manager.lock().unwrap();
-        let buffer_index = buffer_manager.pin(block)?;
+        let buffer_index = self.buffer_manager.pin(block)?;
 
         self.block_to_buffer_map.insert(block.clone(), buffer_index);
         self.pinned_blocks.push(block.clone());
@@ -113,9 +174,8 @@ impl Transaction {
     }
 
     pub fn unpin(&mut self, block: &BlockId) {
-        let mut buffer_manager = self.buffer_manager.lock().unwrap();
-        let &buffer_index = self.block_to_buffer_map.get(&block).unwrap();
-        buffer_manager.unpin(buffer_index);
+        let &buffer_index = self.block_to_buffer_map.get(block).unwrap();
+        self.buffer_manager.unpin(buffer_index);
 
         let first_index = self.pinned_blocks.iter().position(|b| b == block).unwrap();
         self.pinned_blocks.remove(first_index);
@@ -129,10 +189,8 @@ impl Transaction {
     pub fn get_i32(&mut self, block: &BlockId, offset: usize) -> Result<i32, TransactionError> {
         self.concurrency_manager.lock_shared(block)?;
         let &buffer_index = self.block_to_buffer_map.get(&block).unwrap();
-        let buffer_manager = self.buffer_manager.lock().unwrap();
-
-        let buffer_lock = buffer_manager.buffers.lock().unwrap();
-        Ok(buffer_lock[buffer_index].page.get_i32(offset))
+        let buffer = self.buffer_manager.lock_buffer(buffer_index);
+        Ok(buffer.page.get_i32(PAGE_HEADER_SIZE + offset))
     }
 
     // Block with block_id must be pinned before calling this method.
@@ -145,22 +203,22 @@ impl Transaction {
     ) -> Result<usize, TransactionError> {
         self.concurrency_manager.lock_exclusive(block)?;
         let &buffer_index = self.block_to_buffer_map.get(&block).unwrap();
-        let buffer_manager = self.buffer_manager.lock().unwrap();
-        let buffer = &mut buffer_manager.buffers.lock().unwrap()[buffer_index];
+        let mut buffer = self.buffer_manager.lock_buffer(buffer_index);
         let log_sequence_number = if is_log_needed {
             let block = buffer
                 .block
                 .clone()
                 .expect("buffer must be assigned to a block");
-            let old_value = buffer.page.get_i32(offset);
+            let old_value = buffer.page.get_i32(PAGE_HEADER_SIZE + offset);
             let record = LogRecord::SetI32(self.id, block, offset, old_value, value);
 
             let mut log_manager = self.log_manager.lock().unwrap();
+            self.log_page_image(&mut buffer, &mut log_manager)?;
             log_manager.append_record(&record)?
         } else {
             0
         };
-        let written_length = buffer.page.set_i32(offset, value);
+        let written_length = buffer.page.set_i32(PAGE_HEADER_SIZE + offset, value);
         buffer.set_modified(self.id, log_sequence_number);
         Ok(written_length)
     }
@@ -173,11 +231,10 @@ impl Transaction {
     ) -> Result<String, TransactionError> {
         self.concurrency_manager.lock_shared(block)?;
         let &buffer_index = self.block_to_buffer_map.get(&block).unwrap();
-        let buffer_manager = self.buffer_manager.lock().unwrap();
-        let buffer_lock = buffer_manager.buffers.lock().unwrap();
-        Ok(buffer_lock[buffer_index]
+        let buffer = self.buffer_manager.lock_buffer(buffer_index);
+        Ok(buffer
             .page
-            .get_string(offset)
+            .get_string(PAGE_HEADER_SIZE + offset)
             .0
             .to_string())
     }
@@ -192,14 +249,13 @@ impl Transaction {
     ) -> Result<usize, TransactionError> {
         self.concurrency_manager.lock_exclusive(block)?;
         let &buffer_index = self.block_to_buffer_map.get(&block).unwrap();
-        let buffer_manager = self.buffer_manager.lock().unwrap();
-        let buffer = &mut buffer_manager.buffers.lock().unwrap()[buffer_index];
+        let mut buffer = self.buffer_manager.lock_buffer(buffer_index);
         let log_sequence_number = if is_log_needed {
             let block = buffer
                 .block
                 .clone()
                 .expect("buffer must be assigned to a block");
-            let (old_value, _) = buffer.page.get_string(offset);
+            let (old_value, _) = buffer.page.get_string(PAGE_HEADER_SIZE + offset);
             let record = LogRecord::SetString(
                 self.id,
                 block,
@@ -208,15 +264,54 @@ impl Transaction {
                 value.to_string(),
             );
             let mut log_manager = self.log_manager.lock().unwrap();
+            self.log_page_image(&mut buffer, &mut log_manager)?;
             log_manager.append_record(&record)?
         } else {
             0
         };
-        let written_length = buffer.page.set_string(offset, value);
+        let written_length = buffer.page.set_string(PAGE_HEADER_SIZE + offset, value);
         buffer.set_modified(self.id, log_sequence_number);
         Ok(written_length)
     }
 
+    // Logs the page before its first logged update since it was written, in
+    // parts that fit in log blocks, so that recovery can rebuild the block
+    // if a crash tears the next write.
+    fn log_page_image(
+        &self,
+        buffer: &mut Buffer,
+        log_manager: &mut LogManager,
+    ) -> Result<(), TransactionError> {
+        if buffer.has_logged_updates() {
+            return Ok(());
+        }
+        let block = buffer
+            .block
+            .clone()
+            .expect("buffer must be assigned to a block");
+        let record_size = LogRecord::PageImage(self.id, block.clone(), 0, vec![])
+            .to_bytes()
+            .len();
+        let part_size = log_manager
+            .get_max_record_size()
+            .saturating_sub(record_size)
+            .max(1);
+        let contents =
+            &buffer.page.as_bytes()[PAGE_HEADER_SIZE..PAGE_HEADER_SIZE + self.block_size];
+        let records: Vec<_> = contents
+            .chunks(part_size)
+            .enumerate()
+            .map(|(i, part)| {
+                LogRecord::PageImage(self.id, block.clone(), i * part_size, part.to_vec())
+            })
+            .collect();
+        for record in records.iter() {
+            let log_sequence_number = log_manager.append_record(record)?;
+            buffer.set_modified(self.id, log_sequence_number);
+        }
+        Ok(())
+    }
+
     pub fn append_block(&mut self, file_name: &str) -> Result<BlockId, TransactionError> {
         let dummy = BlockId::create_dummy(file_name);
         self.concurrency_manager.lock_exclusive(&dummy)?;
@@ -229,52 +324,77 @@ impl Transaction {
     }
 
     pub fn is_last_block(&mut self, block: &BlockId) -> Result<bool, TransactionError> {
-        let dummy = BlockId::create_dummy(&block.file_name);
+        let dummy = BlockId::create_dummy(&block.file_name());
         self.concurrency_manager.lock_shared(&dummy)?;
-        Ok(self.get_num_blocks(&block.file_name)? == block.block_slot + 1)
+        Ok(self.get_num_blocks(&block.file_name())? == block.block_slot + 1)
     }
 
     pub fn get_num_blocks(&mut self, file_name: &str) -> Result<usize, TransactionError> {
         let dummy = BlockId::create_dummy(file_name);
         self.concurrency_manager.lock_shared(&dummy)?;
-        Ok(self.file_manager.lock().unwrap().get_num_blocks(file_name))
+        Ok(self
+            .file_manager
+            .lock()
+            .unwrap()
+            .get_num_blocks(file_name)?)
+    }
+
+    // Hints that the blocks following the block are going to be read
+    pub fn read_ahead(&self, block: &BlockId) {
+        self.buffer_manager.read_ahead(block);
     }
 
     pub fn get_num_available_buffers(&self) -> usize {
-        let buffer_manager = self.buffer_manager.lock().unwrap();
-        buffer_manager.get_num_availables()
+        self.buffer_manager.get_num_availables()
     }
 
     fn do_rollback(&mut self) -> Result<(), TransactionError> {
         let mut log_manager = self.log_manager.lock().unwrap();
-        let log_iter = log_manager.get_backward_iter()?;
+        let Some(first_log_sequence_number) = log_manager.get_first_log_sequence_number(self.id)
+        else {
+            // The transaction logged nothing
+            return Ok(());
+        };
+        let mut log_iter = log_manager.get_backward_iter()?;
         let mut log_records = vec![];
 
-        for log_record in log_iter {
-            if log_record.get_transaction_id() == self.id {
-                log_records.push(log_record.clone());
+        while let Some((log_sequence_number, log_record)) =
+            log_iter.next_with_log_sequence_number()?
+        {
+            if log_sequence_number < first_log_sequence_number {
+                break;
             }
-            match log_record {
-                LogRecord::Start(_) | LogRecord::Checkpoint(_) => {
-                    break;
-                }
-                _ => {}
+            if log_record.get_transaction_id() == self.id {
+                log_records.push(log_record);
             }
         }
+        drop(log_iter);
         drop(log_manager);
+        // The undo writes are logged, so a crash after the rollback does not
+        // need to write the pages first.
         for log_record in log_records.iter() {
-            self.undo_update(&log_record)?;
+            self.undo_update(&log_record, true)?;
         }
         Ok(())
     }
 
     fn do_recover(&mut self) -> Result<(), TransactionError> {
         let mut log_manager = self.log_manager.lock().unwrap();
-        let log_iter = log_manager.get_backward_iter()?;
+        let mut log_iter = log_manager.get_backward_iter()?;
         let mut finshed_transactions = HashSet::new();
+        // Records since the last checkpoint, latest first
         let mut log_records = vec![];
+        // The last non-quiescent checkpoint and the oldest LSN it needs
+        let mut nonquiescent_checkpoint: Option<(usize, usize)> = None;
 
-        for log_record in log_iter {
+        while let Some((log_sequence_number, log_record)) =
+            log_iter.next_with_log_sequence_number()?
+        {
+            if let Some((_, oldest_log_sequence_number)) = nonquiescent_checkpoint {
+                if log_sequence_number < oldest_log_sequence_number {
+                    break;
+                }
+            }
             match log_record {
                 LogRecord::Commit(transaction_id) | LogRecord::Rollback(transaction_id) => {
                     finshed_transactions.insert(transaction_id);
@@ -282,32 +402,139 @@ impl Transaction {
                 LogRecord::Checkpoint(_) => {
                     break;
                 }
+                LogRecord::NonQuiescentCheckpoint(
+                    checkpoint_transaction_id,
+                    start_log_sequence_number,
+                    active_transactions,
+                    dirty_blocks,
+                ) => {
+                    // Older checkpoints are ignored, while the records of the
+                    // last one are all read as they are adjacent
+                    let is_last_checkpoint =
+                        nonquiescent_checkpoint.is_none_or(|(transaction_id, _)| {
+                            transaction_id == checkpoint_transaction_id
+                        });
+                    if is_last_checkpoint {
+                        let oldest_log_sequence_number = active_transactions
+                            .iter()
+                            .map(|&(_, first_log_sequence_number)| first_log_sequence_number)
+                            .chain(dirty_blocks.iter().map(|&(_, lsn)| lsn))
+                            .chain(nonquiescent_checkpoint.map(|(_, lsn)| lsn))
+                            .fold(start_log_sequence_number + 1, usize::min);
+                        nonquiescent_checkpoint =
+                            Some((checkpoint_transaction_id, oldest_log_sequence_number));
+                    }
+                }
                 _ => {
-                    if !finshed_transactions.contains(&log_record.get_transaction_id()) {
-                        log_records.push(log_record);
+                    // Temporary files are deleted on startup
+                    let is_temporary = log_record
+                        .get_block()
+                        .is_some_and(|block| block.file_name().starts_with("temp_"));
+                    if !is_temporary {
+                        log_records.push((log_sequence_number, log_record));
                     }
                 }
             }
         }
+        drop(log_iter);
         drop(log_manager);
-        for log_record in log_records.iter() {
-            self.undo_update(&log_record)?;
+        let imaged_blocks: HashSet<_> = log_records
+            .iter()
+            .filter_map(|(_, log_record)| match log_record {
+                LogRecord::PageImage(_, block, _, _) => Some(block),
+                _ => None,
+            })
+            .collect();
+        for block in imaged_blocks {
+            self.reset_torn_block(block)?;
+        }
+        for (log_sequence_number, log_record) in log_records.iter().rev() {
+            self.redo_update(*log_sequence_number, log_record)?;
+        }
+        for (_, log_record) in log_records.iter() {
+            if !finshed_transactions.contains(&log_record.get_transaction_id()) {
+                self.undo_update(log_record, false)?;
+            }
         }
         Ok(())
     }
 
-    fn undo_update(&mut self, log_record: &LogRecord) -> Result<(), TransactionError> {
+    // Empties the block if a crash tore it or lost its unsynced append, so
+    // that it is rebuilt from its image in the log. A torn block without an
+    // image fails the recovery when it is read.
+    fn reset_torn_block(&mut self, block: &BlockId) -> Result<(), TransactionError> {
+        let mut file_manager = self.file_manager.lock().unwrap();
+        let block_size = file_manager.block_size;
+        match file_manager.read(block, &mut Page::new(block_size)) {
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    ErrorKind::InvalidData | ErrorKind::UnexpectedEof
+                ) =>
+            {
+                file_manager.write(block, &Page::new(block_size))?;
+            }
+            result => result?,
+        }
+        Ok(())
+    }
+
+    // Applies the update again unless the page already holds it, which the
+    // page LSN tells.
+    fn redo_update(
+        &mut self,
+        log_sequence_number: usize,
+        log_record: &LogRecord,
+    ) -> Result<(), TransactionError> {
+        let Some(block) = log_record.get_block() else {
+            return Ok(());
+        };
+        self.pin(block)?;
+        let &buffer_index = self.block_to_buffer_map.get(block).unwrap();
+        {
+            let mut buffer = self.buffer_manager.lock_buffer(buffer_index);
+            if buffer.get_page_lsn() < log_sequence_number {
+                match log_record {
+                    LogRecord::SetI32(_, _, offset, _, new_value) => {
+                        buffer.page.set_i32(PAGE_HEADER_SIZE + offset, *new_value);
+                    }
+                    LogRecord::SetString(_, _, offset, _, new_value) => {
+                        buffer.page.set_string(PAGE_HEADER_SIZE + offset, new_value);
+                    }
+                    LogRecord::PageImage(_, _, offset, contents) => {
+                        let start = PAGE_HEADER_SIZE + offset;
+                        buffer.page.as_mut_bytes()[start..start + contents.len()]
+                            .copy_from_slice(contents);
+                    }
+                    _ => unreachable!(),
+                }
+                buffer.set_modified(self.id, 0);
+                buffer.set_page_lsn(log_sequence_number);
+            }
+        }
+        self.unpin(block);
+        Ok(())
+    }
+
+    fn undo_update(
+        &mut self,
+        log_record: &LogRecord,
+        is_log_needed: bool,
+    ) -> Result<(), TransactionError> {
         match log_record {
             LogRecord::SetI32(_, block, offset, old_value, _) => {
                 self.pin(block)?;
-                self.set_i32(block, *offset, *old_value, false)?;
+                self.set_i32(block, *offset, *old_value, is_log_needed)?;
                 self.unpin(block);
             }
             LogRecord::SetString(_, block, offset, old_value, _) => {
                 self.pin(block)?;
-                self.set_string(block, *offset, old_value, false)?;
+                self.set_string(block, *offset, old_value, is_log_needed)?;
                 self.unpin(block);
             }
+            // Savepoints only mark a position in the log, and page images
+            // are only redone
+            LogRecord::Savepoint(_, _) | LogRecord::PageImage(_, _, _, _) => {}
             _ => {
                 panic!("unexpected log record: {:?}", log_record);
             }
@@ -316,10 +543,9 @@ impl Transaction {
     }
 
     fn unpin_all(&mut self) {
-        let mut buffer_manager = self.buffer_manager.lock().unwrap();
         for block_index in self.pinned_blocks.iter() {
             let &buffer_index = self.block_to_buffer_map.get(block_index).unwrap();
-            buffer_manager.unpin(buffer_index);
+            self.buffer_manager.unpin(buffer_index);
         }
         self.pinned_blocks.clear();
         self.block_to_buffer_map.clear();
@@ -328,7 +554,15 @@ impl Transaction {
 
 #[cfg(test)]
 mod tests {
-    use crate::{db::SimpleDB, errors::TransactionError};
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        db::{SimpleDB, SimpleDBOptions},
+        file::Durability,
+        page::Page,
+        tx::checkpoint::write_nonquiescent_checkpoint,
+    };
 
     #[test]
     fn test_transaction() -> Result<(), TransactionError> {
@@ -369,4 +603,196 @@ mod tests {
         tx4.commit()?;
         Ok(())
     }
+
+    #[test]
+    fn test_savepoint() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let db = SimpleDB::new(temp_dir, 256, 3)?;
+        let block = db.file_manager.lock().unwrap().append_block("testfile")?;
+
+        let mut tx1 = db.new_transaction()?;
+        tx1.pin(&block)?;
+        tx1.set_i32(&block, 80, 1, true)?;
+        tx1.savepoint("a")?;
+        tx1.set_i32(&block, 80, 2, true)?;
+        tx1.set_string(&block, 40, "two", true)?;
+        tx1.savepoint("b")?;
+        tx1.set_i32(&block, 80, 3, true)?;
+
+        tx1.rollback_to_savepoint("b")?;
+        assert_eq!(tx1.get_i32(&block, 80)?, 2);
+        tx1.rollback_to_savepoint("a")?;
+        assert_eq!(tx1.get_i32(&block, 80)?, 1);
+        assert_eq!(tx1.get_string(&block, 40)?, "");
+        // Rolling back to "a" released "b" but kept "a"
+        assert!(matches!(
+            tx1.rollback_to_savepoint("b"),
+            Err(TransactionError::UnknownSavepoint(_))
+        ));
+        tx1.set_i32(&block, 80, 4, true)?;
+        tx1.release_savepoint("a")?;
+        assert!(tx1.rollback_to_savepoint("a").is_err());
+        tx1.commit()?;
+
+        let mut tx2 = db.new_transaction()?;
+        tx2.pin(&block)?;
+        assert_eq!(tx2.get_i32(&block, 80)?, 4);
+        tx2.savepoint("a")?;
+        tx2.set_i32(&block, 80, 5, true)?;
+        // The whole transaction is undone past its savepoints
+        tx2.rollback()?;
+
+        let mut tx3 = db.new_transaction()?;
+        tx3.pin(&block)?;
+        assert_eq!(tx3.get_i32(&block, 80)?, 4);
+        tx3.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_after_crash() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block = BlockId::new("testfile", 0);
+        let other_block = BlockId::new("testfile", 1);
+        {
+            let db = SimpleDB::new(temp_dir.clone(), 256, 3)?;
+            db.file_manager.lock().unwrap().append_block("testfile")?;
+            db.file_manager.lock().unwrap().append_block("testfile")?;
+
+            let mut tx1 = db.new_transaction()?;
+            tx1.pin(&block)?;
+            tx1.set_i32(&block, 80, 1, true)?;
+            tx1.commit()?;
+
+            // The page of an unfinished transaction is written
+            let mut tx2 = db.new_transaction()?;
+            tx2.pin(&block)?;
+            tx2.set_string(&block, 40, "two", true)?;
+            db.buffer_manager.flush_all_modified()?;
+
+            let mut tx3 = db.new_transaction()?;
+            tx3.pin(&other_block)?;
+            tx3.set_i32(&other_block, 120, 3, true)?;
+            tx3.commit()?;
+
+            // The database stops without writing the page again
+        }
+
+        let mut page = Page::new(256);
+        FileManager::new(temp_dir.clone(), 256).read(&other_block, &mut page)?;
+        assert_eq!(page.get_i32(PAGE_HEADER_SIZE + 120), 0);
+
+        let db = SimpleDB::new(temp_dir, 256, 3)?;
+        let mut tx = db.new_transaction()?;
+        tx.pin(&block)?;
+        assert_eq!(tx.get_i32(&block, 80)?, 1);
+        assert_eq!(tx.get_string(&block, 40)?, "");
+        tx.pin(&other_block)?;
+        assert_eq!(tx.get_i32(&other_block, 120)?, 3);
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn commit_and_lose_power(
+        directory: PathBuf,
+        durability: Durability,
+    ) -> Result<(), TransactionError> {
+        let options = SimpleDBOptions {
+            durability,
+            ..Default::default()
+        };
+        let db = SimpleDB::with_options(directory, 256, 3, options)?;
+        let block = db.file_manager.lock().unwrap().append_block("testfile")?;
+
+        let mut tx1 = db.new_transaction()?;
+        tx1.pin(&block)?;
+        tx1.set_i32(&block, 80, 1, true)?;
+        tx1.commit()?;
+
+        // The page of an unfinished transaction is written but not synced
+        let mut tx2 = db.new_transaction()?;
+        tx2.pin(&block)?;
+        tx2.set_string(&block, 40, "two", true)?;
+        db.buffer_manager.flush_all_modified()?;
+
+        db.file_manager.lock().unwrap().drop_unsynced_writes()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_after_power_loss() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        commit_and_lose_power(temp_dir.clone(), Durability::Normal)?;
+
+        let db = SimpleDB::new(temp_dir, 256, 3)?;
+        let block = BlockId::new("testfile", 0);
+        let mut tx = db.new_transaction()?;
+        tx.pin(&block)?;
+        assert_eq!(tx.get_i32(&block, 80)?, 1);
+        assert_eq!(tx.get_string(&block, 40)?, "");
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_power_loss_without_durability() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        commit_and_lose_power(temp_dir.clone(), Durability::Off)?;
+
+        // Neither the log nor the file survive
+        let mut file_manager = FileManager::new(temp_dir, 256);
+        assert_eq!(file_manager.get_num_blocks("testfile")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_after_nonquiescent_checkpoint() -> Result<(), TransactionError> {
+        let temp_dir = tempfile::tempdir().unwrap().into_path().join("directory");
+        let block = BlockId::new("testfile", 0);
+        let other_block = BlockId::new("testfile", 1);
+        {
+            let db = SimpleDB::new(temp_dir.clone(), 256, 3)?;
+            db.file_manager.lock().unwrap().append_block("testfile")?;
+            db.file_manager.lock().unwrap().append_block("testfile")?;
+
+            let mut tx1 = db.new_transaction()?;
+            tx1.pin(&block)?;
+            tx1.set_i32(&block, 80, 1, true)?;
+            tx1.commit()?;
+
+            let mut tx2 = db.new_transaction()?;
+            tx2.pin(&other_block)?;
+            tx2.set_i32(&other_block, 80, 2, true)?;
+            write_nonquiescent_checkpoint(&db.log_manager, &db.buffer_manager, 100)?;
+            tx2.set_i32(&other_block, 120, 2, true)?;
+            // The rollback reads the log back past the checkpoint
+            tx2.rollback()?;
+
+            // tx3 is active at the checkpoint and does not finish
+            let mut tx3 = db.new_transaction()?;
+            tx3.pin(&other_block)?;
+            assert_eq!(tx3.get_i32(&other_block, 80)?, 0);
+            tx3.set_i32(&other_block, 40, 3, true)?;
+            write_nonquiescent_checkpoint(&db.log_manager, &db.buffer_manager, 101)?;
+            tx3.set_i32(&other_block, 160, 3, true)?;
+            db.buffer_manager.flush_all_modified()?;
+
+            let mut tx4 = db.new_transaction()?;
+            tx4.pin(&block)?;
+            tx4.set_i32(&block, 120, 4, true)?;
+            tx4.commit()?;
+        }
+
+        let db = SimpleDB::new(temp_dir, 256, 3)?;
+        let mut tx = db.new_transaction()?;
+        tx.pin(&block)?;
+        tx.pin(&other_block)?;
+        assert_eq!(tx.get_i32(&block, 80)?, 1);
+        assert_eq!(tx.get_i32(&block, 120)?, 4);
+        assert_eq!(tx.get_i32(&other_block, 40)?, 0);
+        assert_eq!(tx.get_i32(&other_block, 120)?, 0);
+        assert_eq!(tx.get_i32(&other_block, 160)?, 0);
+        tx.commit()?;
+        Ok(())
+    }
 }